@@ -0,0 +1,36 @@
+use crate::audit::audit_log::{AuditEntry, AuditLog, AuditLogError, AuditQuery};
+use log::error;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tauri::State;
+
+#[tauri::command]
+pub fn list_audit_entries(
+    audit_log: State<'_, Arc<AuditLog>>,
+    query: AuditQuery,
+) -> Result<Vec<AuditEntry>, AuditCommandError> {
+    Ok(audit_log.entries(&query)?)
+}
+
+// The JSON lines are saved by the frontend, like every other export.
+#[tauri::command]
+pub fn export_audit_log(
+    audit_log: State<'_, Arc<AuditLog>>,
+    query: AuditQuery,
+) -> Result<String, AuditCommandError> {
+    Ok(audit_log.export_json_lines(&query)?)
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AuditCommandError {
+    message: String,
+}
+
+impl From<AuditLogError> for AuditCommandError {
+    fn from(error: AuditLogError) -> Self {
+        error!("An audit log error occurred: {error}");
+        AuditCommandError {
+            message: "The audit log could not be read".to_string(),
+        }
+    }
+}
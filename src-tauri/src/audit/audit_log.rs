@@ -0,0 +1,392 @@
+use crate::cloudflare::kv::{KvPairsDeleteResult, KvPairsWriteResult};
+use crate::environment::environment_labels::Environment;
+use crate::session::session_registry::SessionProfile;
+use chrono::{DateTime, Utc};
+use log::{error, warn};
+use ring::digest::{SHA256, digest};
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::fmt;
+use std::fmt::{Display, Formatter};
+use std::fs;
+use std::fs::OpenOptions;
+use std::io::{ErrorKind, Write};
+use std::path::PathBuf;
+use std::sync::{Mutex, MutexGuard};
+use uuid::Uuid;
+
+pub const AUDIT_LOG_FILE_NAME: &str = "audit-log.jsonl";
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum AuditOperation {
+    NamespaceCreate,
+    NamespaceRename,
+    NamespaceDelete,
+    NamespaceUnprotect,
+    KeyCreate,
+    KeyWrite,
+    KeyDelete,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AuditedKey {
+    pub key: String,
+    pub value_hash: Option<String>,
+}
+
+impl AuditedKey {
+    pub fn new(key: &str, value: Option<&[u8]>) -> Self {
+        Self {
+            key: key.to_string(),
+            value_hash: value.map(value_hash),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "status")]
+pub enum AuditOutcome {
+    Succeeded,
+    Completed {
+        successful_key_count: u32,
+        unsuccessful_keys: Vec<String>,
+    },
+    Failed {
+        error: String,
+    },
+}
+
+impl AuditOutcome {
+    pub fn of<T, E: Display>(result: &Result<T, E>, completed: impl FnOnce(&T) -> Self) -> Self {
+        match result {
+            Ok(value) => completed(value),
+            Err(error) => AuditOutcome::Failed {
+                error: error.to_string(),
+            },
+        }
+    }
+}
+
+impl From<&KvPairsWriteResult> for AuditOutcome {
+    fn from(result: &KvPairsWriteResult) -> Self {
+        AuditOutcome::Completed {
+            successful_key_count: result.successful_key_count,
+            unsuccessful_keys: result.unsuccessful_keys.clone(),
+        }
+    }
+}
+
+impl From<&KvPairsDeleteResult> for AuditOutcome {
+    fn from(result: &KvPairsDeleteResult) -> Self {
+        AuditOutcome::Completed {
+            successful_key_count: result.successful_key_count,
+            unsuccessful_keys: result.unsuccessful_keys.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AuditEntry {
+    pub id: String,
+    pub timestamp: DateTime<Utc>,
+    pub profile: Option<SessionProfile>,
+    pub operation: AuditOperation,
+    pub account_id: String,
+    pub namespace_id: Option<String>,
+    pub namespace_title: Option<String>,
+
+    #[serde(default)]
+    pub environment: Option<Environment>,
+    pub keys: Vec<AuditedKey>,
+    pub outcome: AuditOutcome,
+}
+
+impl AuditEntry {
+    pub fn new(
+        profile: Option<SessionProfile>,
+        operation: AuditOperation,
+        account_id: &str,
+        namespace_id: Option<&str>,
+    ) -> Self {
+        Self {
+            id: Uuid::new_v4().to_string(),
+            timestamp: Utc::now(),
+            profile,
+            operation,
+            account_id: account_id.to_string(),
+            namespace_id: namespace_id.map(str::to_string),
+            namespace_title: None,
+            environment: None,
+            keys: vec![],
+            outcome: AuditOutcome::Succeeded,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct AuditQuery {
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+    pub account_id: Option<String>,
+    pub namespace_id: Option<String>,
+    pub key: Option<String>,
+    pub limit: Option<usize>,
+}
+
+impl AuditQuery {
+    fn matches(&self, entry: &AuditEntry) -> bool {
+        self.from.is_none_or(|from| entry.timestamp >= from)
+            && self.to.is_none_or(|to| entry.timestamp <= to)
+            && self
+                .account_id
+                .as_ref()
+                .is_none_or(|account_id| &entry.account_id == account_id)
+            && self
+                .namespace_id
+                .as_ref()
+                .is_none_or(|namespace_id| entry.namespace_id.as_ref() == Some(namespace_id))
+            && self
+                .key
+                .as_ref()
+                .is_none_or(|key| entry.keys.iter().any(|audited| &audited.key == key))
+    }
+}
+
+// Every entry is appended as one JSON line and never changed afterwards.
+pub struct AuditLog {
+    path: PathBuf,
+    file_lock: Mutex<()>,
+}
+
+impl AuditLog {
+    pub fn new(path: PathBuf) -> Self {
+        Self {
+            path,
+            file_lock: Mutex::new(()),
+        }
+    }
+
+    // The operation already happened when it is recorded, so a failed append is only logged.
+    // Production changes are written to the application log as well.
+    pub fn record(&self, entry: AuditEntry) {
+        if entry.environment == Some(Environment::Production) {
+            let keys: Vec<&str> = entry
+                .keys
+                .iter()
+                .map(|audited| audited.key.as_str())
+                .collect();
+            warn!(
+                "Production change {:?} on the namespace {} ({}) of the account {} by the profile {}, keys: {:?}, outcome: {:?}",
+                entry.operation,
+                entry.namespace_title.as_deref().unwrap_or("-"),
+                entry.namespace_id.as_deref().unwrap_or("-"),
+                entry.account_id,
+                entry
+                    .profile
+                    .as_ref()
+                    .map_or("-", |profile| profile.name.as_str()),
+                keys,
+                entry.outcome
+            );
+        }
+        if let Err(error) = self.append(&entry) {
+            error!(
+                "The audit log entry {} could not be written: {error}",
+                entry.id
+            );
+        }
+    }
+
+    pub fn append(&self, entry: &AuditEntry) -> Result<(), AuditLogError> {
+        let mut line = serde_json::to_vec(entry)?;
+        line.push(b'\n');
+
+        let _file_lock = self.lock_file();
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        file.write_all(&line)?;
+        file.sync_data()?;
+        Ok(())
+    }
+
+    // The newest entries come first.
+    pub fn entries(&self, query: &AuditQuery) -> Result<Vec<AuditEntry>, AuditLogError> {
+        let mut entries = self.matching_entries(query)?;
+        entries.reverse();
+        if let Some(limit) = query.limit {
+            entries.truncate(limit);
+        }
+        Ok(entries)
+    }
+
+    // The entries are exported in the order they were recorded.
+    pub fn export_json_lines(&self, query: &AuditQuery) -> Result<String, AuditLogError> {
+        let mut json_lines = String::new();
+        for entry in self.entries(query)?.iter().rev() {
+            json_lines.push_str(&serde_json::to_string(entry)?);
+            json_lines.push('\n');
+        }
+        Ok(json_lines)
+    }
+
+    fn matching_entries(&self, query: &AuditQuery) -> Result<Vec<AuditEntry>, AuditLogError> {
+        let content = {
+            let _file_lock = self.lock_file();
+            match fs::read_to_string(&self.path) {
+                Ok(content) => content,
+                Err(error) if error.kind() == ErrorKind::NotFound => return Ok(vec![]),
+                Err(error) => return Err(error.into()),
+            }
+        };
+
+        Ok(content
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .filter_map(|line| match serde_json::from_str::<AuditEntry>(line) {
+                Ok(entry) => Some(entry),
+                Err(error) => {
+                    warn!("An audit log entry could not be read: {error}");
+                    None
+                }
+            })
+            .filter(|entry| query.matches(entry))
+            .collect())
+    }
+
+    fn lock_file(&self) -> MutexGuard<'_, ()> {
+        self.file_lock
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+pub fn value_hash(value: &[u8]) -> String {
+    digest(&SHA256, value)
+        .as_ref()
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect()
+}
+
+#[derive(Debug)]
+pub enum AuditLogError {
+    Io(std::io::Error),
+    Serialization(serde_json::Error),
+}
+
+impl From<std::io::Error> for AuditLogError {
+    fn from(error: std::io::Error) -> Self {
+        AuditLogError::Io(error)
+    }
+}
+
+impl From<serde_json::Error> for AuditLogError {
+    fn from(error: serde_json::Error) -> Self {
+        AuditLogError::Serialization(error)
+    }
+}
+
+impl Error for AuditLogError {}
+
+impl Display for AuditLogError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match &self {
+            AuditLogError::Io(err) => write!(f, "IO error: {}", err),
+            AuditLogError::Serialization(err) => write!(f, "Serialization error: {}", err),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{
+        AuditEntry, AuditLog, AuditLogError, AuditOperation, AuditOutcome, AuditQuery, AuditedKey,
+    };
+    use crate::cloudflare::kv::KvPairsDeleteResult;
+    use crate::session::session_registry::SessionProfile;
+    use crate::test_support::create_temp_dir;
+    use chrono::{TimeDelta, Utc};
+    use std::fs;
+
+    #[test]
+    fn should_query_the_entries_by_time_namespace_and_key() -> Result<(), AuditLogError> {
+        let directory = create_temp_dir();
+        let audit_log = AuditLog::new(directory.join("audit-log.jsonl"));
+        let profile = Some(SessionProfile {
+            id: "profile_id".to_string(),
+            name: "Production".to_string(),
+            environment: None,
+        });
+
+        let mut old_entry = AuditEntry::new(
+            profile.clone(),
+            AuditOperation::NamespaceCreate,
+            "account_id",
+            Some("namespace_id"),
+        );
+        old_entry.timestamp = Utc::now() - TimeDelta::days(2);
+        audit_log.append(&old_entry)?;
+
+        let mut delete_entry = AuditEntry::new(
+            profile,
+            AuditOperation::KeyDelete,
+            "account_id",
+            Some("namespace_id"),
+        );
+        delete_entry.keys = vec![AuditedKey::new("key1", None), AuditedKey::new("key2", None)];
+        delete_entry.outcome = AuditOutcome::from(&KvPairsDeleteResult {
+            successful_key_count: 1,
+            unsuccessful_keys: vec!["key2".to_string()],
+        });
+        audit_log.append(&delete_entry)?;
+
+        let mut write_entry = AuditEntry::new(
+            None,
+            AuditOperation::KeyWrite,
+            "account_id",
+            Some("other_namespace_id"),
+        );
+        write_entry.keys = vec![AuditedKey::new("key1", Some(b"value"))];
+        audit_log.append(&write_entry)?;
+
+        let all_entries = audit_log.entries(&AuditQuery::default())?;
+        assert_eq!(
+            all_entries,
+            vec![write_entry.clone(), delete_entry.clone(), old_entry]
+        );
+        assert_eq!(
+            audit_log.entries(&AuditQuery {
+                from: Some(Utc::now() - TimeDelta::days(1)),
+                namespace_id: Some("namespace_id".to_string()),
+                ..AuditQuery::default()
+            })?,
+            vec![delete_entry.clone()]
+        );
+        assert_eq!(
+            audit_log.entries(&AuditQuery {
+                key: Some("key1".to_string()),
+                limit: Some(1),
+                ..AuditQuery::default()
+            })?,
+            vec![write_entry.clone()]
+        );
+
+        let json_lines = audit_log.export_json_lines(&AuditQuery {
+            key: Some("key1".to_string()),
+            ..AuditQuery::default()
+        })?;
+        let exported: Vec<AuditEntry> = json_lines
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(exported, vec![delete_entry, write_entry]);
+
+        fs::remove_dir_all(directory)?;
+        Ok(())
+    }
+}
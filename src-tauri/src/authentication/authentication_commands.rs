@@ -1,95 +1,314 @@
-use crate::cloudflare::account::AccountError;
-use crate::cloudflare::common::{Credentials, TokenStatus};
-use crate::cloudflare::user::UserError;
 use crate::cloudflare::Cloudflare;
-use log::error;
+use crate::cloudflare::account::{
+    Account, AccountClient, AccountError, AccountSettings, AccountsListInput,
+};
+use crate::cloudflare::common::{
+    CloudflareApiError, Credentials, Page, Token, TokenCapabilities, TokenError, TokenStatus,
+    TokenWarning, paginate_pages, retry_after_ms,
+};
+use crate::cloudflare::user::{
+    Membership, MembershipStatus, MembershipsListInput, UserClient, UserError,
+};
+use crate::credentials::credentials_registry::{CredentialsInput, CredentialsRegistry};
+use crate::environment::environment_labels::Environment;
+use crate::session::session_registry::{SessionProfile, SessionRegistry};
+use chrono::Utc;
+use futures::TryStreamExt;
+use log::{error, warn};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tauri::State;
+
+const ACCOUNTS_PER_PAGE: u32 = 50;
 
 #[tauri::command]
-pub async fn verify_account_and_credentials(
+pub async fn list_accessible_accounts(
+    sessions: State<'_, Arc<SessionRegistry>>,
+    credentials_registry: State<'_, Arc<CredentialsRegistry>>,
+    credentials: CredentialsInput,
+) -> Result<Vec<AccessibleAccount>, AuthenticationCommandError> {
+    let credentials = resolve_credentials(&credentials_registry, credentials)?;
+    credentials.headers()?;
+
+    let cloudflare_client = sessions.create_client(credentials.clone(), None);
+    let accounts = list_all_accounts(&cloudflare_client.accounts).await?;
+
+    // Account tokens can't see memberships, and user tokens need an extra permission for them.
+    let mut roles_by_account_id: HashMap<String, Vec<String>> = match credentials {
+        Credentials::AccountAuthToken { .. } => HashMap::new(),
+        _ => match list_all_memberships(&cloudflare_client.user).await {
+            Ok(memberships) => memberships
+                .into_iter()
+                .map(|membership| (membership.account.id, membership.roles))
+                .collect(),
+            Err(error) => {
+                warn!("Memberships could not be listed: {error}");
+                HashMap::new()
+            }
+        },
+    };
+
+    Ok(accounts
+        .into_iter()
+        .map(|account| AccessibleAccount {
+            roles: roles_by_account_id.remove(&account.id).unwrap_or_default(),
+            id: account.id,
+            name: account.name,
+            settings: account.settings,
+        })
+        .collect())
+}
+
+#[tauri::command]
+pub async fn login(
+    sessions: State<'_, Arc<SessionRegistry>>,
+    credentials_registry: State<'_, Arc<CredentialsRegistry>>,
     account_id: String,
+    credentials: CredentialsInput,
+) -> Result<AccountSession, AuthenticationCommandError> {
+    let credentials = resolve_credentials(&credentials_registry, credentials)?;
+    create_account_session(&sessions, &account_id, credentials, None, None, false).await
+}
+
+#[tauri::command]
+pub fn logout(sessions: State<'_, Arc<SessionRegistry>>, session_id: String) {
+    sessions.remove(&session_id);
+}
+
+pub async fn create_account_session(
+    sessions: &SessionRegistry,
+    account_id: &str,
     credentials: Credentials,
-) -> Result<AccountWithCredentials, AuthenticationCommandError> {
-    let cloudflare_client = Cloudflare::new(credentials.clone(), None);
-    let account_client = cloudflare_client.accounts;
+    api_url: Option<String>,
+    profile: Option<SessionProfile>,
+    read_only: bool,
+) -> Result<AccountSession, AuthenticationCommandError> {
+    credentials.headers()?;
+
+    let cloudflare_client = sessions.create_client(credentials.clone(), api_url);
+    let (account, token) =
+        verify_account_and_credentials(&cloudflare_client, account_id, &credentials).await?;
+    let token_capabilities =
+        get_token_capabilities(&cloudflare_client, account_id, &credentials, &token.id).await;
+    let environment = profile.as_ref().and_then(|profile| profile.environment);
+    let session_id = sessions.create_session(cloudflare_client, profile, read_only);
+
+    Ok(AccountSession {
+        id: account.id,
+        name: account.name,
+        session_id,
+        token_capabilities,
+        read_only,
+        environment,
+    })
+}
+
+// Discovered credentials are only known until the next discovery.
+pub fn resolve_credentials(
+    credentials_registry: &CredentialsRegistry,
+    credentials: CredentialsInput,
+) -> Result<Credentials, AuthenticationCommandError> {
+    credentials_registry
+        .resolve(credentials)
+        .ok_or_else(|| AuthenticationCommandError {
+            kind: AuthenticationCommandErrorKind::UnknownCredentials,
+            message: "Discovered credentials are unknown, please discover them again".to_string(),
+            api_error: None,
+            retry_after_ms: None,
+        })
+}
+
+pub async fn verify_account_and_credentials(
+    cloudflare_client: &Cloudflare,
+    account_id: &str,
+    credentials: &Credentials,
+) -> Result<(Account, Token), AuthenticationCommandError> {
+    let account_client = &cloudflare_client.accounts;
 
     let verified_token = match credentials {
         Credentials::AccountAuthToken { token: _ } => {
-            account_client.verify_token(&account_id).await?
+            account_client.verify_token(account_id).await?
         }
         _ => {
-            let user_client = cloudflare_client.user;
+            let user_client = &cloudflare_client.user;
             user_client.verify_token().await?
         }
     };
 
     match verified_token.status {
-        TokenStatus::Active => {
-            let account = account_client.get_account(&account_id).await?;
-            Ok(AccountWithCredentials {
-                id: account.id,
-                name: account.name,
-                credentials,
-            })
-        }
+        TokenStatus::Active => Ok((
+            account_client.get_account(account_id).await?,
+            verified_token,
+        )),
         TokenStatus::Disabled => Err(AuthenticationCommandError {
             kind: AuthenticationCommandErrorKind::DisabledToken,
             message: "Token is disabled".to_string(),
+            api_error: None,
+            retry_after_ms: None,
         }),
         TokenStatus::Expired => Err(AuthenticationCommandError {
             kind: AuthenticationCommandErrorKind::ExpiredToken,
             message: "Token is expired".to_string(),
+            api_error: None,
+            retry_after_ms: None,
         }),
     }
 }
 
+// Reading the token details needs the "API Tokens Read" permission, so they are optional.
+async fn get_token_capabilities(
+    cloudflare_client: &Cloudflare,
+    account_id: &str,
+    credentials: &Credentials,
+    token_id: &str,
+) -> Option<TokenCapabilities> {
+    let token_details_result = match credentials {
+        Credentials::AccountAuthToken { .. } => cloudflare_client
+            .accounts
+            .get_token_details(account_id, token_id)
+            .await
+            .map_err(|error| error.to_string()),
+        Credentials::UserAuthToken { .. } => cloudflare_client
+            .user
+            .get_token_details(token_id)
+            .await
+            .map_err(|error| error.to_string()),
+        _ => return None,
+    };
+
+    match token_details_result {
+        Ok(token_details) => {
+            let token_capabilities = token_details.capabilities(account_id, Utc::now());
+            if token_capabilities
+                .warnings
+                .contains(&TokenWarning::ExpiresSoon)
+            {
+                warn!(
+                    "The token of account {account_id} expires on {:?}",
+                    token_capabilities.expires_on
+                );
+            }
+            Some(token_capabilities)
+        }
+        Err(error) => {
+            warn!("The token details could not be read: {error}");
+            None
+        }
+    }
+}
+
+async fn list_all_accounts(account_client: &AccountClient) -> Result<Vec<Account>, AccountError> {
+    paginate_pages(|page| async move {
+        let accounts = account_client
+            .list_accounts(AccountsListInput {
+                page: Some(page),
+                per_page: Some(ACCOUNTS_PER_PAGE),
+                ..AccountsListInput::default()
+            })
+            .await?;
+        Ok(Page::from_page_info(accounts.items, &accounts.page_info))
+    })
+    .try_collect()
+    .await
+}
+
+async fn list_all_memberships(user_client: &UserClient) -> Result<Vec<Membership>, UserError> {
+    paginate_pages(|page| async move {
+        let memberships = user_client
+            .list_memberships(MembershipsListInput {
+                status: Some(MembershipStatus::Accepted),
+                page: Some(page),
+                per_page: Some(ACCOUNTS_PER_PAGE),
+                ..MembershipsListInput::default()
+            })
+            .await?;
+        Ok(Page::from_page_info(
+            memberships.items,
+            &memberships.page_info,
+        ))
+    })
+    .try_collect()
+    .await
+}
+
 #[derive(Debug, PartialEq, Serialize, Deserialize)]
-pub struct AccountWithCredentials {
+pub struct AccessibleAccount {
     pub id: String,
     pub name: String,
-    pub credentials: Credentials,
+    pub settings: Option<AccountSettings>,
+    pub roles: Vec<String>,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct AccountSession {
+    pub id: String,
+    pub name: String,
+    pub session_id: String,
+    pub token_capabilities: Option<TokenCapabilities>,
+    pub read_only: bool,
+    pub environment: Option<Environment>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct AuthenticationCommandError {
     kind: AuthenticationCommandErrorKind,
-    message: String,
+    pub(crate) message: String,
+    api_error: Option<CloudflareApiError>,
+    // When a rate limited request may be retried, if Cloudflare told.
+    retry_after_ms: Option<u64>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub enum AuthenticationCommandErrorKind {
     Authentication,
     InvalidToken,
+    MalformedCredentials,
+    UnknownCredentials,
     DisabledToken,
     ExpiredToken,
     InvalidAccountId,
+    RateLimited,
     Unknown,
 }
 
+impl From<TokenError> for AuthenticationCommandError {
+    fn from(error: TokenError) -> Self {
+        error!("Token error occurred: {}", error);
+        match error {
+            TokenError::Malformed => AuthenticationCommandError {
+                kind: AuthenticationCommandErrorKind::MalformedCredentials,
+                message: "Credentials contain invalid characters".to_string(),
+                api_error: None,
+                retry_after_ms: None,
+            },
+            _ => AuthenticationCommandError {
+                kind: AuthenticationCommandErrorKind::InvalidToken,
+                message: "Token is invalid".to_string(),
+                api_error: None,
+                retry_after_ms: None,
+            },
+        }
+    }
+}
+
 impl From<UserError> for AuthenticationCommandError {
     fn from(value: UserError) -> Self {
         match value {
-            UserError::Token(token_error) => {
-                error!("Token error occurred: {}", token_error);
-                AuthenticationCommandError {
-                    kind: AuthenticationCommandErrorKind::InvalidToken,
-                    message: "Token is invalid".to_string(),
-                }
-            }
+            UserError::Token(token_error) => token_error.into(),
+            UserError::Unauthorized(api_error) => unauthorized(api_error),
+            UserError::RateLimited(retry_after, api_error) => rate_limited(retry_after, api_error),
             UserError::Reqwest(reqwest_err) => {
                 error!("A reqwest error occurred: {}", reqwest_err);
                 AuthenticationCommandError {
                     kind: AuthenticationCommandErrorKind::Unknown,
                     message: "A network error occurred".to_string(),
+                    api_error: None,
+                    retry_after_ms: None,
                 }
             }
-            UserError::Unknown(unknown_err) => {
-                error!("An unknown error occurred: {}", unknown_err);
-                AuthenticationCommandError {
-                    kind: AuthenticationCommandErrorKind::Unknown,
-                    message: "An unknown error occurred".to_string(),
-                }
-            }
+            UserError::Api(api_error) => unknown_api_error(api_error),
         }
     }
 }
@@ -97,34 +316,63 @@ impl From<UserError> for AuthenticationCommandError {
 impl From<AccountError> for AuthenticationCommandError {
     fn from(error: AccountError) -> Self {
         match error {
-            AccountError::InvalidAccountId => {
-                error!("Invalid account id error occurred");
+            AccountError::InvalidAccountId(api_error) => {
+                error!("Invalid account id error occurred: {api_error}");
                 AuthenticationCommandError {
                     kind: AuthenticationCommandErrorKind::InvalidAccountId,
                     message: "Account ID is invalid".to_string(),
+                    api_error: Some(api_error),
+                    retry_after_ms: None,
                 }
             }
-            AccountError::Token(token_error) => {
-                error!("Token error occurred: {}", token_error);
-                AuthenticationCommandError {
-                    kind: AuthenticationCommandErrorKind::InvalidToken,
-                    message: "Token is invalid".to_string(),
-                }
+            AccountError::Token(token_error) => token_error.into(),
+            AccountError::Unauthorized(api_error) => unauthorized(api_error),
+            AccountError::RateLimited(retry_after, api_error) => {
+                rate_limited(retry_after, api_error)
             }
             AccountError::Reqwest(reqwest_err) => {
                 error!("A reqwest error occurred: {}", reqwest_err);
                 AuthenticationCommandError {
                     kind: AuthenticationCommandErrorKind::Unknown,
                     message: "A network error occurred".to_string(),
+                    api_error: None,
+                    retry_after_ms: None,
                 }
             }
-            AccountError::Unknown(unknown_err) => {
-                error!("An unknown error occurred: {}", unknown_err);
-                AuthenticationCommandError {
-                    kind: AuthenticationCommandErrorKind::Unknown,
-                    message: "An unknown error occurred".to_string(),
-                }
-            }
+            AccountError::Api(api_error) => unknown_api_error(api_error),
         }
     }
 }
+
+fn unauthorized(api_error: CloudflareApiError) -> AuthenticationCommandError {
+    error!("A request could not be authenticated: {api_error}");
+    AuthenticationCommandError {
+        kind: AuthenticationCommandErrorKind::InvalidToken,
+        message: "Token is invalid".to_string(),
+        api_error: Some(api_error),
+        retry_after_ms: None,
+    }
+}
+
+fn rate_limited(
+    retry_after: Option<Duration>,
+    api_error: CloudflareApiError,
+) -> AuthenticationCommandError {
+    error!("Rate limit exceeded, retry after: {retry_after:?}");
+    AuthenticationCommandError {
+        kind: AuthenticationCommandErrorKind::RateLimited,
+        message: "Too many requests, please try again later".to_string(),
+        api_error: Some(api_error),
+        retry_after_ms: retry_after_ms(retry_after),
+    }
+}
+
+fn unknown_api_error(api_error: CloudflareApiError) -> AuthenticationCommandError {
+    error!("An API error occurred: {api_error}");
+    AuthenticationCommandError {
+        kind: AuthenticationCommandErrorKind::Unknown,
+        message: api_error.message(),
+        api_error: Some(api_error),
+        retry_after_ms: None,
+    }
+}
@@ -0,0 +1,481 @@
+use crate::audit::audit_log::{AuditEntry, AuditLog, AuditOperation, AuditOutcome};
+use crate::bulk::namespace_backups::NamespaceBackups;
+use crate::bulk::namespace_operations::{
+    KvNamespaceCloneInput, KvNamespaceCloneResult, KvNamespaceDeleteOutcome,
+    KvNamespaceOperationStatus, KvNamespaceRenameOutcome, KvNamespacesDeleteInput,
+    KvNamespacesRenameInput, copy_namespace_pairs, namespace_pair_chunks, plan_renames,
+};
+use crate::cache::kv_cache::{KvCache, namespace_cache_prefix, namespaces_cache_prefix};
+use crate::cloudflare::kv::{
+    KvClient, KvNamespace, KvNamespaceCreateInput, KvNamespaceDeleteInput, KvNamespaceGetInput,
+    KvNamespaceUpdateInput, KvNamespacesListInput, KvPairsWriteResult,
+};
+use crate::environment::environment_commands::{acknowledge_production, label_namespace};
+use crate::environment::environment_labels::NamespaceEnvironments;
+use crate::kv::kv_commands::KvCommandError;
+use crate::protection::protected_namespaces::ProtectedNamespaces;
+use crate::protection::protection_commands::confirm_deletion;
+use crate::session::session_registry::SessionRegistry;
+use chrono::Utc;
+use futures::TryStreamExt;
+use log::warn;
+use regex::Regex;
+use std::path::PathBuf;
+use std::pin::pin;
+use std::sync::Arc;
+use tauri::State;
+
+// Each namespace passes the same checks as a single delete. A namespace which fails them, or
+// whose backup could not be written, is kept and the others are deleted anyway.
+#[tauri::command]
+pub async fn delete_namespaces(
+    sessions: State<'_, Arc<SessionRegistry>>,
+    cache: State<'_, Arc<KvCache>>,
+    audit_log: State<'_, Arc<AuditLog>>,
+    protected_namespaces: State<'_, Arc<ProtectedNamespaces>>,
+    environments: State<'_, Arc<NamespaceEnvironments>>,
+    backups: State<'_, Arc<NamespaceBackups>>,
+    session_id: String,
+    input: KvNamespacesDeleteInput,
+) -> Result<Vec<KvNamespaceDeleteOutcome>, KvCommandError> {
+    let cloudflare_client = sessions.get_writable(&session_id)?;
+    let kv = &cloudflare_client.kv;
+    let mut outcomes = vec![];
+    for namespace_id in &input.namespace_ids {
+        let mut backup_path = None;
+        let result: Result<(), KvCommandError> = async {
+            let mut audit_entry = AuditEntry::new(
+                sessions.profile(&session_id),
+                AuditOperation::NamespaceDelete,
+                &input.account_id,
+                Some(namespace_id),
+            );
+            acknowledge_production(
+                &environments,
+                kv,
+                &mut audit_entry,
+                input.acknowledge_production,
+            )
+            .await?;
+            backup_path =
+                prepare_deletion(kv, &protected_namespaces, &backups, &input, namespace_id).await?;
+
+            let result = kv
+                .delete_namespace(KvNamespaceDeleteInput {
+                    account_id: input.account_id.clone(),
+                    namespace_id: namespace_id.clone(),
+                })
+                .await;
+            invalidate(
+                &cache,
+                &namespace_cache_prefix(&input.account_id, namespace_id),
+            );
+            audit_entry.outcome = AuditOutcome::of(&result, |_| AuditOutcome::Succeeded);
+            audit_log.record(audit_entry);
+            Ok(result?)
+        }
+        .await;
+
+        let (status, error) = status_of(result);
+        outcomes.push(KvNamespaceDeleteOutcome {
+            namespace_id: namespace_id.clone(),
+            backup_path,
+            status,
+            error,
+        });
+    }
+    invalidate(&cache, &namespaces_cache_prefix(&input.account_id));
+
+    Ok(outcomes)
+}
+
+// A dry run only lists the planned renames, so it works with read-only profiles as well.
+#[tauri::command]
+pub async fn rename_namespaces(
+    sessions: State<'_, Arc<SessionRegistry>>,
+    cache: State<'_, Arc<KvCache>>,
+    audit_log: State<'_, Arc<AuditLog>>,
+    environments: State<'_, Arc<NamespaceEnvironments>>,
+    session_id: String,
+    input: KvNamespacesRenameInput,
+) -> Result<Vec<KvNamespaceRenameOutcome>, KvCommandError> {
+    let pattern = Regex::new(&input.pattern)?;
+    let cloudflare_client = if input.dry_run {
+        sessions.get(&session_id)?
+    } else {
+        sessions.get_writable(&session_id)?
+    };
+    let kv = &cloudflare_client.kv;
+    let namespaces: Vec<KvNamespace> = kv
+        .list_all_namespaces(KvNamespacesListInput {
+            account_id: input.account_id.clone(),
+            ..KvNamespacesListInput::default()
+        })
+        .try_collect()
+        .await?;
+    let renames = plan_renames(
+        &namespaces,
+        &pattern,
+        &input.replacement,
+        input.namespace_ids.as_deref(),
+    );
+
+    if input.dry_run {
+        return Ok(renames
+            .into_iter()
+            .map(|rename| KvNamespaceRenameOutcome {
+                namespace_id: rename.namespace_id,
+                title: rename.title,
+                new_title: rename.new_title,
+                status: KvNamespaceOperationStatus::Planned,
+                error: None,
+            })
+            .collect());
+    }
+
+    let mut outcomes = vec![];
+    for rename in renames {
+        let result: Result<(), KvCommandError> = async {
+            let mut audit_entry = AuditEntry::new(
+                sessions.profile(&session_id),
+                AuditOperation::NamespaceRename,
+                &input.account_id,
+                Some(&rename.namespace_id),
+            );
+            audit_entry.namespace_title = Some(rename.new_title.clone());
+            acknowledge_production(
+                &environments,
+                kv,
+                &mut audit_entry,
+                input.acknowledge_production,
+            )
+            .await?;
+            let result = kv
+                .update_namespace(KvNamespaceUpdateInput {
+                    account_id: input.account_id.clone(),
+                    namespace_id: rename.namespace_id.clone(),
+                    title: rename.new_title.clone(),
+                })
+                .await;
+            audit_entry.outcome = AuditOutcome::of(&result, |_| AuditOutcome::Succeeded);
+            audit_log.record(audit_entry);
+            result?;
+            Ok(())
+        }
+        .await;
+
+        let (status, error) = status_of(result);
+        outcomes.push(KvNamespaceRenameOutcome {
+            namespace_id: rename.namespace_id,
+            title: rename.title,
+            new_title: rename.new_title,
+            status,
+            error,
+        });
+    }
+    invalidate(&cache, &namespaces_cache_prefix(&input.account_id));
+
+    Ok(outcomes)
+}
+
+// The source is looked up first, so a mistyped id does not leave an empty namespace behind. A
+// copy which fails halfway leaves the new namespace in place, so it can be inspected or deleted.
+#[tauri::command]
+pub async fn clone_namespace(
+    sessions: State<'_, Arc<SessionRegistry>>,
+    cache: State<'_, Arc<KvCache>>,
+    audit_log: State<'_, Arc<AuditLog>>,
+    environments: State<'_, Arc<NamespaceEnvironments>>,
+    session_id: String,
+    input: KvNamespaceCloneInput,
+) -> Result<KvNamespaceCloneResult, KvCommandError> {
+    let cloudflare_client = sessions.get_writable(&session_id)?;
+    let kv = &cloudflare_client.kv;
+    kv.get_namespace(KvNamespaceGetInput {
+        account_id: input.account_id.clone(),
+        namespace_id: input.namespace_id.clone(),
+    })
+    .await?;
+    let mut audit_entry = AuditEntry::new(
+        sessions.profile(&session_id),
+        AuditOperation::NamespaceCreate,
+        &input.account_id,
+        None,
+    );
+    audit_entry.namespace_title = Some(input.title.clone());
+    acknowledge_production(
+        &environments,
+        kv,
+        &mut audit_entry,
+        input.acknowledge_production,
+    )
+    .await?;
+    let result = kv
+        .create_namespace(KvNamespaceCreateInput {
+            account_id: input.account_id.clone(),
+            title: input.title.clone(),
+        })
+        .await;
+    invalidate(&cache, &namespaces_cache_prefix(&input.account_id));
+    audit_entry.namespace_id = result.as_ref().ok().map(|namespace| namespace.id.clone());
+    audit_entry.outcome = AuditOutcome::of(&result, |_| AuditOutcome::Succeeded);
+    let environment = audit_entry.environment;
+    audit_log.record(audit_entry);
+    let namespace = result?;
+
+    let mut copy_entry = AuditEntry::new(
+        sessions.profile(&session_id),
+        AuditOperation::KeyWrite,
+        &input.account_id,
+        Some(&namespace.id),
+    );
+    copy_entry.namespace_title = Some(namespace.title.clone());
+    copy_entry.environment = environment;
+    let result =
+        copy_namespace_pairs(kv, &input.account_id, &input.namespace_id, &namespace.id).await;
+    copy_entry.outcome = AuditOutcome::of(&result, |copy| {
+        AuditOutcome::from(&KvPairsWriteResult {
+            successful_key_count: copy.copied_key_count,
+            unsuccessful_keys: copy.unsuccessful_keys.clone(),
+        })
+    });
+    audit_log.record(copy_entry);
+    let copy = result?;
+
+    let namespace = label_namespace(
+        &environments,
+        sessions
+            .profile(&session_id)
+            .and_then(|profile| profile.environment),
+        &input.account_id,
+        namespace,
+    );
+    Ok(KvNamespaceCloneResult { namespace, copy })
+}
+
+// A protected namespace is neither backed up nor deleted without its title as confirmation.
+async fn prepare_deletion(
+    kv: &KvClient,
+    protected_namespaces: &ProtectedNamespaces,
+    backups: &NamespaceBackups,
+    input: &KvNamespacesDeleteInput,
+    namespace_id: &str,
+) -> Result<Option<PathBuf>, KvCommandError> {
+    confirm_deletion(
+        protected_namespaces,
+        kv,
+        &input.account_id,
+        namespace_id,
+        input.confirmations.get(namespace_id).map(String::as_str),
+    )
+    .await?;
+    if !input.backup {
+        return Ok(None);
+    }
+
+    Ok(Some(
+        backup_namespace(kv, backups, &input.account_id, namespace_id).await?,
+    ))
+}
+
+async fn backup_namespace(
+    kv: &KvClient,
+    backups: &NamespaceBackups,
+    account_id: &str,
+    namespace_id: &str,
+) -> Result<PathBuf, KvCommandError> {
+    let namespace = kv
+        .get_namespace(KvNamespaceGetInput {
+            account_id: account_id.to_string(),
+            namespace_id: namespace_id.to_string(),
+        })
+        .await?;
+    let mut backup = backups.create(account_id, namespace_id, &namespace.title, Utc::now())?;
+    let mut chunks = pin!(namespace_pair_chunks(kv, account_id, namespace_id));
+    let mut listed_key_count = 0;
+    while let Some(chunk) = chunks.try_next().await? {
+        listed_key_count += chunk.listed_key_count;
+        backup.write_pairs(&chunk.pairs)?;
+    }
+    Ok(backup.finish(listed_key_count)?)
+}
+
+fn status_of(
+    result: Result<(), KvCommandError>,
+) -> (KvNamespaceOperationStatus, Option<KvCommandError>) {
+    match result {
+        Ok(()) => (KvNamespaceOperationStatus::Succeeded, None),
+        Err(error) => (KvNamespaceOperationStatus::Failed, Some(error)),
+    }
+}
+
+fn invalidate(cache: &KvCache, cache_prefix: &str) {
+    if let Err(error) = cache.invalidate(cache_prefix) {
+        warn!("The cache entries {cache_prefix} could not be invalidated: {error}");
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::prepare_deletion;
+    use crate::bulk::namespace_backups::NamespaceBackups;
+    use crate::bulk::namespace_operations::KvNamespacesDeleteInput;
+    use crate::cloudflare::common::ApiResponse;
+    use crate::cloudflare::kv::KvNamespace;
+    use crate::kv::kv_commands::KvCommandError;
+    use crate::protection::protected_namespaces::{ProtectedNamespace, ProtectedNamespaces};
+    use crate::test_support::{create_kv_client, create_temp_dir};
+    use serde_json::{Value, json};
+    use std::collections::HashMap;
+    use std::fs;
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    const NAMESPACE_PATH: &str =
+        "/client/v4/accounts/account_id/storage/kv/namespaces/namespace_id";
+
+    #[tokio::test]
+    async fn should_back_up_a_protected_namespace_only_once_it_is_confirmed()
+    -> Result<(), KvCommandError> {
+        let mock_server = MockServer::start().await;
+        mount_namespace(
+            &mock_server,
+            json!([{"name": "key1"}, {"name": "key2"}]),
+            json!({
+                "key1": {
+                    "value": "value1",
+                    "metadata": {"owner": "team"},
+                    "expiration": 4102444800_i64,
+                },
+                "key2": {"value": "value2", "metadata": null},
+            }),
+        )
+        .await;
+        let kv = create_kv_client(&mock_server);
+        let directory = create_temp_dir();
+        let protected_namespaces =
+            ProtectedNamespaces::new(directory.join("protected-namespaces.json"));
+        protected_namespaces.set_protected(
+            ProtectedNamespace {
+                account_id: "account_id".to_string(),
+                namespace_id: "namespace_id".to_string(),
+            },
+            true,
+        )?;
+        let backups = NamespaceBackups::new(directory.join("namespace-backups"));
+        let mut input = KvNamespacesDeleteInput {
+            account_id: "account_id".to_string(),
+            namespace_ids: vec!["namespace_id".to_string()],
+            backup: true,
+            confirmations: HashMap::new(),
+            acknowledge_production: false,
+        };
+
+        let error = prepare_deletion(&kv, &protected_namespaces, &backups, &input, "namespace_id")
+            .await
+            .unwrap_err();
+        assert_eq!(
+            serde_json::to_value(error).unwrap()["kind"],
+            "ConfirmationRequired"
+        );
+        assert!(!directory.join("namespace-backups").exists());
+
+        input
+            .confirmations
+            .insert("namespace_id".to_string(), "sessions".to_string());
+        let backup_path =
+            prepare_deletion(&kv, &protected_namespaces, &backups, &input, "namespace_id")
+                .await?
+                .unwrap();
+
+        let backup: Value = serde_json::from_slice(&fs::read(backup_path).unwrap()).unwrap();
+        assert_eq!(backup["title"], "sessions");
+        assert_eq!(backup["pairs"].as_array().unwrap().len(), 2);
+        assert_eq!(backup["pairs"][0]["key"], "key1");
+        assert_eq!(backup["pairs"][0]["metadata"], json!({"owner": "team"}));
+        assert_eq!(backup["pairs"][0]["expiration"], 4102444800_i64);
+        assert_eq!(backup["pairs"][1]["key"], "key2");
+
+        fs::remove_dir_all(directory).unwrap();
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn should_fail_a_backup_which_misses_listed_keys() -> Result<(), KvCommandError> {
+        let mock_server = MockServer::start().await;
+        mount_namespace(
+            &mock_server,
+            json!([{"name": "key1"}, {"name": "key2"}]),
+            json!({
+                "key1": {"value": "value1", "metadata": null},
+                "key2": null,
+            }),
+        )
+        .await;
+        let kv = create_kv_client(&mock_server);
+        let directory = create_temp_dir();
+        let protected_namespaces =
+            ProtectedNamespaces::new(directory.join("protected-namespaces.json"));
+        let backups = NamespaceBackups::new(directory.join("namespace-backups"));
+        let input = KvNamespacesDeleteInput {
+            account_id: "account_id".to_string(),
+            namespace_ids: vec!["namespace_id".to_string()],
+            backup: true,
+            confirmations: HashMap::new(),
+            acknowledge_production: false,
+        };
+
+        let error = prepare_deletion(&kv, &protected_namespaces, &backups, &input, "namespace_id")
+            .await
+            .unwrap_err();
+
+        assert_eq!(serde_json::to_value(error).unwrap()["kind"], "BackupFailed");
+        assert_eq!(
+            fs::read_dir(directory.join("namespace-backups").join("account_id"))
+                .unwrap()
+                .count(),
+            0
+        );
+
+        fs::remove_dir_all(directory).unwrap();
+        Ok(())
+    }
+
+    async fn mount_namespace(mock_server: &MockServer, keys: Value, values: Value) {
+        Mock::given(method("GET"))
+            .and(path(NAMESPACE_PATH))
+            .respond_with(ResponseTemplate::new(200).set_body_json(ApiResponse {
+                result: KvNamespace {
+                    id: "namespace_id".to_string(),
+                    title: "sessions".to_string(),
+                    beta: None,
+                    supports_url_encoding: None,
+                },
+            }))
+            .mount(mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path(format!("{NAMESPACE_PATH}/keys")))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "success": true,
+                "errors": [],
+                "messages": [],
+                "result": keys,
+                "result_info": {"count": 2, "cursor": ""},
+            })))
+            .expect(1)
+            .mount(mock_server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path(format!("{NAMESPACE_PATH}/bulk/get")))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "success": true,
+                "errors": [],
+                "messages": [],
+                "result": {"values": values},
+            })))
+            .expect(1)
+            .mount(mock_server)
+            .await;
+    }
+}
@@ -0,0 +1,3 @@
+pub mod bulk_commands;
+pub mod namespace_backups;
+pub mod namespace_operations;
@@ -0,0 +1,266 @@
+use crate::cloudflare::kv::KvPair;
+use chrono::{DateTime, Utc};
+use std::error::Error;
+use std::fmt;
+use std::fmt::{Display, Formatter};
+use std::fs;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::PathBuf;
+
+pub const NAMESPACE_BACKUPS_DIRECTORY_NAME: &str = "namespace-backups";
+
+// Every backup is a separate file which is never overwritten, so deleting a namespace twice keeps
+// both copies.
+pub struct NamespaceBackups {
+    directory: PathBuf,
+}
+
+impl NamespaceBackups {
+    pub fn new(directory: PathBuf) -> Self {
+        Self { directory }
+    }
+
+    // The pairs are appended as they are read, the file only gets its final name once the backup
+    // is finished.
+    pub fn create(
+        &self,
+        account_id: &str,
+        namespace_id: &str,
+        title: &str,
+        created_at: DateTime<Utc>,
+    ) -> Result<NamespaceBackupWriter, NamespaceBackupError> {
+        let account_directory = self.directory.join(account_id);
+        fs::create_dir_all(&account_directory)?;
+        let path = account_directory.join(format!(
+            "{}-{}.json",
+            namespace_id,
+            created_at.format("%Y%m%dT%H%M%S%.3fZ")
+        ));
+        let temporary_path = path.with_extension("tmp");
+        let mut writer = NamespaceBackupWriter {
+            file: BufWriter::new(File::create(&temporary_path)?),
+            path,
+            temporary_path,
+            pair_count: 0,
+            finished: false,
+        };
+        writer.write_header(account_id, namespace_id, title, created_at)?;
+        Ok(writer)
+    }
+}
+
+// A backup is a JSON object with the account id, namespace id, title, creation time and the pairs
+// of the namespace. A backup which is dropped before it is finished removes its temporary file.
+pub struct NamespaceBackupWriter {
+    file: BufWriter<File>,
+    path: PathBuf,
+    temporary_path: PathBuf,
+    pair_count: usize,
+    finished: bool,
+}
+
+impl NamespaceBackupWriter {
+    pub fn write_pairs(&mut self, pairs: &[KvPair]) -> Result<(), NamespaceBackupError> {
+        for pair in pairs {
+            if self.pair_count > 0 {
+                self.file.write_all(b",")?;
+            }
+            serde_json::to_writer(&mut self.file, pair)?;
+            self.pair_count += 1;
+        }
+        Ok(())
+    }
+
+    // The backup is only complete if a pair was written for every listed key. Keys which were
+    // deleted or whose pages went missing while it was written fail it.
+    pub fn finish(mut self, listed_key_count: usize) -> Result<PathBuf, NamespaceBackupError> {
+        if self.pair_count != listed_key_count {
+            return Err(NamespaceBackupError::Incomplete {
+                listed_key_count,
+                pair_count: self.pair_count,
+            });
+        }
+
+        self.file.write_all(b"]}")?;
+        self.file.flush()?;
+        self.file.get_ref().sync_all()?;
+        fs::rename(&self.temporary_path, &self.path)?;
+        self.finished = true;
+        Ok(self.path.clone())
+    }
+
+    fn write_header(
+        &mut self,
+        account_id: &str,
+        namespace_id: &str,
+        title: &str,
+        created_at: DateTime<Utc>,
+    ) -> Result<(), NamespaceBackupError> {
+        let file = &mut self.file;
+        file.write_all(b"{\"account_id\":")?;
+        serde_json::to_writer(&mut *file, account_id)?;
+        file.write_all(b",\"namespace_id\":")?;
+        serde_json::to_writer(&mut *file, namespace_id)?;
+        file.write_all(b",\"title\":")?;
+        serde_json::to_writer(&mut *file, title)?;
+        file.write_all(b",\"created_at\":")?;
+        serde_json::to_writer(&mut *file, &created_at)?;
+        file.write_all(b",\"pairs\":[")?;
+        Ok(())
+    }
+}
+
+impl Drop for NamespaceBackupWriter {
+    fn drop(&mut self) {
+        if !self.finished {
+            let _ = fs::remove_file(&self.temporary_path);
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum NamespaceBackupError {
+    Io(std::io::Error),
+    Serialization(serde_json::Error),
+    Incomplete {
+        listed_key_count: usize,
+        pair_count: usize,
+    },
+}
+
+impl From<std::io::Error> for NamespaceBackupError {
+    fn from(error: std::io::Error) -> Self {
+        NamespaceBackupError::Io(error)
+    }
+}
+
+impl From<serde_json::Error> for NamespaceBackupError {
+    fn from(error: serde_json::Error) -> Self {
+        NamespaceBackupError::Serialization(error)
+    }
+}
+
+impl Error for NamespaceBackupError {}
+
+impl Display for NamespaceBackupError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match &self {
+            NamespaceBackupError::Io(err) => write!(f, "IO error: {}", err),
+            NamespaceBackupError::Serialization(err) => write!(f, "Serialization error: {}", err),
+            NamespaceBackupError::Incomplete {
+                listed_key_count,
+                pair_count,
+            } => write!(
+                f,
+                "Incomplete backup: {} of {} listed keys were written",
+                pair_count, listed_key_count
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{NamespaceBackupError, NamespaceBackups};
+    use crate::cloudflare::kv::KvPair;
+    use crate::test_support::create_temp_dir;
+    use chrono::{DateTime, Utc};
+    use serde::Deserialize;
+    use std::collections::HashMap;
+    use std::fs;
+    use std::path::PathBuf;
+
+    #[derive(Debug, Clone, PartialEq, Deserialize)]
+    struct NamespaceBackup {
+        account_id: String,
+        namespace_id: String,
+        title: String,
+        created_at: DateTime<Utc>,
+        pairs: Vec<KvPair>,
+    }
+
+    #[test]
+    fn should_write_every_backup_to_a_new_file() -> Result<(), NamespaceBackupError> {
+        let directory = create_temp_dir();
+        let backups = NamespaceBackups::new(directory.join("namespace-backups"));
+        let backup = NamespaceBackup {
+            account_id: "account_id".to_string(),
+            namespace_id: "namespace_id".to_string(),
+            title: "sessions".to_string(),
+            created_at: Utc::now(),
+            pairs: vec![
+                create_pair("key1", vec![0, 159, 146, 150]),
+                create_pair("key2", b"value".to_vec()),
+                create_pair("key3", b"value".to_vec()),
+            ],
+        };
+
+        let first_path = write(&backups, &backup)?;
+        let second_path = write(
+            &backups,
+            &NamespaceBackup {
+                created_at: Utc::now() + chrono::TimeDelta::seconds(1),
+                pairs: vec![],
+                ..backup.clone()
+            },
+        )?;
+
+        assert_ne!(first_path, second_path);
+        let written: NamespaceBackup = serde_json::from_slice(&fs::read(first_path)?)?;
+        assert_eq!(written, backup);
+        let written: NamespaceBackup = serde_json::from_slice(&fs::read(second_path)?)?;
+        assert_eq!(written.pairs, vec![]);
+
+        fs::remove_dir_all(directory)?;
+        Ok(())
+    }
+
+    #[test]
+    fn should_remove_an_incomplete_backup() -> Result<(), NamespaceBackupError> {
+        let directory = create_temp_dir();
+        let backups = NamespaceBackups::new(directory.clone());
+
+        let mut writer = backups.create("account_id", "namespace_id", "sessions", Utc::now())?;
+        writer.write_pairs(&[create_pair("key", b"value".to_vec())])?;
+        let error = writer.finish(2).unwrap_err();
+
+        assert!(matches!(
+            error,
+            NamespaceBackupError::Incomplete {
+                listed_key_count: 2,
+                pair_count: 1
+            }
+        ));
+        assert_eq!(fs::read_dir(directory.join("account_id"))?.count(), 0);
+
+        fs::remove_dir_all(directory)?;
+        Ok(())
+    }
+
+    // The pairs are written in two chunks, like they are read from a namespace.
+    fn write(
+        backups: &NamespaceBackups,
+        backup: &NamespaceBackup,
+    ) -> Result<PathBuf, NamespaceBackupError> {
+        let mut writer = backups.create(
+            &backup.account_id,
+            &backup.namespace_id,
+            &backup.title,
+            backup.created_at,
+        )?;
+        let (first_chunk, second_chunk) = backup.pairs.split_at(backup.pairs.len().min(2));
+        writer.write_pairs(first_chunk)?;
+        writer.write_pairs(second_chunk)?;
+        writer.finish(backup.pairs.len())
+    }
+
+    fn create_pair(key: &str, value: Vec<u8>) -> KvPair {
+        KvPair {
+            key: key.to_string(),
+            value,
+            metadata: Some(HashMap::from([("owner".to_string(), "team".into())])),
+            expiration: None,
+        }
+    }
+}
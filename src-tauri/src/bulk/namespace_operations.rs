@@ -0,0 +1,421 @@
+use crate::cloudflare::kv::{
+    BULK_WRITE_KEY_LIMIT, KvClient, KvError, KvKeysListInput, KvNamespace, KvPair,
+    KvPairBulkWriteInput, KvPairValue, KvPairsGetInput, KvPairsWriteInput, KvPairsWriteResult,
+    bulk_write_chunks,
+};
+use crate::environment::environment_labels::LabelledNamespace;
+use crate::kv::kv_commands::KvCommandError;
+use chrono::Utc;
+use futures::{Stream, TryStreamExt};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::pin::pin;
+
+// A chunk holds as many keys as a single bulk write accepts.
+const PAIRS_CHUNK_SIZE: usize = BULK_WRITE_KEY_LIMIT;
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct KvNamespacesDeleteInput {
+    pub account_id: String,
+    pub namespace_ids: Vec<String>,
+
+    // The pairs of each namespace are written to a local file before it is deleted.
+    #[serde(default)]
+    pub backup: bool,
+
+    // The titles confirming the deletion of protected namespaces, by namespace id.
+    #[serde(default)]
+    pub confirmations: HashMap<String, String>,
+
+    #[serde(default)]
+    pub acknowledge_production: bool,
+}
+
+// The replacement may refer to groups of the pattern, like `$1-dev` or `${name}`.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct KvNamespacesRenameInput {
+    pub account_id: String,
+    pub pattern: String,
+    pub replacement: String,
+
+    // All namespaces of the account are matched if no ids are given.
+    #[serde(default)]
+    pub namespace_ids: Option<Vec<String>>,
+
+    // The renames are only planned, nothing is changed.
+    #[serde(default)]
+    pub dry_run: bool,
+
+    #[serde(default)]
+    pub acknowledge_production: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct KvNamespaceCloneInput {
+    pub account_id: String,
+    pub namespace_id: String,
+    pub title: String,
+
+    #[serde(default)]
+    pub acknowledge_production: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub enum KvNamespaceOperationStatus {
+    Planned,
+    Succeeded,
+    Failed,
+}
+
+#[derive(Debug, Serialize)]
+pub struct KvNamespaceDeleteOutcome {
+    pub namespace_id: String,
+    pub backup_path: Option<PathBuf>,
+    pub status: KvNamespaceOperationStatus,
+    pub error: Option<KvCommandError>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct KvNamespaceRename {
+    pub namespace_id: String,
+    pub title: String,
+    pub new_title: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct KvNamespaceRenameOutcome {
+    pub namespace_id: String,
+    pub title: String,
+    pub new_title: String,
+    pub status: KvNamespaceOperationStatus,
+    pub error: Option<KvCommandError>,
+}
+
+// Keys which would expire within a minute are not copied, the API does not accept them.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+pub struct KvNamespaceCopyResult {
+    pub copied_key_count: u32,
+    pub skipped_keys: Vec<String>,
+    pub unsuccessful_keys: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct KvNamespaceCloneResult {
+    pub namespace: LabelledNamespace,
+    pub copy: KvNamespaceCopyResult,
+}
+
+// Every match within a title is replaced. Titles which stay the same are left out.
+pub fn plan_renames(
+    namespaces: &[KvNamespace],
+    pattern: &Regex,
+    replacement: &str,
+    namespace_ids: Option<&[String]>,
+) -> Vec<KvNamespaceRename> {
+    namespaces
+        .iter()
+        .filter(|namespace| namespace_ids.is_none_or(|ids| ids.contains(&namespace.id)))
+        .filter_map(|namespace| {
+            let new_title = pattern.replace_all(&namespace.title, replacement);
+            (new_title != namespace.title).then(|| KvNamespaceRename {
+                namespace_id: namespace.id.clone(),
+                title: namespace.title.clone(),
+                new_title: new_title.into_owned(),
+            })
+        })
+        .collect()
+}
+
+// The pairs read for a chunk of listed keys. Keys deleted after they were listed have no pair.
+pub struct NamespacePairsChunk {
+    pub listed_key_count: usize,
+    pub pairs: Vec<KvPair>,
+}
+
+// The pairs are read in chunks while the keys are listed, so huge namespaces are never held in
+// memory at once.
+pub fn namespace_pair_chunks<'a>(
+    kv: &'a KvClient,
+    account_id: &'a str,
+    namespace_id: &'a str,
+) -> impl Stream<Item = Result<NamespacePairsChunk, KvError>> + Send + 'a {
+    kv.list_all_keys(keys_list_input(account_id, namespace_id))
+        .map_ok(|key| key.name)
+        .try_chunks(PAIRS_CHUNK_SIZE)
+        .map_err(|error| error.1)
+        .and_then(move |keys| async move {
+            let listed_key_count = keys.len();
+            let pairs = kv
+                .find_kv_pairs(KvPairsGetInput {
+                    account_id: account_id.to_string(),
+                    namespace_id: namespace_id.to_string(),
+                    keys,
+                })
+                .await?;
+            Ok(NamespacePairsChunk {
+                listed_key_count,
+                pairs,
+            })
+        })
+}
+
+// Keys deleted while the namespace is copied are not copied.
+pub async fn copy_namespace_pairs(
+    kv: &KvClient,
+    account_id: &str,
+    source_namespace_id: &str,
+    target_namespace_id: &str,
+) -> Result<KvNamespaceCopyResult, KvError> {
+    let mut result = KvNamespaceCopyResult::default();
+    let mut chunks = pin!(namespace_pair_chunks(kv, account_id, source_namespace_id));
+    while let Some(chunk) = chunks.try_next().await? {
+        let chunk_result = copy_chunk(kv, account_id, target_namespace_id, chunk.pairs).await?;
+        merge(&mut result, chunk_result);
+    }
+
+    Ok(result)
+}
+
+async fn copy_chunk(
+    kv: &KvClient,
+    account_id: &str,
+    target_namespace_id: &str,
+    pairs: Vec<KvPair>,
+) -> Result<KvNamespaceCopyResult, KvError> {
+    let (writes, skipped_keys) = copy_writes(pairs);
+    let (chunks, too_large) = bulk_write_chunks(writes);
+    let mut result = KvNamespaceCopyResult {
+        skipped_keys,
+        unsuccessful_keys: too_large.into_iter().map(|pair| pair.key).collect(),
+        ..KvNamespaceCopyResult::default()
+    };
+    for pairs in chunks {
+        let KvPairsWriteResult {
+            successful_key_count,
+            unsuccessful_keys,
+        } = kv
+            .write_kv_pairs(KvPairsWriteInput {
+                account_id: account_id.to_string(),
+                namespace_id: target_namespace_id.to_string(),
+                pairs,
+            })
+            .await?;
+        result.copied_key_count += successful_key_count;
+        result.unsuccessful_keys.extend(unsuccessful_keys);
+    }
+
+    Ok(result)
+}
+
+fn copy_writes(pairs: Vec<KvPair>) -> (Vec<KvPairBulkWriteInput>, Vec<String>) {
+    let now = Utc::now();
+    let mut writes = vec![];
+    let mut skipped_keys = vec![];
+    for pair in pairs {
+        if pair.expires_too_soon(now) {
+            skipped_keys.push(pair.key);
+            continue;
+        }
+
+        writes.push(KvPairBulkWriteInput {
+            key: pair.key,
+            value: KvPairValue::Binary(pair.value),
+            expiration: pair.expiration,
+            expiration_ttl: None,
+            metadata: pair.metadata,
+            base64: None,
+        });
+    }
+
+    (writes, skipped_keys)
+}
+
+fn merge(result: &mut KvNamespaceCopyResult, chunk_result: KvNamespaceCopyResult) {
+    result.copied_key_count += chunk_result.copied_key_count;
+    result.skipped_keys.extend(chunk_result.skipped_keys);
+    result
+        .unsuccessful_keys
+        .extend(chunk_result.unsuccessful_keys);
+}
+
+fn keys_list_input(account_id: &str, namespace_id: &str) -> KvKeysListInput {
+    KvKeysListInput {
+        account_id: account_id.to_string(),
+        namespace_id: namespace_id.to_string(),
+        cursor: None,
+        limit: None,
+        prefix: None,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{
+        KvNamespaceCopyResult, KvNamespaceRename, copy_namespace_pairs, copy_writes, plan_renames,
+    };
+    use crate::cloudflare::common::ApiResponse;
+    use crate::cloudflare::kv::{KvError, KvNamespace, KvPair, KvPairValue, KvPairsWriteResult};
+    use crate::test_support::create_kv_client;
+    use chrono::{TimeDelta, Utc};
+    use regex::Regex;
+    use serde_json::{Value, json};
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    const NAMESPACES_PATH: &str = "/client/v4/accounts/account_id/storage/kv/namespaces";
+
+    #[test]
+    fn should_plan_the_renames_of_matching_titles() {
+        let namespaces = vec![
+            create_namespace("namespace1", "sessions-staging"),
+            create_namespace("namespace2", "config-staging"),
+            create_namespace("namespace3", "sessions-production"),
+            create_namespace("namespace4", "users-staging"),
+        ];
+        let pattern = Regex::new("^(?<name>.+)-staging$").unwrap();
+        let namespace_ids = vec![
+            "namespace1".to_string(),
+            "namespace2".to_string(),
+            "namespace3".to_string(),
+        ];
+
+        let renames = plan_renames(
+            &namespaces,
+            &pattern,
+            "${name}-dev-alice",
+            Some(&namespace_ids),
+        );
+
+        assert_eq!(
+            renames,
+            vec![
+                KvNamespaceRename {
+                    namespace_id: "namespace1".to_string(),
+                    title: "sessions-staging".to_string(),
+                    new_title: "sessions-dev-alice".to_string(),
+                },
+                KvNamespaceRename {
+                    namespace_id: "namespace2".to_string(),
+                    title: "config-staging".to_string(),
+                    new_title: "config-dev-alice".to_string(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn should_skip_pairs_which_expire_before_they_are_copied() {
+        let expiration = Utc::now() + TimeDelta::hours(1);
+        let pairs = vec![
+            create_pair("fresh", Some(expiration)),
+            create_pair("expiring", Some(Utc::now() + TimeDelta::seconds(10))),
+            create_pair("permanent", None),
+        ];
+
+        let (writes, skipped_keys) = copy_writes(pairs);
+
+        assert_eq!(skipped_keys, vec!["expiring".to_string()]);
+        assert_eq!(writes.len(), 2);
+        assert_eq!(writes[0].key, "fresh");
+        assert_eq!(writes[0].expiration, Some(expiration));
+        assert_eq!(writes[0].value, KvPairValue::Binary(b"value".to_vec()));
+        assert_eq!(writes[1].expiration, None);
+    }
+
+    #[tokio::test]
+    async fn should_copy_the_metadata_and_expiration_of_every_pair() -> Result<(), KvError> {
+        let expiration = (Utc::now() + TimeDelta::hours(1)).timestamp();
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path(format!("{NAMESPACES_PATH}/source/keys")))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "success": true,
+                "errors": [],
+                "messages": [],
+                "result": [{"name": "key1"}, {"name": "key2"}, {"name": "key3"}],
+                "result_info": {"count": 3, "cursor": ""},
+            })))
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path(format!("{NAMESPACES_PATH}/source/bulk/get")))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "success": true,
+                "errors": [],
+                "messages": [],
+                "result": {"values": {
+                    "key1": {
+                        "value": "value1",
+                        "metadata": {"owner": "team"},
+                        "expiration": expiration,
+                    },
+                    "key2": {"value": "value2", "metadata": null},
+                    "key3": {
+                        "value": "value3",
+                        "metadata": null,
+                        "expiration": Utc::now().timestamp() + 10,
+                    },
+                }},
+            })))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("PUT"))
+            .and(path(format!("{NAMESPACES_PATH}/target/bulk")))
+            .respond_with(ResponseTemplate::new(200).set_body_json(ApiResponse {
+                result: KvPairsWriteResult {
+                    successful_key_count: 2,
+                    unsuccessful_keys: vec![],
+                },
+            }))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+        let kv = create_kv_client(&mock_server);
+
+        let result = copy_namespace_pairs(&kv, "account_id", "source", "target").await?;
+
+        assert_eq!(
+            result,
+            KvNamespaceCopyResult {
+                copied_key_count: 2,
+                skipped_keys: vec!["key3".to_string()],
+                unsuccessful_keys: vec![],
+            }
+        );
+        let requests = mock_server.received_requests().await.unwrap();
+        let write_request = requests
+            .iter()
+            .find(|request| request.method.as_str() == "PUT")
+            .unwrap();
+        let writes: Value = serde_json::from_slice(&write_request.body).unwrap();
+        assert_eq!(writes.as_array().unwrap().len(), 2);
+        assert_eq!(writes[0]["key"], "key1");
+        assert_eq!(writes[0]["value"], "value1");
+        assert_eq!(writes[0]["metadata"], json!({"owner": "team"}));
+        assert_eq!(writes[0]["expiration"], expiration);
+        assert_eq!(writes[1]["key"], "key2");
+        assert_eq!(writes[1]["expiration"], Value::Null);
+        Ok(())
+    }
+
+    fn create_namespace(id: &str, title: &str) -> KvNamespace {
+        KvNamespace {
+            id: id.to_string(),
+            title: title.to_string(),
+            beta: None,
+            supports_url_encoding: None,
+        }
+    }
+
+    fn create_pair(key: &str, expiration: Option<chrono::DateTime<Utc>>) -> KvPair {
+        KvPair {
+            key: key.to_string(),
+            value: b"value".to_vec(),
+            metadata: None,
+            expiration,
+        }
+    }
+}
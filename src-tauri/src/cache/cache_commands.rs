@@ -0,0 +1,38 @@
+use crate::cache::kv_cache::{KvCache, KvCacheConfig, KvCacheError, KvCacheStatus};
+use log::error;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tauri::State;
+
+#[tauri::command]
+pub fn get_kv_cache_status(cache: State<'_, Arc<KvCache>>) -> KvCacheStatus {
+    cache.status()
+}
+
+#[tauri::command]
+pub fn update_kv_cache_config(
+    cache: State<'_, Arc<KvCache>>,
+    config: KvCacheConfig,
+) -> Result<KvCacheStatus, CacheCommandError> {
+    Ok(cache.reconfigure(config)?)
+}
+
+#[tauri::command]
+pub fn clear_kv_cache(cache: State<'_, Arc<KvCache>>) -> Result<KvCacheStatus, CacheCommandError> {
+    cache.clear()?;
+    Ok(cache.status())
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CacheCommandError {
+    message: String,
+}
+
+impl From<KvCacheError> for CacheCommandError {
+    fn from(error: KvCacheError) -> Self {
+        error!("A cache error occurred: {error}");
+        CacheCommandError {
+            message: "The cache could not be updated".to_string(),
+        }
+    }
+}
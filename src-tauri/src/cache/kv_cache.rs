@@ -0,0 +1,500 @@
+use chrono::{DateTime, Utc};
+use log::warn;
+use ring::digest::{SHA256, digest};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::fmt::{Display, Formatter};
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, MutexGuard};
+
+pub const KV_CACHE_DIRECTORY_NAME: &str = "kv-cache";
+const INDEX_FILE_NAME: &str = "index.json";
+
+// Reads only touch the index in memory. It is saved with the next write, or by a read once this
+// many seconds passed since the first unsaved one.
+const UNSAVED_READS_SECS: i64 = 60;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct KvCacheConfig {
+    pub enabled: bool,
+    pub max_size_bytes: u64,
+    pub stale_after_secs: i64,
+}
+
+impl Default for KvCacheConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            max_size_bytes: 100 * 1024 * 1024,
+            stale_after_secs: 5 * 60,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct KvCacheStatus {
+    pub config: KvCacheConfig,
+    pub entry_count: usize,
+    pub size_bytes: u64,
+}
+
+pub struct Cached<T> {
+    pub value: T,
+    pub fetched_at: DateTime<Utc>,
+    pub is_stale: bool,
+}
+
+// Keeps namespace listings, key pages and values on disk. Every entry is stored in its own
+// file, the index tracks their sizes and when they were fetched and last read.
+pub struct KvCache {
+    directory: PathBuf,
+    state: Mutex<KvCacheState>,
+}
+
+struct KvCacheState {
+    config: KvCacheConfig,
+    index: Option<HashMap<String, IndexEntry>>,
+    generation: u64,
+    unsaved_reads_since: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct IndexEntry {
+    file_name: String,
+    size_bytes: u64,
+    fetched_at: DateTime<Utc>,
+    last_read_at: DateTime<Utc>,
+}
+
+impl KvCache {
+    pub fn new(directory: PathBuf, config: KvCacheConfig) -> Self {
+        Self {
+            directory,
+            state: Mutex::new(KvCacheState {
+                config,
+                index: None,
+                generation: 0,
+                unsaved_reads_since: None,
+            }),
+        }
+    }
+
+    pub fn status(&self) -> KvCacheStatus {
+        let mut state = self.lock_state();
+        let config = state.config.clone();
+        let index = self.index(&mut state);
+        KvCacheStatus {
+            config,
+            entry_count: index.len(),
+            size_bytes: index.values().map(|entry| entry.size_bytes).sum(),
+        }
+    }
+
+    // Disabling the cache removes everything it holds.
+    pub fn reconfigure(&self, config: KvCacheConfig) -> Result<KvCacheStatus, KvCacheError> {
+        {
+            let mut state = self.lock_state();
+            state.config = config;
+            if state.config.enabled {
+                self.evict(&mut state)?;
+            } else {
+                self.remove_matching(&mut state, |_| true)?;
+            }
+        }
+
+        Ok(self.status())
+    }
+
+    // Writes and deletes change the generation, so fetches started before them are not cached.
+    pub fn generation(&self) -> u64 {
+        self.lock_state().generation
+    }
+
+    pub fn get<T: DeserializeOwned>(&self, key: &str) -> Option<Cached<T>> {
+        let mut state = self.lock_state();
+        if !state.config.enabled {
+            return None;
+        }
+
+        let stale_after = chrono::Duration::seconds(state.config.stale_after_secs);
+        let file_name = self.index(&mut state).get(key)?.file_name.clone();
+        let value = fs::read(self.directory.join(file_name))
+            .ok()
+            .and_then(|content| serde_json::from_slice(&content).ok());
+        let Some(value) = value else {
+            warn!("The cache entry {key} could not be read and is removed");
+            let _ = self.remove_matching(&mut state, |entry_key| entry_key == key);
+            return None;
+        };
+
+        let entry = self.index(&mut state).get_mut(key)?;
+        let now = Utc::now();
+        entry.last_read_at = now;
+        let cached = Cached {
+            value,
+            fetched_at: entry.fetched_at,
+            is_stale: now - entry.fetched_at >= stale_after,
+        };
+
+        let unsaved_reads_since = *state.unsaved_reads_since.get_or_insert(now);
+        if now - unsaved_reads_since >= chrono::Duration::seconds(UNSAVED_READS_SECS)
+            && let Err(error) = self.save_index(&mut state)
+        {
+            warn!("The cache index could not be saved: {error}");
+        }
+        Some(cached)
+    }
+
+    pub fn put<T: Serialize>(
+        &self,
+        key: &str,
+        value: &T,
+        generation: u64,
+    ) -> Result<(), KvCacheError> {
+        self.put_many([(key.to_string(), value)], generation)
+    }
+
+    // Stores all entries with a single write of the index.
+    pub fn put_many<'a, T: Serialize + 'a>(
+        &self,
+        entries: impl IntoIterator<Item = (String, &'a T)>,
+        generation: u64,
+    ) -> Result<(), KvCacheError> {
+        let mut state = self.lock_state();
+        if !state.config.enabled || state.generation != generation {
+            return Ok(());
+        }
+
+        let mut changed = false;
+        for (key, value) in entries {
+            let content = serde_json::to_vec(value)?;
+            if content.len() as u64 > state.config.max_size_bytes {
+                continue;
+            }
+
+            let file_name = file_name(&key);
+            write_atomically(&self.directory.join(&file_name), &content)?;
+
+            let now = Utc::now();
+            self.index(&mut state).insert(
+                key,
+                IndexEntry {
+                    file_name,
+                    size_bytes: content.len() as u64,
+                    fetched_at: now,
+                    last_read_at: now,
+                },
+            );
+            changed = true;
+        }
+        if !changed {
+            return Ok(());
+        }
+
+        self.evict(&mut state)?;
+        self.save_index(&mut state)
+    }
+
+    pub fn invalidate(&self, key_prefix: &str) -> Result<(), KvCacheError> {
+        let mut state = self.lock_state();
+        state.generation += 1;
+        self.remove_matching(&mut state, |key| key.starts_with(key_prefix))
+    }
+
+    pub fn clear(&self) -> Result<(), KvCacheError> {
+        let mut state = self.lock_state();
+        state.generation += 1;
+        self.remove_matching(&mut state, |_| true)
+    }
+
+    fn index<'a>(&self, state: &'a mut KvCacheState) -> &'a mut HashMap<String, IndexEntry> {
+        state
+            .index
+            .get_or_insert_with(|| match fs::read(self.directory.join(INDEX_FILE_NAME)) {
+                Ok(content) => serde_json::from_slice(&content).unwrap_or_else(|error| {
+                    warn!("The cache index could not be read, the cache starts empty: {error}");
+                    HashMap::new()
+                }),
+                Err(_) => HashMap::new(),
+            })
+    }
+
+    // Removes the least recently read entries until the cache fits into its size again.
+    fn evict(&self, state: &mut KvCacheState) -> Result<(), KvCacheError> {
+        let max_size_bytes = state.config.max_size_bytes;
+        let index = self.index(state);
+        let mut size_bytes: u64 = index.values().map(|entry| entry.size_bytes).sum();
+        if size_bytes <= max_size_bytes {
+            return Ok(());
+        }
+
+        let mut entries: Vec<(String, DateTime<Utc>, u64)> = index
+            .iter()
+            .map(|(key, entry)| (key.clone(), entry.last_read_at, entry.size_bytes))
+            .collect();
+        entries.sort_by_key(|(_, last_read_at, _)| *last_read_at);
+
+        let mut evicted_keys = vec![];
+        for (key, _, entry_size_bytes) in entries {
+            if size_bytes <= max_size_bytes {
+                break;
+            }
+            size_bytes -= entry_size_bytes;
+            evicted_keys.push(key);
+        }
+
+        self.remove_matching(state, |key| {
+            evicted_keys.iter().any(|evicted| evicted == key)
+        })
+    }
+
+    fn remove_matching<F>(&self, state: &mut KvCacheState, matches: F) -> Result<(), KvCacheError>
+    where
+        F: Fn(&str) -> bool,
+    {
+        let index = self.index(state);
+        let removed_keys: Vec<String> = index.keys().filter(|key| matches(key)).cloned().collect();
+        if removed_keys.is_empty() {
+            return Ok(());
+        }
+
+        for key in removed_keys {
+            if let Some(entry) = index.remove(&key) {
+                remove_file_if_exists(&self.directory.join(entry.file_name))?;
+            }
+        }
+
+        self.save_index(state)
+    }
+
+    fn save_index(&self, state: &mut KvCacheState) -> Result<(), KvCacheError> {
+        let content = serde_json::to_vec(self.index(state))?;
+        write_atomically(&self.directory.join(INDEX_FILE_NAME), &content)?;
+        state.unsaved_reads_since = None;
+        Ok(())
+    }
+
+    fn lock_state(&self) -> MutexGuard<'_, KvCacheState> {
+        self.state
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+pub fn namespaces_cache_key<T: Serialize>(account_id: &str, input: &T) -> String {
+    format!(
+        "{}{}",
+        namespaces_cache_prefix(account_id),
+        serde_json::to_string(input).unwrap_or_default()
+    )
+}
+
+pub fn namespaces_cache_prefix(account_id: &str) -> String {
+    format!("{account_id}/namespaces/")
+}
+
+pub fn keys_cache_key<T: Serialize>(account_id: &str, namespace_id: &str, input: &T) -> String {
+    format!(
+        "{}keys/{}",
+        namespace_cache_prefix(account_id, namespace_id),
+        serde_json::to_string(input).unwrap_or_default()
+    )
+}
+
+pub fn value_cache_key(account_id: &str, namespace_id: &str, key: &str) -> String {
+    format!(
+        "{}values/{key}",
+        namespace_cache_prefix(account_id, namespace_id)
+    )
+}
+
+// Statistics are kept apart from the keys and values, so writes to the namespace do not drop them.
+pub fn statistics_cache_key(account_id: &str, namespace_id: &str, delimiter: &str) -> String {
+    format!("{account_id}/statistics/{namespace_id}/{delimiter}")
+}
+
+// Everything cached about the keys and values of a namespace starts with this prefix.
+pub fn namespace_cache_prefix(account_id: &str, namespace_id: &str) -> String {
+    format!("{account_id}/{namespace_id}/")
+}
+
+fn file_name(key: &str) -> String {
+    let hash = digest(&SHA256, key.as_bytes());
+    let hex: String = hash
+        .as_ref()
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect();
+    format!("{hex}.json")
+}
+
+fn write_atomically(path: &Path, content: &[u8]) -> Result<(), KvCacheError> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    let temporary_path = path.with_extension("tmp");
+    fs::write(&temporary_path, content)?;
+    fs::rename(&temporary_path, path)?;
+    Ok(())
+}
+
+fn remove_file_if_exists(path: &Path) -> Result<(), KvCacheError> {
+    match fs::remove_file(path) {
+        Err(error) if error.kind() != ErrorKind::NotFound => Err(error.into()),
+        _ => Ok(()),
+    }
+}
+
+#[derive(Debug)]
+pub enum KvCacheError {
+    Io(std::io::Error),
+    Serialization(serde_json::Error),
+}
+
+impl From<std::io::Error> for KvCacheError {
+    fn from(error: std::io::Error) -> Self {
+        KvCacheError::Io(error)
+    }
+}
+
+impl From<serde_json::Error> for KvCacheError {
+    fn from(error: serde_json::Error) -> Self {
+        KvCacheError::Serialization(error)
+    }
+}
+
+impl Error for KvCacheError {}
+
+impl Display for KvCacheError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match &self {
+            KvCacheError::Io(err) => write!(f, "IO error: {}", err),
+            KvCacheError::Serialization(err) => write!(f, "Serialization error: {}", err),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{KvCache, KvCacheConfig, KvCacheError, namespace_cache_prefix, value_cache_key};
+    use crate::test_support::create_temp_dir;
+    use std::fs;
+    use std::path::PathBuf;
+
+    #[test]
+    fn should_serve_cached_values_after_a_restart() -> Result<(), KvCacheError> {
+        let directory = create_cache_directory();
+        let key = value_cache_key("account_id", "namespace_id", "key1");
+
+        let cache = KvCache::new(directory.clone(), KvCacheConfig::default());
+        cache.put(&key, &"value1".to_string(), cache.generation())?;
+
+        let restarted_cache = KvCache::new(directory.clone(), KvCacheConfig::default());
+        let cached = restarted_cache.get::<String>(&key).unwrap();
+        assert_eq!(cached.value, "value1");
+        assert!(!cached.is_stale);
+
+        fs::remove_dir_all(directory.parent().unwrap())?;
+        Ok(())
+    }
+
+    #[test]
+    fn should_mark_old_entries_as_stale() -> Result<(), KvCacheError> {
+        let directory = create_cache_directory();
+        let cache = KvCache::new(
+            directory.clone(),
+            KvCacheConfig {
+                stale_after_secs: 0,
+                ..KvCacheConfig::default()
+            },
+        );
+        cache.put("key", &1, cache.generation())?;
+
+        assert!(cache.get::<u32>("key").unwrap().is_stale);
+
+        fs::remove_dir_all(directory.parent().unwrap())?;
+        Ok(())
+    }
+
+    #[test]
+    fn should_evict_the_least_recently_read_entries() -> Result<(), KvCacheError> {
+        let directory = create_cache_directory();
+        let cache = KvCache::new(
+            directory.clone(),
+            KvCacheConfig {
+                max_size_bytes: 20,
+                ..KvCacheConfig::default()
+            },
+        );
+        cache.put("first", &"12345678", cache.generation())?;
+        cache.put("second", &"12345678", cache.generation())?;
+        cache.get::<String>("first");
+        cache.put("third", &"12345678", cache.generation())?;
+
+        assert!(cache.get::<String>("first").is_some());
+        assert!(cache.get::<String>("second").is_none());
+        assert!(cache.get::<String>("third").is_some());
+        assert_eq!(cache.status().entry_count, 2);
+
+        fs::remove_dir_all(directory.parent().unwrap())?;
+        Ok(())
+    }
+
+    #[test]
+    fn should_keep_the_read_times_over_a_restart() -> Result<(), KvCacheError> {
+        let directory = create_cache_directory();
+        let config = KvCacheConfig {
+            max_size_bytes: 30,
+            ..KvCacheConfig::default()
+        };
+        let cache = KvCache::new(directory.clone(), config.clone());
+        cache.put_many(
+            [
+                ("first".to_string(), &"12345678"),
+                ("second".to_string(), &"12345678"),
+            ],
+            cache.generation(),
+        )?;
+        cache.get::<String>("first");
+        cache.put("small", &"", cache.generation())?;
+
+        let restarted_cache = KvCache::new(directory.clone(), config);
+        restarted_cache.put("third", &"12345678", restarted_cache.generation())?;
+
+        assert!(restarted_cache.get::<String>("first").is_some());
+        assert!(restarted_cache.get::<String>("second").is_none());
+
+        fs::remove_dir_all(directory.parent().unwrap())?;
+        Ok(())
+    }
+
+    #[test]
+    fn should_invalidate_a_namespace_and_skip_fetches_started_before() -> Result<(), KvCacheError> {
+        let directory = create_cache_directory();
+        let cache = KvCache::new(directory.clone(), KvCacheConfig::default());
+        let key = value_cache_key("account_id", "namespace_id", "key1");
+        let other_key = value_cache_key("account_id", "other_namespace_id", "key1");
+        cache.put(&key, &"value1", cache.generation())?;
+        cache.put(&other_key, &"value1", cache.generation())?;
+
+        let generation = cache.generation();
+        cache.invalidate(&namespace_cache_prefix("account_id", "namespace_id"))?;
+        cache.put(&key, &"outdated value", generation)?;
+
+        assert!(cache.get::<String>(&key).is_none());
+        assert!(cache.get::<String>(&other_key).is_some());
+
+        fs::remove_dir_all(directory.parent().unwrap())?;
+        Ok(())
+    }
+
+    fn create_cache_directory() -> PathBuf {
+        create_temp_dir().join("kv-cache")
+    }
+}
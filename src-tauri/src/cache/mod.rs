@@ -0,0 +1,2 @@
+pub mod cache_commands;
+pub mod kv_cache;
@@ -1,100 +1,177 @@
-use crate::cloudflare::account::{Account, AccountError};
+use crate::cloudflare::account::{Account, AccountError, Accounts, AccountsListInput};
 use crate::cloudflare::common::{
-    ApiError, ApiErrorResponse, ApiResponse, Credentials, Token, TokenError, API_URL,
+    ApiPaginatedResponse, ApiResponse, Token, TokenDetails, Transport,
 };
-use reqwest::{Response, StatusCode};
-use serde::Deserialize;
+use std::collections::HashMap;
 use std::sync::Arc;
 
 pub struct AccountClient {
-    api_url: Arc<String>,
-    credentials: Arc<Credentials>,
-    http_client: Arc<reqwest::Client>,
+    transport: Arc<Transport>,
 }
 
 impl AccountClient {
-    pub fn new(
-        credentials: Arc<Credentials>,
-        api_url: Option<Arc<String>>,
-        http_client: Option<Arc<reqwest::Client>>,
-    ) -> Self {
-        Self {
-            api_url: api_url.unwrap_or(Arc::new(API_URL.to_string())),
-            credentials,
-            http_client: http_client.unwrap_or_default(),
-        }
+    pub fn new(transport: Arc<Transport>) -> Self {
+        Self { transport }
     }
 
-    pub async fn get_account(&self, account_id: &str) -> Result<Account, AccountError> {
-        let url = format!("{}/accounts/{}", self.api_url, account_id,);
-
-        let response = self
-            .http_client
-            .get(&url)
-            .headers(self.credentials.headers())
-            .send()
-            .await?;
+    pub async fn list_accounts(&self, input: AccountsListInput) -> Result<Accounts, AccountError> {
+        let query_parameters: HashMap<String, String> = input.into();
+        self.transport
+            .get("/accounts")
+            .query(&query_parameters)
+            .fetch::<ApiPaginatedResponse<Vec<Account>>, _, _>()
+            .await
+    }
 
-        self.handle_api_response::<ApiResponse<Account>, Account>(response)
+    pub async fn get_account(&self, account_id: &str) -> Result<Account, AccountError> {
+        self.transport
+            .get(&format!("/accounts/{}", account_id))
+            .fetch::<ApiResponse<Account>, _, _>()
             .await
     }
 
     pub async fn verify_token(&self, account_id: &str) -> Result<Token, AccountError> {
-        let url = format!("{}/accounts/{}/tokens/verify", self.api_url, account_id);
-
-        let response = self
-            .http_client
-            .get(&url)
-            .headers(self.credentials.headers())
-            .send()
-            .await?;
-
-        self.handle_api_response::<ApiResponse<Token>, Token>(response)
+        self.transport
+            .get(&format!("/accounts/{}/tokens/verify", account_id))
+            .fetch::<ApiResponse<Token>, _, _>()
             .await
     }
 
-    async fn handle_api_response<T: for<'a> Deserialize<'a>, R: From<T>>(
+    pub async fn get_token_details(
         &self,
-        response: Response,
-    ) -> Result<R, AccountError> {
-        match response.status() {
-            StatusCode::OK => {
-                let api_result: T = response.json().await?;
-                Ok(api_result.into())
-            }
-            _ => Err(self.handle_api_error_response(response).await),
-        }
+        account_id: &str,
+        token_id: &str,
+    ) -> Result<TokenDetails, AccountError> {
+        self.transport
+            .get(&format!("/accounts/{}/tokens/{}", account_id, token_id))
+            .fetch::<ApiResponse<TokenDetails>, _, _>()
+            .await
     }
+}
 
-    async fn handle_api_error_response(&self, response: Response) -> AccountError {
-        let api_response_result = response.json::<ApiErrorResponse>().await;
-        match api_response_result {
-            Ok(api_response) => self.map_api_errors(api_response.errors),
-            Err(error) => error.into(),
-        }
-    }
+#[cfg(test)]
+mod test {
+    use crate::cloudflare::common::{Credentials, Transport};
+    use std::sync::Arc;
 
-    fn map_api_errors(&self, errors: Vec<ApiError>) -> AccountError {
-        if errors.is_empty() {
-            return AccountError::Unknown("No errors in the response.".to_string());
+    mod list_accounts {
+        use crate::cloudflare::account::account_client::test::create_account_client;
+        use crate::cloudflare::account::{Account, AccountError, Accounts, AccountsListInput};
+        use crate::cloudflare::common::{ApiPaginatedResponse, PageInfo};
+        use wiremock::matchers::{method, path, query_param};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        #[tokio::test]
+        async fn should_list_accounts() -> Result<(), AccountError> {
+            let expected_accounts = Accounts {
+                items: vec![
+                    Account {
+                        id: "12345".to_string(),
+                        name: "First Account".to_string(),
+                        created_on: None,
+                        settings: None,
+                    },
+                    Account {
+                        id: "67890".to_string(),
+                        name: "Second Account".to_string(),
+                        created_on: None,
+                        settings: None,
+                    },
+                ],
+                page_info: PageInfo {
+                    count: 2,
+                    page: 2,
+                    per_page: 2,
+                    total_count: 4,
+                },
+            };
+            let mock_server = create_succeeding_mock_server(&expected_accounts).await;
+
+            let account_client = create_account_client(&mock_server.uri());
+            let accounts = account_client
+                .list_accounts(AccountsListInput {
+                    page: Some(2),
+                    per_page: Some(2),
+                    ..AccountsListInput::default()
+                })
+                .await?;
+
+            assert_eq!(accounts, expected_accounts);
+
+            Ok(())
         }
 
-        let error = &errors[0];
-        match error.code {
-            1000 => AccountError::Token(TokenError::Invalid),
-            1001 => AccountError::Token(TokenError::Invalid),
-            6003 => AccountError::Token(TokenError::Invalid),
-            7003 => AccountError::InvalidAccountId,
-            9109 => AccountError::InvalidAccountId,
-            _ => AccountError::Unknown(error.message.clone()),
+        async fn create_succeeding_mock_server(accounts: &Accounts) -> MockServer {
+            let mock_server = MockServer::start().await;
+
+            let response_template =
+                ResponseTemplate::new(200).set_body_json(ApiPaginatedResponse::<Vec<Account>> {
+                    result: accounts.items.clone(),
+                    result_info: accounts.page_info.clone(),
+                });
+
+            Mock::given(method("GET"))
+                .and(path("/client/v4/accounts"))
+                .and(query_param("page", "2"))
+                .and(query_param("per_page", "2"))
+                .respond_with(response_template)
+                .mount(&mock_server)
+                .await;
+
+            mock_server
         }
     }
-}
 
-#[cfg(test)]
-mod test {
-    use crate::cloudflare::common::Credentials;
-    use std::sync::Arc;
+    mod get_token_details {
+        use crate::cloudflare::account::AccountError;
+        use crate::cloudflare::account::account_client::test::create_account_client;
+        use crate::cloudflare::common::{KvAccess, TokenPolicyEffect, TokenStatus};
+        use chrono::Utc;
+        use serde_json::json;
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        #[tokio::test]
+        async fn should_get_token_details_with_policies() -> Result<(), AccountError> {
+            let mock_server = MockServer::start().await;
+            Mock::given(method("GET"))
+                .and(path("/client/v4/accounts/12345/tokens/token-id"))
+                .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                    "result": {
+                        "id": "token-id",
+                        "name": "KV Token",
+                        "status": "active",
+                        "expires_on": "2100-01-01T00:00:00Z",
+                        "policies": [{
+                            "id": "policy-id",
+                            "effect": "allow",
+                            "permission_groups": [{
+                                "id": "permission-group-id",
+                                "name": "Workers KV Storage Read"
+                            }],
+                            "resources": { "com.cloudflare.api.account.12345": "*" }
+                        }],
+                        "condition": { "request_ip": { "in": ["192.0.2.0/24"] } }
+                    }
+                })))
+                .mount(&mock_server)
+                .await;
+
+            let account_client = create_account_client(&mock_server.uri());
+            let token_details = account_client
+                .get_token_details("12345", "token-id")
+                .await?;
+
+            assert_eq!(token_details.status, TokenStatus::Active);
+            assert_eq!(token_details.policies[0].effect, TokenPolicyEffect::Allow);
+
+            let token_capabilities = token_details.capabilities("12345", Utc::now());
+            assert_eq!(token_capabilities.kv_access, KvAccess::Read);
+            assert!(token_capabilities.ip_restricted);
+
+            Ok(())
+        }
+    }
 
     mod get_account {
         use crate::cloudflare::account::account_client::test::create_account_client;
@@ -137,7 +214,7 @@ mod test {
             let account_client = create_account_client(&mock_server.uri());
             let result = account_client.get_account(account_id).await;
 
-            assert!(matches!(result, Err(AccountError::InvalidAccountId)));
+            assert!(matches!(result, Err(AccountError::InvalidAccountId(_))));
 
             Ok(())
         }
@@ -165,8 +242,10 @@ mod test {
         ) -> MockServer {
             let mock_server = MockServer::start().await;
 
-            let response_template =
-                ResponseTemplate::new(400).set_body_json(ApiErrorResponse { errors });
+            let response_template = ResponseTemplate::new(400).set_body_json(ApiErrorResponse {
+                errors,
+                messages: vec![],
+            });
 
             Mock::given(method("GET"))
                 .and(path(format!("/client/v4/accounts/{account_id}")))
@@ -182,7 +261,7 @@ mod test {
         use crate::cloudflare::account::account_client::test::create_account_client;
         use crate::cloudflare::account::AccountError;
         use crate::cloudflare::common::{
-            ApiError, ApiErrorResponse, ApiResponse, Token, TokenError, TokenStatus,
+            ApiError, ApiErrorResponse, ApiResponse, Token, TokenStatus,
         };
         use wiremock::matchers::{method, path};
         use wiremock::{Mock, MockServer, ResponseTemplate};
@@ -224,7 +303,7 @@ mod test {
             let error = token_result.unwrap_err();
 
             assert!(
-                matches!(error, AccountError::Token(token_error) if token_error == TokenError::Invalid),
+                matches!(error, AccountError::Unauthorized(api_error) if api_error.code() == Some(1000)),
             );
 
             Ok(())
@@ -256,8 +335,10 @@ mod test {
         ) -> MockServer {
             let mock_server = MockServer::start().await;
 
-            let response_template =
-                ResponseTemplate::new(400).set_body_json(ApiErrorResponse { errors });
+            let response_template = ResponseTemplate::new(400).set_body_json(ApiErrorResponse {
+                errors,
+                messages: vec![],
+            });
 
             Mock::given(method("GET"))
                 .and(path(format!(
@@ -272,12 +353,15 @@ mod test {
     }
 
     pub fn create_account_client(host_url: &str) -> super::AccountClient {
-        super::AccountClient::new(
-            Arc::new(Credentials::UserAuthToken {
-                token: "12345".to_string(),
-            }),
-            Some(Arc::new(format!("{}/client/v4", host_url))),
+        super::AccountClient::new(Arc::new(Transport::new(
+            Credentials::UserAuthToken {
+                token: "12345".into(),
+            },
+            Some(format!("{}/client/v4", host_url)),
+            None,
+            None,
+            None,
             None,
-        )
+        )))
     }
 }
@@ -1,9 +1,14 @@
-use crate::cloudflare::common::{ApiResponse, TokenError};
+use crate::cloudflare::common::{
+    ApiErrorMapping, ApiPaginatedResponse, ApiResponse, CloudflareApiError, OrderDirection,
+    PageInfo, TokenError,
+};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::error::Error;
 use std::fmt;
 use std::fmt::{Display, Formatter};
+use std::time::Duration;
 
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct Account {
@@ -25,14 +30,63 @@ pub struct AccountSettings {
     pub enforce_twofactor: Option<bool>,
 }
 
+#[derive(Debug, PartialEq, Clone, Default, Serialize, Deserialize)]
+pub struct AccountsListInput {
+    pub name: Option<String>,
+    pub order_direction: Option<OrderDirection>,
+    pub page: Option<u32>,
+    pub per_page: Option<u32>,
+}
+
+impl From<AccountsListInput> for HashMap<String, String> {
+    fn from(value: AccountsListInput) -> Self {
+        let mut map = HashMap::new();
+        if let Some(name) = value.name {
+            map.insert("name".to_string(), name);
+        }
+
+        if let Some(order_direction) = value.order_direction {
+            map.insert("direction".to_string(), order_direction.to_string());
+        }
+
+        if let Some(page) = value.page {
+            map.insert("page".to_string(), page.to_string());
+        }
+
+        if let Some(per_page) = value.per_page {
+            map.insert("per_page".to_string(), per_page.to_string());
+        }
+
+        map
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct Accounts {
+    pub items: Vec<Account>,
+    pub page_info: PageInfo,
+}
+
+impl From<ApiPaginatedResponse<Vec<Account>>> for Accounts {
+    fn from(value: ApiPaginatedResponse<Vec<Account>>) -> Self {
+        Self {
+            items: value.result,
+            page_info: value.result_info,
+        }
+    }
+}
+
 #[derive(Debug)]
 pub enum AccountError {
-    InvalidAccountId,
+    InvalidAccountId(CloudflareApiError),
 
     Token(TokenError),
+    Unauthorized(CloudflareApiError),
 
+    RateLimited(Option<Duration>, CloudflareApiError),
+
+    Api(CloudflareApiError),
     Reqwest(reqwest::Error),
-    Unknown(String),
 }
 
 impl Error for AccountError {}
@@ -51,3 +105,26 @@ impl From<reqwest::Error> for AccountError {
         AccountError::Reqwest(err)
     }
 }
+
+impl ApiErrorMapping for AccountError {
+    fn rate_limited(retry_after: Option<Duration>, api_error: CloudflareApiError) -> Self {
+        AccountError::RateLimited(retry_after, api_error)
+    }
+
+    fn from_api_error(api_error: CloudflareApiError) -> Self {
+        match api_error.code() {
+            Some(1000) => AccountError::Unauthorized(api_error),
+            Some(1001) => AccountError::Unauthorized(api_error),
+            Some(6003) => AccountError::Unauthorized(api_error),
+            Some(7003) => AccountError::InvalidAccountId(api_error),
+            Some(9109) => AccountError::InvalidAccountId(api_error),
+            _ => AccountError::Api(api_error),
+        }
+    }
+}
+
+impl From<TokenError> for AccountError {
+    fn from(err: TokenError) -> Self {
+        AccountError::Token(err)
+    }
+}
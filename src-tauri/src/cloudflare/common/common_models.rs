@@ -1,8 +1,11 @@
+use crate::cloudflare::common::{RAY_ID_HEADER, Secret};
+use reqwest::Response;
 use reqwest::header::{HeaderMap, HeaderValue};
 use serde::{Deserialize, Serialize};
 use std::error::Error;
 use std::fmt;
 use std::fmt::Display;
+use zeroize::Zeroizing;
 
 #[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
 pub struct ApiPaginatedResponse<T> {
@@ -24,6 +27,8 @@ pub struct ApiResponse<T> {
 #[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
 pub struct ApiErrorResponse {
     pub errors: Vec<ApiError>,
+    #[serde(default)]
+    pub messages: Vec<ApiMessage>,
 }
 
 #[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
@@ -32,6 +37,75 @@ pub struct ApiError {
     pub message: String,
 }
 
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct ApiMessage {
+    pub code: Option<u16>,
+    pub message: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct CloudflareApiError {
+    pub status: u16,
+    pub ray_id: Option<String>,
+    pub errors: Vec<ApiError>,
+    pub messages: Vec<ApiMessage>,
+}
+
+impl CloudflareApiError {
+    pub async fn from_response(response: Response) -> Self {
+        let status = response.status().as_u16();
+        let ray_id = response
+            .headers()
+            .get(RAY_ID_HEADER)
+            .and_then(|header_value| header_value.to_str().ok())
+            .map(|ray_id| ray_id.to_string());
+
+        // Proxies in front of the API may answer with HTML, which leaves only the status and the ray ID.
+        let body = response.bytes().await.unwrap_or_default();
+        let (errors, messages) = match serde_json::from_slice::<ApiErrorResponse>(&body) {
+            Ok(api_response) => (api_response.errors, api_response.messages),
+            Err(_) => (vec![], vec![]),
+        };
+
+        Self {
+            status,
+            ray_id,
+            errors,
+            messages,
+        }
+    }
+
+    pub fn code(&self) -> Option<u16> {
+        self.errors.first().map(|error| error.code)
+    }
+
+    pub fn message(&self) -> String {
+        if self.errors.is_empty() {
+            return format!("The request failed with status {}", self.status);
+        }
+
+        self.errors
+            .iter()
+            .map(|error| error.message.as_str())
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
+
+impl Error for CloudflareApiError {}
+
+impl Display for CloudflareApiError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}, status: {}, ray ID: {}",
+            self.message(),
+            self.status,
+            self.ray_id.as_deref().unwrap_or("none")
+        )
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
 pub struct PageInfo {
     pub count: usize,
@@ -68,51 +142,65 @@ impl Display for OrderDirection {
 #[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
 #[serde(tag = "type")]
 pub enum Credentials {
-    UserAuthKey { email: String, key: String },
-    UserAuthToken { token: String },
-    AccountAuthToken { token: String },
-    Service { key: String },
+    UserAuthKey { email: String, key: Secret },
+    UserAuthToken { token: Secret },
+    AccountAuthToken { token: Secret },
+    Service { key: Secret },
 }
 
 impl Credentials {
-    pub fn headers(&self) -> HeaderMap {
+    pub fn headers(&self) -> Result<HeaderMap, TokenError> {
         let mut headers = HeaderMap::new();
 
         match self {
             Self::UserAuthKey { email, key } => {
-                headers.insert(
-                    "X-Auth-Email",
-                    HeaderValue::from_str(email).expect("Invalid email"),
-                );
-                headers.insert(
-                    "X-Auth-Key",
-                    HeaderValue::from_str(key).expect("Invalid auth key"),
-                );
+                headers.insert("X-Auth-Email", header_value(email)?);
+                headers.insert("X-Auth-Key", sensitive_header_value(key.expose())?);
             }
-            Self::UserAuthToken { token } => {
-                headers.insert(
-                    "Authorization",
-                    HeaderValue::from_str(&format!("Bearer {}", token)).expect("Invalid token"),
-                );
-            }
-            Self::AccountAuthToken { token } => {
-                headers.insert(
-                    "Authorization",
-                    HeaderValue::from_str(&format!("Bearer {}", token)).expect("Invalid token"),
-                );
+            Self::UserAuthToken { token } | Self::AccountAuthToken { token } => {
+                let bearer_token = Zeroizing::new(format!("Bearer {}", token.expose()));
+                headers.insert("Authorization", sensitive_header_value(&bearer_token)?);
             }
             Self::Service { key } => {
                 headers.insert(
                     "X-Auth-User-Service-Key",
-                    HeaderValue::from_str(key).expect("Invalid service key"),
+                    sensitive_header_value(key.expose())?,
                 );
             }
         }
 
-        headers
+        Ok(headers)
+    }
+
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Self::UserAuthKey { .. } => "UserAuthKey",
+            Self::UserAuthToken { .. } => "UserAuthToken",
+            Self::AccountAuthToken { .. } => "AccountAuthToken",
+            Self::Service { .. } => "Service",
+        }
+    }
+
+    // Enough of the secret to tell credentials apart, without revealing it.
+    pub fn hint(&self) -> String {
+        match self {
+            Self::UserAuthKey { email, key } => format!("{email} {}", key.hint()),
+            Self::UserAuthToken { token } | Self::AccountAuthToken { token } => token.hint(),
+            Self::Service { key } => key.hint(),
+        }
     }
 }
 
+fn header_value(value: &str) -> Result<HeaderValue, TokenError> {
+    HeaderValue::from_str(value).map_err(|_| TokenError::Malformed)
+}
+
+fn sensitive_header_value(value: &str) -> Result<HeaderValue, TokenError> {
+    let mut header_value = header_value(value)?;
+    header_value.set_sensitive(true);
+    Ok(header_value)
+}
+
 #[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
 pub struct Token {
     pub id: String,
@@ -137,8 +225,7 @@ pub enum TokenStatus {
 pub enum TokenError {
     Expired,
     Disabled,
-    Invalid,
-    Unknown(String),
+    Malformed,
 }
 
 impl Error for TokenError {}
@@ -148,3 +235,41 @@ impl Display for TokenError {
         write!(f, "TokenError: {:?}", self)
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::{Credentials, TokenError};
+
+    #[test]
+    fn should_redact_the_token_in_debug_output() {
+        let credentials = Credentials::UserAuthToken {
+            token: "my-secret-token".into(),
+        };
+
+        assert!(!format!("{:?}", credentials).contains("my-secret-token"));
+    }
+
+    #[test]
+    fn should_create_sensitive_authorization_header() -> Result<(), TokenError> {
+        let credentials = Credentials::AccountAuthToken {
+            token: "12345".into(),
+        };
+
+        let headers = credentials.headers()?;
+        let authorization = headers.get("Authorization").unwrap();
+
+        assert_eq!(authorization, "Bearer 12345");
+        assert!(authorization.is_sensitive());
+
+        Ok(())
+    }
+
+    #[test]
+    fn should_respond_with_a_malformed_error_for_invalid_header_characters() {
+        let credentials = Credentials::UserAuthToken {
+            token: "12345\n".into(),
+        };
+
+        assert_eq!(credentials.headers(), Err(TokenError::Malformed));
+    }
+}
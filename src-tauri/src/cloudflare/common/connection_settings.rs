@@ -0,0 +1,207 @@
+use crate::cloudflare::common::RetryPolicy;
+use reqwest::{Certificate, Proxy, Url};
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::fmt;
+use std::fmt::{Display, Formatter};
+use std::fs;
+use std::path::PathBuf;
+use std::time::Duration;
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ConnectionSettings {
+    pub api_url: Option<String>,
+    pub proxy_url: Option<String>,
+    pub ca_bundle_path: Option<PathBuf>,
+    pub connect_timeout_secs: Option<u64>,
+    pub request_timeout_secs: Option<u64>,
+
+    // Unset retry settings keep the defaults of the retry policy.
+    pub max_retries: Option<u32>,
+    pub initial_backoff_ms: Option<u64>,
+    pub max_backoff_ms: Option<u64>,
+}
+
+impl ConnectionSettings {
+    pub fn retry_policy(&self) -> RetryPolicy {
+        let default = RetryPolicy::default();
+        RetryPolicy {
+            max_retries: self.max_retries.unwrap_or(default.max_retries),
+            initial_backoff: self
+                .initial_backoff_ms
+                .map_or(default.initial_backoff, Duration::from_millis),
+            max_backoff: self
+                .max_backoff_ms
+                .map_or(default.max_backoff, Duration::from_millis),
+            ..default
+        }
+    }
+
+    pub fn build_http_client(&self) -> Result<reqwest::Client, ConnectionSettingsError> {
+        if let Some(api_url) = &self.api_url
+            && !Url::parse(api_url)
+                .is_ok_and(|url| url.scheme() == "https" || url.scheme() == "http")
+        {
+            return Err(ConnectionSettingsError::InvalidApiUrl(api_url.clone()));
+        }
+
+        let mut builder = reqwest::Client::builder();
+
+        if let Some(proxy_url) = &self.proxy_url {
+            let proxy = Proxy::all(proxy_url)
+                .map_err(|_| ConnectionSettingsError::InvalidProxyUrl(proxy_url.clone()))?;
+            builder = builder.proxy(proxy);
+        }
+
+        if let Some(ca_bundle_path) = &self.ca_bundle_path {
+            let ca_bundle = fs::read(ca_bundle_path)?;
+            let certificates = Certificate::from_pem_bundle(&ca_bundle).map_err(|_| {
+                ConnectionSettingsError::InvalidCaBundle(ca_bundle_path.display().to_string())
+            })?;
+            if certificates.is_empty() {
+                return Err(ConnectionSettingsError::InvalidCaBundle(
+                    ca_bundle_path.display().to_string(),
+                ));
+            }
+
+            for certificate in certificates {
+                builder = builder.add_root_certificate(certificate);
+            }
+        }
+
+        if let Some(connect_timeout_secs) = self.connect_timeout_secs {
+            builder = builder.connect_timeout(Duration::from_secs(connect_timeout_secs));
+        }
+
+        if let Some(request_timeout_secs) = self.request_timeout_secs {
+            builder = builder.timeout(Duration::from_secs(request_timeout_secs));
+        }
+
+        Ok(builder.build()?)
+    }
+}
+
+#[derive(Debug)]
+pub enum ConnectionSettingsError {
+    InvalidApiUrl(String),
+    InvalidProxyUrl(String),
+    InvalidCaBundle(String),
+    Io(std::io::Error),
+    Serialization(serde_json::Error),
+    Reqwest(reqwest::Error),
+}
+
+impl From<std::io::Error> for ConnectionSettingsError {
+    fn from(error: std::io::Error) -> Self {
+        ConnectionSettingsError::Io(error)
+    }
+}
+
+impl From<serde_json::Error> for ConnectionSettingsError {
+    fn from(error: serde_json::Error) -> Self {
+        ConnectionSettingsError::Serialization(error)
+    }
+}
+
+impl From<reqwest::Error> for ConnectionSettingsError {
+    fn from(error: reqwest::Error) -> Self {
+        ConnectionSettingsError::Reqwest(error)
+    }
+}
+
+impl Error for ConnectionSettingsError {}
+
+impl Display for ConnectionSettingsError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match &self {
+            ConnectionSettingsError::InvalidApiUrl(api_url) => {
+                write!(f, "{api_url} is not a valid API URL")
+            }
+            ConnectionSettingsError::InvalidProxyUrl(proxy_url) => {
+                write!(f, "{proxy_url} is not a valid proxy URL")
+            }
+            ConnectionSettingsError::InvalidCaBundle(path) => {
+                write!(f, "{path} does not contain PEM encoded certificates")
+            }
+            ConnectionSettingsError::Io(err) => write!(f, "IO error: {}", err),
+            ConnectionSettingsError::Serialization(err) => {
+                write!(f, "Serialization error: {}", err)
+            }
+            ConnectionSettingsError::Reqwest(err) => write!(f, "HTTP client error: {}", err),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{ConnectionSettings, ConnectionSettingsError};
+    use crate::cloudflare::common::RetryPolicy;
+    use crate::test_support::create_temp_dir;
+    use std::fs;
+    use std::time::Duration;
+
+    #[test]
+    fn should_build_a_client_with_proxy_and_timeouts() -> Result<(), ConnectionSettingsError> {
+        let settings = ConnectionSettings {
+            proxy_url: Some("http://proxy.example.com:8080".to_string()),
+            connect_timeout_secs: Some(5),
+            request_timeout_secs: Some(30),
+            ..ConnectionSettings::default()
+        };
+
+        settings.build_http_client()?;
+        Ok(())
+    }
+
+    #[test]
+    fn should_keep_the_default_retry_policy_for_unset_retry_settings() {
+        let settings = ConnectionSettings {
+            max_retries: Some(5),
+            max_backoff_ms: Some(10_000),
+            ..ConnectionSettings::default()
+        };
+
+        assert_eq!(
+            settings.retry_policy(),
+            RetryPolicy {
+                max_retries: 5,
+                max_backoff: Duration::from_secs(10),
+                ..RetryPolicy::default()
+            }
+        );
+    }
+
+    #[test]
+    fn should_reject_an_invalid_api_url() {
+        let settings = ConnectionSettings {
+            api_url: Some("ftp://example.com".to_string()),
+            ..ConnectionSettings::default()
+        };
+
+        assert!(matches!(
+            settings.build_http_client(),
+            Err(ConnectionSettingsError::InvalidApiUrl(_))
+        ));
+    }
+
+    #[test]
+    fn should_reject_a_ca_bundle_without_certificates() -> Result<(), ConnectionSettingsError> {
+        let directory = create_temp_dir();
+        fs::create_dir_all(&directory)?;
+        let ca_bundle_path = directory.join("ca-bundle.pem");
+        fs::write(&ca_bundle_path, "not a certificate")?;
+
+        let settings = ConnectionSettings {
+            ca_bundle_path: Some(ca_bundle_path),
+            ..ConnectionSettings::default()
+        };
+
+        assert!(matches!(
+            settings.build_http_client(),
+            Err(ConnectionSettingsError::InvalidCaBundle(_))
+        ));
+
+        fs::remove_dir_all(directory)?;
+        Ok(())
+    }
+}
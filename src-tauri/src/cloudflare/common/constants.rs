@@ -1 +1,5 @@
 pub const API_URL: &str = "https://api.cloudflare.com/client/v4";
+pub const RAY_ID_HEADER: &str = "cf-ray";
+pub const OAUTH_TOKEN_URL: &str = "https://dash.cloudflare.com/oauth2/token";
+// The public client id of wrangler, which issued the OAuth tokens found in its config.
+pub const WRANGLER_OAUTH_CLIENT_ID: &str = "54d11594-84e4-41aa-b438-e81b8fa78ee7";
@@ -1,5 +1,21 @@
 mod common_models;
+mod connection_settings;
 mod constants;
+mod pagination;
+mod rate_limiter;
+mod retry_policy;
+mod secret;
+mod token_models;
+mod traffic_recorder;
+mod transport;
 
 pub use common_models::*;
+pub use connection_settings::*;
 pub use constants::*;
+pub use pagination::*;
+pub use rate_limiter::*;
+pub use retry_policy::*;
+pub use secret::*;
+pub use token_models::*;
+pub use traffic_recorder::*;
+pub use transport::*;
@@ -0,0 +1,211 @@
+use crate::cloudflare::common::PageInfo;
+use futures::Stream;
+use std::collections::VecDeque;
+use std::future::Future;
+use std::marker::PhantomData;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+pub struct Page<T, C> {
+    pub items: Vec<T>,
+    pub next: Option<C>,
+}
+
+impl<T> Page<T, u32> {
+    pub fn from_page_info(items: Vec<T>, page_info: &PageInfo) -> Self {
+        let is_last_page =
+            items.is_empty() || page_info.page * page_info.per_page >= page_info.total_count;
+        Self {
+            items,
+            next: (!is_last_page).then_some(page_info.page as u32 + 1),
+        }
+    }
+}
+
+impl<T> Page<T, Option<String>> {
+    // The first page is requested without a cursor, the last one answers with an empty cursor.
+    // A page may be empty while others follow, e.g. when all of its keys have expired.
+    pub fn from_cursor(items: Vec<T>, cursor: Option<String>) -> Self {
+        let next = cursor.filter(|cursor| !cursor.is_empty());
+        Self {
+            items,
+            next: next.map(Some),
+        }
+    }
+}
+
+// Yields the items of every page. The next page is requested as soon as the current one
+// arrives, so it downloads while the items of the current page are consumed.
+pub struct Paginated<C, T, E, F, Fut> {
+    fetch_page: F,
+    in_flight: Option<Pin<Box<Fut>>>,
+    items: VecDeque<T>,
+    error: Option<E>,
+    _cursor: PhantomData<fn() -> C>,
+}
+
+pub fn paginate<C, T, E, F, Fut>(first: C, mut fetch_page: F) -> Paginated<C, T, E, F, Fut>
+where
+    F: FnMut(C) -> Fut,
+    Fut: Future<Output = Result<Page<T, C>, E>>,
+{
+    let in_flight = Box::pin(fetch_page(first));
+    Paginated {
+        fetch_page,
+        in_flight: Some(in_flight),
+        items: VecDeque::new(),
+        error: None,
+        _cursor: PhantomData,
+    }
+}
+
+pub fn paginate_pages<T, E, F, Fut>(fetch_page: F) -> Paginated<u32, T, E, F, Fut>
+where
+    F: FnMut(u32) -> Fut,
+    Fut: Future<Output = Result<Page<T, u32>, E>>,
+{
+    paginate(1, fetch_page)
+}
+
+pub fn paginate_cursor<T, E, F, Fut>(fetch_page: F) -> Paginated<Option<String>, T, E, F, Fut>
+where
+    F: FnMut(Option<String>) -> Fut,
+    Fut: Future<Output = Result<Page<T, Option<String>>, E>>,
+{
+    paginate(None, fetch_page)
+}
+
+impl<C, T, E, F, Fut> Stream for Paginated<C, T, E, F, Fut>
+where
+    F: FnMut(C) -> Fut + Unpin,
+    Fut: Future<Output = Result<Page<T, C>, E>>,
+    T: Unpin,
+    E: Unpin,
+{
+    type Item = Result<T, E>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        while let Some(in_flight) = this.in_flight.as_mut() {
+            let Poll::Ready(result) = in_flight.as_mut().poll(cx) else {
+                break;
+            };
+
+            this.in_flight = None;
+            match result {
+                Ok(page) => {
+                    this.items.extend(page.items);
+                    if let Some(next) = page.next {
+                        this.in_flight = Some(Box::pin((this.fetch_page)(next)));
+                    }
+                }
+                // The items of the previous pages are still handed out before the error.
+                Err(error) => this.error = Some(error),
+            }
+        }
+
+        if let Some(item) = this.items.pop_front() {
+            return Poll::Ready(Some(Ok(item)));
+        }
+
+        if let Some(error) = this.error.take() {
+            return Poll::Ready(Some(Err(error)));
+        }
+
+        if this.in_flight.is_some() {
+            return Poll::Pending;
+        }
+
+        Poll::Ready(None)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Page, paginate_cursor, paginate_pages};
+    use crate::cloudflare::common::PageInfo;
+    use futures::TryStreamExt;
+    use futures::executor::block_on;
+    use std::sync::Mutex;
+
+    #[test]
+    fn should_yield_the_items_of_every_page() {
+        let requested_pages = Mutex::new(vec![]);
+        let items: Vec<u32> = block_on(
+            paginate_pages(|page| {
+                requested_pages.lock().unwrap().push(page);
+                async move {
+                    let page_info = PageInfo {
+                        count: 2,
+                        page: page as usize,
+                        per_page: 2,
+                        total_count: 5,
+                    };
+                    let items = (page * 2 - 1..=(page * 2).min(5)).collect();
+                    Ok::<_, ()>(Page::from_page_info(items, &page_info))
+                }
+            })
+            .try_collect(),
+        )
+        .unwrap();
+
+        assert_eq!(items, vec![1, 2, 3, 4, 5]);
+        assert_eq!(*requested_pages.lock().unwrap(), vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn should_follow_the_cursor_until_it_is_empty() {
+        let items: Vec<String> = block_on(
+            paginate_cursor(|cursor| async move {
+                let (items, next_cursor) = match cursor.as_deref() {
+                    None => (vec!["a".to_string(), "b".to_string()], "next"),
+                    Some("next") => (vec!["c".to_string()], ""),
+                    Some(cursor) => panic!("unexpected cursor {cursor}"),
+                };
+                Ok::<_, ()>(Page::from_cursor(items, Some(next_cursor.to_string())))
+            })
+            .try_collect(),
+        )
+        .unwrap();
+
+        assert_eq!(items, vec!["a", "b", "c"]);
+    }
+
+    #[test]
+    fn should_follow_the_cursor_past_an_empty_page() {
+        let items: Vec<String> = block_on(
+            paginate_cursor(|cursor| async move {
+                let (items, next_cursor) = match cursor.as_deref() {
+                    None => (vec![], "next"),
+                    Some("next") => (vec!["a".to_string()], ""),
+                    Some(cursor) => panic!("unexpected cursor {cursor}"),
+                };
+                Ok::<_, ()>(Page::from_cursor(items, Some(next_cursor.to_string())))
+            })
+            .try_collect(),
+        )
+        .unwrap();
+
+        assert_eq!(items, vec!["a"]);
+    }
+
+    #[test]
+    fn should_yield_the_items_before_the_error() {
+        let results: Vec<Result<u32, String>> = block_on(futures::StreamExt::collect(
+            paginate_pages(|page| async move {
+                if page > 1 {
+                    return Err("failed".to_string());
+                }
+                let page_info = PageInfo {
+                    count: 1,
+                    page: page as usize,
+                    per_page: 1,
+                    total_count: 2,
+                };
+                Ok(Page::from_page_info(vec![page], &page_info))
+            }),
+        ));
+
+        assert_eq!(results, vec![Ok(1), Err("failed".to_string())]);
+    }
+}
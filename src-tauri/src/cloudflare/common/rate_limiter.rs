@@ -0,0 +1,220 @@
+use serde::{Deserialize, Serialize};
+use std::sync::{Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RateLimitConfig {
+    pub requests_per_second: u32,
+    pub burst: u32,
+}
+
+impl Default for RateLimitConfig {
+    fn default() -> Self {
+        // Cloudflare allows 1200 requests per five minutes for a user.
+        Self {
+            requests_per_second: 4,
+            burst: 20,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RateLimitStatus {
+    pub config: RateLimitConfig,
+    pub available_tokens: f64,
+    pub waiting_requests: usize,
+    pub total_requests: u64,
+    pub throttled_requests: u64,
+}
+
+pub struct RateLimiter {
+    state: Mutex<RateLimiterState>,
+}
+
+struct RateLimiterState {
+    config: RateLimitConfig,
+    available_tokens: f64,
+    last_refill: Instant,
+    waiting_requests: usize,
+    total_requests: u64,
+    throttled_requests: u64,
+}
+
+impl Default for RateLimiter {
+    fn default() -> Self {
+        Self::new(RateLimitConfig::default())
+    }
+}
+
+impl RateLimiter {
+    pub fn new(config: RateLimitConfig) -> Self {
+        let config = normalize_config(config);
+        Self {
+            state: Mutex::new(RateLimiterState {
+                available_tokens: config.burst as f64,
+                config,
+                last_refill: Instant::now(),
+                waiting_requests: 0,
+                total_requests: 0,
+                throttled_requests: 0,
+            }),
+        }
+    }
+
+    pub async fn acquire(&self) {
+        let mut waiting_guard: Option<WaitingGuard> = None;
+        loop {
+            let wait = {
+                let mut state = self.lock_state();
+                state.refill();
+
+                if state.available_tokens >= 1.0 {
+                    state.available_tokens -= 1.0;
+                    state.total_requests += 1;
+                    if waiting_guard.is_some() {
+                        state.throttled_requests += 1;
+                    }
+                    break;
+                }
+
+                if waiting_guard.is_none() {
+                    state.waiting_requests += 1;
+                    waiting_guard = Some(WaitingGuard { rate_limiter: self });
+                }
+
+                Duration::from_secs_f64(
+                    (1.0 - state.available_tokens) / state.config.requests_per_second as f64,
+                )
+            };
+
+            tokio::time::sleep(wait).await;
+        }
+    }
+
+    pub fn status(&self) -> RateLimitStatus {
+        let mut state = self.lock_state();
+        state.refill();
+
+        RateLimitStatus {
+            config: state.config.clone(),
+            available_tokens: state.available_tokens,
+            waiting_requests: state.waiting_requests,
+            total_requests: state.total_requests,
+            throttled_requests: state.throttled_requests,
+        }
+    }
+
+    pub fn reconfigure(&self, config: RateLimitConfig) -> RateLimitStatus {
+        {
+            let mut state = self.lock_state();
+            state.refill();
+            state.config = normalize_config(config);
+            state.available_tokens = state.available_tokens.min(state.config.burst as f64);
+        }
+
+        self.status()
+    }
+
+    fn lock_state(&self) -> MutexGuard<'_, RateLimiterState> {
+        self.state
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl RateLimiterState {
+    fn refill(&mut self) {
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.last_refill).as_secs_f64();
+        self.available_tokens = (self.available_tokens
+            + elapsed * self.config.requests_per_second as f64)
+            .min(self.config.burst as f64);
+        self.last_refill = now;
+    }
+}
+
+// Keeps the number of waiting requests correct even if a waiting request is cancelled.
+struct WaitingGuard<'a> {
+    rate_limiter: &'a RateLimiter,
+}
+
+impl Drop for WaitingGuard<'_> {
+    fn drop(&mut self) {
+        self.rate_limiter.lock_state().waiting_requests -= 1;
+    }
+}
+
+fn normalize_config(config: RateLimitConfig) -> RateLimitConfig {
+    RateLimitConfig {
+        requests_per_second: config.requests_per_second.max(1),
+        burst: config.burst.max(1),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{RateLimitConfig, RateLimiter};
+    use std::time::{Duration, Instant};
+
+    #[tokio::test]
+    async fn should_allow_a_burst_without_waiting() {
+        let rate_limiter = RateLimiter::new(RateLimitConfig {
+            requests_per_second: 1,
+            burst: 5,
+        });
+
+        let start = Instant::now();
+        for _ in 0..5 {
+            rate_limiter.acquire().await;
+        }
+
+        assert!(start.elapsed() < Duration::from_millis(500));
+
+        let status = rate_limiter.status();
+        assert_eq!(status.total_requests, 5);
+        assert_eq!(status.throttled_requests, 0);
+        assert!(status.available_tokens < 1.0);
+    }
+
+    #[tokio::test]
+    async fn should_wait_for_a_token_if_the_bucket_is_empty() {
+        let rate_limiter = RateLimiter::new(RateLimitConfig {
+            requests_per_second: 20,
+            burst: 1,
+        });
+
+        let start = Instant::now();
+        rate_limiter.acquire().await;
+        rate_limiter.acquire().await;
+        rate_limiter.acquire().await;
+
+        assert!(start.elapsed() >= Duration::from_millis(90));
+
+        let status = rate_limiter.status();
+        assert_eq!(status.total_requests, 3);
+        assert_eq!(status.throttled_requests, 2);
+        assert_eq!(status.waiting_requests, 0);
+    }
+
+    #[tokio::test]
+    async fn should_cap_available_tokens_on_reconfiguration() {
+        let rate_limiter = RateLimiter::new(RateLimitConfig {
+            requests_per_second: 1,
+            burst: 10,
+        });
+
+        let status = rate_limiter.reconfigure(RateLimitConfig {
+            requests_per_second: 0,
+            burst: 2,
+        });
+
+        assert_eq!(
+            status.config,
+            RateLimitConfig {
+                requests_per_second: 1,
+                burst: 2,
+            }
+        );
+        assert_eq!(status.available_tokens, 2.0);
+    }
+}
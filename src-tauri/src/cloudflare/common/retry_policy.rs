@@ -0,0 +1,201 @@
+use crate::cloudflare::common::RateLimiter;
+use chrono::{DateTime, Utc};
+use log::warn;
+use reqwest::header::RETRY_AFTER;
+use reqwest::{RequestBuilder, Response, StatusCode};
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::time::Duration;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RetryPolicy {
+    pub max_retries: u32,
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+    pub jitter: bool,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: 3,
+            initial_backoff: Duration::from_millis(500),
+            max_backoff: Duration::from_secs(30),
+            jitter: true,
+        }
+    }
+}
+
+impl RetryPolicy {
+    // Only use this for requests which are safe to repeat.
+    pub async fn send<F>(
+        &self,
+        rate_limiter: &RateLimiter,
+        build_request: F,
+    ) -> Result<Response, reqwest::Error>
+    where
+        F: Fn() -> RequestBuilder,
+    {
+        let mut attempt = 0;
+        loop {
+            rate_limiter.acquire().await;
+            let result = build_request().send().await;
+            if attempt >= self.max_retries {
+                return result;
+            }
+
+            let delay = match &result {
+                Ok(response) if is_retryable_status(response.status()) => {
+                    match self.server_delay(retry_after(response), attempt) {
+                        Some(delay) => delay,
+                        None => return result,
+                    }
+                }
+                Err(error) if error.is_connect() || error.is_timeout() => self.backoff(attempt),
+                _ => return result,
+            };
+
+            attempt += 1;
+            warn!(
+                "Request failed with {}, retrying in {:?} (attempt {} of {})",
+                describe_result(&result),
+                delay,
+                attempt,
+                self.max_retries
+            );
+            tokio::time::sleep(delay).await;
+        }
+    }
+
+    // Waiting longer than the maximum backoff would hang the command without feedback, so the
+    // response is returned as it is and the caller sees the rate limit with its retry time.
+    fn server_delay(&self, retry_after: Option<Duration>, attempt: u32) -> Option<Duration> {
+        match retry_after {
+            Some(delay) if delay > self.max_backoff => None,
+            Some(delay) => Some(delay),
+            None => Some(self.backoff(attempt)),
+        }
+    }
+
+    fn backoff(&self, attempt: u32) -> Duration {
+        let exponential = self
+            .initial_backoff
+            .saturating_mul(2u32.saturating_pow(attempt))
+            .min(self.max_backoff);
+
+        if self.jitter {
+            // Equal jitter: keep half of the delay and randomize the other half.
+            let half = exponential / 2;
+            half + half.mul_f64(random_fraction())
+        } else {
+            exponential
+        }
+    }
+}
+
+pub fn is_retryable_status(status: StatusCode) -> bool {
+    status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error()
+}
+
+// The retry time as command errors hand it to the UI.
+pub fn retry_after_ms(retry_after: Option<Duration>) -> Option<u64> {
+    retry_after.map(|delay| u64::try_from(delay.as_millis()).unwrap_or(u64::MAX))
+}
+
+pub fn retry_after(response: &Response) -> Option<Duration> {
+    let header_value = response.headers().get(RETRY_AFTER)?.to_str().ok()?;
+    parse_retry_after(header_value, Utc::now())
+}
+
+fn parse_retry_after(value: &str, now: DateTime<Utc>) -> Option<Duration> {
+    if let Ok(seconds) = value.trim().parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+
+    let date = DateTime::parse_from_rfc2822(value.trim()).ok()?;
+    Some(
+        (date.with_timezone(&Utc) - now)
+            .to_std()
+            .unwrap_or(Duration::ZERO),
+    )
+}
+
+fn describe_result(result: &Result<Response, reqwest::Error>) -> String {
+    match result {
+        Ok(response) => format!("status {}", response.status()),
+        Err(error) => format!("error {}", error),
+    }
+}
+
+fn random_fraction() -> f64 {
+    let hasher = RandomState::new().build_hasher();
+    hasher.finish() as f64 / u64::MAX as f64
+}
+
+#[cfg(test)]
+mod test {
+    use super::{RetryPolicy, parse_retry_after};
+    use chrono::{TimeZone, Utc};
+    use std::time::Duration;
+
+    #[test]
+    fn should_parse_retry_after_in_seconds() {
+        assert_eq!(
+            parse_retry_after("120", Utc::now()),
+            Some(Duration::from_secs(120))
+        );
+    }
+
+    #[test]
+    fn should_parse_retry_after_as_http_date() {
+        let now = Utc.with_ymd_and_hms(2015, 10, 21, 7, 27, 30).unwrap();
+        assert_eq!(
+            parse_retry_after("Wed, 21 Oct 2015 07:28:00 GMT", now),
+            Some(Duration::from_secs(30))
+        );
+    }
+
+    #[test]
+    fn should_double_the_backoff_up_to_the_maximum() {
+        let policy = RetryPolicy {
+            max_retries: 5,
+            initial_backoff: Duration::from_secs(1),
+            max_backoff: Duration::from_secs(5),
+            jitter: false,
+        };
+
+        assert_eq!(policy.backoff(0), Duration::from_secs(1));
+        assert_eq!(policy.backoff(1), Duration::from_secs(2));
+        assert_eq!(policy.backoff(2), Duration::from_secs(4));
+        assert_eq!(policy.backoff(3), Duration::from_secs(5));
+    }
+
+    #[test]
+    fn should_not_wait_longer_than_the_maximum_backoff() {
+        let policy = RetryPolicy {
+            max_backoff: Duration::from_secs(30),
+            jitter: false,
+            ..RetryPolicy::default()
+        };
+
+        assert_eq!(
+            policy.server_delay(Some(Duration::from_secs(10)), 0),
+            Some(Duration::from_secs(10))
+        );
+        assert_eq!(policy.server_delay(Some(Duration::from_secs(300)), 0), None);
+        assert_eq!(policy.server_delay(None, 1), Some(Duration::from_secs(1)));
+    }
+
+    #[test]
+    fn should_keep_jittered_backoff_within_bounds() {
+        let policy = RetryPolicy {
+            initial_backoff: Duration::from_secs(2),
+            ..RetryPolicy::default()
+        };
+
+        for _ in 0..100 {
+            let backoff = policy.backoff(0);
+            assert!(backoff >= Duration::from_secs(1) && backoff <= Duration::from_secs(2));
+        }
+    }
+}
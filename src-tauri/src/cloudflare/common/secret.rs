@@ -0,0 +1,86 @@
+use serde::{Deserialize, Serialize, Serializer};
+use std::fmt;
+use std::fmt::{Debug, Display, Formatter};
+use zeroize::Zeroize;
+
+const REDACTED: &str = "***";
+const HINT_LENGTH: usize = 4;
+// Shorter secrets are redacted completely, their last characters would give away too much.
+const MIN_HINTED_LENGTH: usize = 16;
+
+#[derive(Clone, PartialEq, Deserialize)]
+#[serde(transparent)]
+pub struct Secret(String);
+
+impl Secret {
+    pub fn expose(&self) -> &str {
+        &self.0
+    }
+
+    pub fn hint(&self) -> String {
+        let length = self.0.chars().count();
+        if length < MIN_HINTED_LENGTH {
+            return REDACTED.to_string();
+        }
+
+        let last_characters: String = self.0.chars().skip(length - HINT_LENGTH).collect();
+        format!("{REDACTED}{last_characters}")
+    }
+}
+
+impl From<String> for Secret {
+    fn from(value: String) -> Self {
+        Self(value)
+    }
+}
+
+impl From<&str> for Secret {
+    fn from(value: &str) -> Self {
+        Self(value.to_string())
+    }
+}
+
+impl Debug for Secret {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "Secret({})", REDACTED)
+    }
+}
+
+impl Display for Secret {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "{}", REDACTED)
+    }
+}
+
+// Only serialize secrets into storage which is encrypted, never into command responses.
+impl Serialize for Secret {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.0)
+    }
+}
+
+impl Drop for Secret {
+    fn drop(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Secret;
+
+    #[test]
+    fn should_redact_the_secret_in_debug_and_display() {
+        let secret = Secret::from("my-secret-token");
+
+        assert_eq!(format!("{:?}", secret), "Secret(***)");
+        assert_eq!(format!("{}", secret), "***");
+        assert_eq!(secret.expose(), "my-secret-token");
+    }
+
+    #[test]
+    fn should_only_hint_at_the_end_of_long_secrets() {
+        assert_eq!(Secret::from("my-long-secret-token").hint(), "***oken");
+        assert_eq!(Secret::from("short-token").hint(), "***");
+    }
+}
@@ -0,0 +1,355 @@
+use crate::cloudflare::common::{
+    ApiPaginatedResponse, ApiResponse, OrderDirection, PageInfo, Secret, TokenStatus,
+};
+use chrono::{DateTime, TimeDelta, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+
+pub const TOKEN_EXPIRY_WARNING_PERIOD: TimeDelta = TimeDelta::days(7);
+
+pub const KV_READ_PERMISSION_GROUP: &str = "Workers KV Storage Read";
+pub const KV_WRITE_PERMISSION_GROUP: &str = "Workers KV Storage Write";
+pub const ACCOUNT_RESOURCE_PREFIX: &str = "com.cloudflare.api.account.";
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct TokenDetails {
+    pub id: String,
+    pub name: Option<String>,
+    pub status: TokenStatus,
+    pub issued_on: Option<DateTime<Utc>>,
+    pub modified_on: Option<DateTime<Utc>>,
+    pub last_used_on: Option<DateTime<Utc>>,
+    pub not_before: Option<DateTime<Utc>>,
+    pub expires_on: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub policies: Vec<TokenPolicy>,
+    pub condition: Option<TokenCondition>,
+}
+
+impl From<ApiResponse<TokenDetails>> for TokenDetails {
+    fn from(api_response: ApiResponse<TokenDetails>) -> Self {
+        api_response.result
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Default, Serialize, Deserialize)]
+pub struct TokensListInput {
+    pub order_direction: Option<OrderDirection>,
+    pub page: Option<u32>,
+    pub per_page: Option<u32>,
+}
+
+impl From<TokensListInput> for HashMap<String, String> {
+    fn from(value: TokensListInput) -> Self {
+        let mut map = HashMap::new();
+        if let Some(order_direction) = value.order_direction {
+            map.insert("direction".to_string(), order_direction.to_string());
+        }
+
+        if let Some(page) = value.page {
+            map.insert("page".to_string(), page.to_string());
+        }
+
+        if let Some(per_page) = value.per_page {
+            map.insert("per_page".to_string(), per_page.to_string());
+        }
+
+        map
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct Tokens {
+    pub items: Vec<TokenDetails>,
+    pub page_info: PageInfo,
+}
+
+impl From<ApiPaginatedResponse<Vec<TokenDetails>>> for Tokens {
+    fn from(value: ApiPaginatedResponse<Vec<TokenDetails>>) -> Self {
+        Self {
+            items: value.result,
+            page_info: value.result_info,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct TokenCreateInput {
+    pub name: String,
+    pub policies: Vec<TokenPolicy>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub not_before: Option<DateTime<Utc>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expires_on: Option<DateTime<Utc>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub condition: Option<TokenCondition>,
+}
+
+// The value of a token is only returned once, on creation or when it is rolled.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct CreatedToken {
+    #[serde(flatten)]
+    pub details: TokenDetails,
+    pub value: Secret,
+}
+
+impl From<ApiResponse<CreatedToken>> for CreatedToken {
+    fn from(api_response: ApiResponse<CreatedToken>) -> Self {
+        api_response.result
+    }
+}
+
+impl From<ApiResponse<Secret>> for Secret {
+    fn from(api_response: ApiResponse<Secret>) -> Self {
+        api_response.result
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct TokenPolicy {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    pub effect: TokenPolicyEffect,
+    #[serde(default)]
+    pub permission_groups: Vec<PermissionGroup>,
+    // Maps a resource like `com.cloudflare.api.account.<id>` either to "*" or to nested resources.
+    #[serde(default)]
+    pub resources: HashMap<String, Value>,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+#[serde(rename_all = "lowercase")]
+pub enum TokenPolicyEffect {
+    Allow,
+    Deny,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct PermissionGroup {
+    pub id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+}
+
+impl From<ApiResponse<Vec<PermissionGroup>>> for Vec<PermissionGroup> {
+    fn from(api_response: ApiResponse<Vec<PermissionGroup>>) -> Self {
+        api_response.result
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct TokenCondition {
+    pub request_ip: Option<RequestIpCondition>,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct RequestIpCondition {
+    #[serde(rename = "in", default)]
+    pub allowed: Vec<String>,
+    #[serde(rename = "not_in", default)]
+    pub denied: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct TokenCapabilities {
+    pub kv_access: KvAccess,
+    pub permission_groups: Vec<String>,
+    pub expires_on: Option<DateTime<Utc>>,
+    pub ip_restricted: bool,
+    pub warnings: Vec<TokenWarning>,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub enum KvAccess {
+    None,
+    Read,
+    ReadWrite,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub enum TokenWarning {
+    ExpiresSoon,
+    NotYetValid,
+    KvReadOnly,
+    NoKvAccess,
+}
+
+impl TokenDetails {
+    pub fn capabilities(&self, account_id: &str, now: DateTime<Utc>) -> TokenCapabilities {
+        let permission_groups = self.permission_groups(account_id);
+        let has_permission_group = |name: &str| permission_groups.iter().any(|group| group == name);
+
+        let kv_access = if has_permission_group(KV_WRITE_PERMISSION_GROUP) {
+            KvAccess::ReadWrite
+        } else if has_permission_group(KV_READ_PERMISSION_GROUP) {
+            KvAccess::Read
+        } else {
+            KvAccess::None
+        };
+
+        let mut warnings = vec![];
+        if let Some(expires_on) = self.expires_on
+            && expires_on - now <= TOKEN_EXPIRY_WARNING_PERIOD
+        {
+            warnings.push(TokenWarning::ExpiresSoon);
+        }
+        if let Some(not_before) = self.not_before
+            && not_before > now
+        {
+            warnings.push(TokenWarning::NotYetValid);
+        }
+        match kv_access {
+            KvAccess::None => warnings.push(TokenWarning::NoKvAccess),
+            KvAccess::Read => warnings.push(TokenWarning::KvReadOnly),
+            KvAccess::ReadWrite => {}
+        }
+
+        let ip_restricted = self
+            .condition
+            .as_ref()
+            .and_then(|condition| condition.request_ip.as_ref())
+            .is_some_and(|request_ip| {
+                !request_ip.allowed.is_empty() || !request_ip.denied.is_empty()
+            });
+
+        TokenCapabilities {
+            kv_access,
+            permission_groups,
+            expires_on: self.expires_on,
+            ip_restricted,
+            warnings,
+        }
+    }
+
+    // Permission groups which are allowed for the account and not taken away by a deny policy.
+    fn permission_groups(&self, account_id: &str) -> Vec<String> {
+        let policy_groups = |effect: TokenPolicyEffect| {
+            self.policies
+                .iter()
+                .filter(move |policy| {
+                    policy.effect == effect && applies_to_account(policy, account_id)
+                })
+                .flat_map(|policy| policy.permission_groups.iter())
+                .filter_map(|group| group.name.clone())
+        };
+
+        let denied_groups: Vec<String> = policy_groups(TokenPolicyEffect::Deny).collect();
+        let mut allowed_groups: Vec<String> = policy_groups(TokenPolicyEffect::Allow)
+            .filter(|group| !denied_groups.contains(group))
+            .collect();
+        allowed_groups.sort();
+        allowed_groups.dedup();
+        allowed_groups
+    }
+}
+
+fn applies_to_account(policy: &TokenPolicy, account_id: &str) -> bool {
+    policy.resources.is_empty()
+        || policy.resources.keys().any(|resource| {
+            resource
+                .strip_prefix(ACCOUNT_RESOURCE_PREFIX)
+                .is_some_and(|resource_id| resource_id == account_id || resource_id == "*")
+        })
+}
+
+#[cfg(test)]
+mod test {
+    use super::{
+        KvAccess, PermissionGroup, TokenDetails, TokenPolicy, TokenPolicyEffect, TokenWarning,
+    };
+    use crate::cloudflare::common::TokenStatus;
+    use chrono::{TimeDelta, Utc};
+    use serde_json::Value;
+    use std::collections::HashMap;
+
+    #[test]
+    fn should_detect_read_write_kv_access_for_the_account() {
+        let token = create_token(vec![create_policy(
+            TokenPolicyEffect::Allow,
+            "com.cloudflare.api.account.12345",
+            vec!["Workers KV Storage Read", "Workers KV Storage Write"],
+        )]);
+
+        let capabilities = token.capabilities("12345", Utc::now());
+
+        assert_eq!(capabilities.kv_access, KvAccess::ReadWrite);
+        assert!(capabilities.warnings.is_empty());
+    }
+
+    #[test]
+    fn should_ignore_policies_of_other_accounts_and_denied_groups() {
+        let token = create_token(vec![
+            create_policy(
+                TokenPolicyEffect::Allow,
+                "com.cloudflare.api.account.67890",
+                vec!["Workers KV Storage Write"],
+            ),
+            create_policy(
+                TokenPolicyEffect::Allow,
+                "com.cloudflare.api.account.*",
+                vec!["Workers KV Storage Read", "Workers KV Storage Write"],
+            ),
+            create_policy(
+                TokenPolicyEffect::Deny,
+                "com.cloudflare.api.account.12345",
+                vec!["Workers KV Storage Write"],
+            ),
+        ]);
+
+        let capabilities = token.capabilities("12345", Utc::now());
+
+        assert_eq!(capabilities.kv_access, KvAccess::Read);
+        assert_eq!(capabilities.warnings, vec![TokenWarning::KvReadOnly]);
+    }
+
+    #[test]
+    fn should_warn_about_a_token_which_expires_soon() {
+        let now = Utc::now();
+        let token = TokenDetails {
+            expires_on: Some(now + TimeDelta::days(2)),
+            ..create_token(vec![])
+        };
+
+        let capabilities = token.capabilities("12345", now);
+
+        assert_eq!(
+            capabilities.warnings,
+            vec![TokenWarning::ExpiresSoon, TokenWarning::NoKvAccess]
+        );
+    }
+
+    fn create_token(policies: Vec<TokenPolicy>) -> TokenDetails {
+        TokenDetails {
+            id: "token-id".to_string(),
+            name: Some("Test Token".to_string()),
+            status: TokenStatus::Active,
+            issued_on: None,
+            modified_on: None,
+            last_used_on: None,
+            not_before: None,
+            expires_on: None,
+            policies,
+            condition: None,
+        }
+    }
+
+    fn create_policy(
+        effect: TokenPolicyEffect,
+        resource: &str,
+        permission_groups: Vec<&str>,
+    ) -> TokenPolicy {
+        TokenPolicy {
+            id: None,
+            effect,
+            permission_groups: permission_groups
+                .into_iter()
+                .map(|name| PermissionGroup {
+                    id: name.to_lowercase(),
+                    name: Some(name.to_string()),
+                })
+                .collect(),
+            resources: HashMap::from([(resource.to_string(), Value::from("*"))]),
+        }
+    }
+}
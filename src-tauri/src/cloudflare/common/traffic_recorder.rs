@@ -0,0 +1,595 @@
+use crate::cloudflare::common::RAY_ID_HEADER;
+use chrono::{DateTime, Utc};
+use reqwest::header::{CONTENT_TYPE, HeaderMap};
+use reqwest::{RequestBuilder, Response, Url};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value, json};
+use std::collections::VecDeque;
+use std::sync::{Mutex, MutexGuard};
+use std::time::Instant;
+
+const REDACTED: &str = "[REDACTED]";
+const CREDENTIAL_HEADERS: [&str; 4] = [
+    "authorization",
+    "x-auth-email",
+    "x-auth-key",
+    "x-auth-user-service-key",
+];
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TrafficRecorderConfig {
+    pub enabled: bool,
+    pub capacity: usize,
+    pub max_body_size: usize,
+}
+
+impl Default for TrafficRecorderConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            capacity: 200,
+            max_body_size: 4096,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TrafficHeader {
+    pub name: String,
+    pub value: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TrafficEntry {
+    pub id: u64,
+    pub started_at: DateTime<Utc>,
+    pub method: String,
+    pub url: String,
+    pub request_headers: Vec<TrafficHeader>,
+    pub request_body: Option<String>,
+    pub status: Option<u16>,
+    pub duration_ms: u64,
+    pub response_headers: Vec<TrafficHeader>,
+    pub response_size: usize,
+    pub ray_id: Option<String>,
+    pub response_body: Option<String>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct TrafficQuery {
+    pub method: Option<String>,
+    pub url_contains: Option<String>,
+    pub status: Option<u16>,
+    #[serde(default)]
+    pub failed_only: bool,
+    pub limit: Option<usize>,
+}
+
+impl TrafficQuery {
+    fn matches(&self, entry: &TrafficEntry) -> bool {
+        self.method
+            .as_ref()
+            .is_none_or(|method| entry.method.eq_ignore_ascii_case(method))
+            && self
+                .url_contains
+                .as_ref()
+                .is_none_or(|url_part| entry.url.contains(url_part.as_str()))
+            && self
+                .status
+                .is_none_or(|status| entry.status == Some(status))
+            && (!self.failed_only || entry.is_failed())
+    }
+}
+
+impl TrafficEntry {
+    fn is_failed(&self) -> bool {
+        self.status.is_none_or(|status| status >= 400)
+    }
+
+    fn har_entry(&self) -> Value {
+        let query_string: Vec<Value> = Url::parse(&self.url)
+            .map(|url| {
+                url.query_pairs()
+                    .map(|(name, value)| json!({ "name": name, "value": value }))
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        let mut request = json!({
+            "method": self.method,
+            "url": self.url,
+            "httpVersion": "HTTP/1.1",
+            "cookies": [],
+            "headers": har_headers(&self.request_headers),
+            "queryString": query_string,
+            "headersSize": -1,
+            "bodySize": self.request_body.as_ref().map_or(0, String::len),
+        });
+        if let Some(request_body) = &self.request_body {
+            request["postData"] = json!({
+                "mimeType": content_type(&self.request_headers),
+                "text": request_body,
+            });
+        }
+
+        let mut entry = json!({
+            "startedDateTime": self.started_at.to_rfc3339(),
+            "time": self.duration_ms,
+            "request": request,
+            "response": {
+                "status": self.status.unwrap_or(0),
+                "statusText": "",
+                "httpVersion": "HTTP/1.1",
+                "cookies": [],
+                "headers": har_headers(&self.response_headers),
+                "content": {
+                    "size": self.response_size,
+                    "mimeType": content_type(&self.response_headers),
+                    "text": self.response_body.clone().unwrap_or_default(),
+                },
+                "redirectURL": "",
+                "headersSize": -1,
+                "bodySize": self.response_size,
+            },
+            "cache": {},
+            "timings": { "send": 0, "wait": self.duration_ms, "receive": 0 },
+        });
+        if let Some(error) = &self.error {
+            entry["_error"] = json!(error);
+        }
+
+        entry
+    }
+}
+
+// Records the requests sent to Cloudflare into a ring buffer, as long as it is enabled.
+pub struct TrafficRecorder {
+    state: Mutex<TrafficRecorderState>,
+}
+
+struct TrafficRecorderState {
+    config: TrafficRecorderConfig,
+    entries: VecDeque<TrafficEntry>,
+    next_id: u64,
+}
+
+pub struct TrafficCapture {
+    started_at: DateTime<Utc>,
+    started: Instant,
+    method: String,
+    url: String,
+    request_headers: Vec<TrafficHeader>,
+    request_body: Option<String>,
+}
+
+impl Default for TrafficRecorder {
+    fn default() -> Self {
+        Self::new(TrafficRecorderConfig::default())
+    }
+}
+
+impl TrafficRecorder {
+    pub fn new(config: TrafficRecorderConfig) -> Self {
+        Self {
+            state: Mutex::new(TrafficRecorderState {
+                config: normalize_config(config),
+                entries: VecDeque::new(),
+                next_id: 1,
+            }),
+        }
+    }
+
+    pub fn config(&self) -> TrafficRecorderConfig {
+        self.lock_state().config.clone()
+    }
+
+    pub fn reconfigure(&self, config: TrafficRecorderConfig) -> TrafficRecorderConfig {
+        let mut state = self.lock_state();
+        state.config = normalize_config(config);
+        while state.entries.len() > state.config.capacity {
+            state.entries.pop_front();
+        }
+
+        state.config.clone()
+    }
+
+    // Returns the newest entries first.
+    pub fn entries(&self, query: &TrafficQuery) -> Vec<TrafficEntry> {
+        let state = self.lock_state();
+        state
+            .entries
+            .iter()
+            .rev()
+            .filter(|entry| query.matches(entry))
+            .take(query.limit.unwrap_or(usize::MAX))
+            .cloned()
+            .collect()
+    }
+
+    pub fn clear(&self) {
+        self.lock_state().entries.clear();
+    }
+
+    pub fn export_har(&self) -> Value {
+        let state = self.lock_state();
+        json!({
+            "log": {
+                "version": "1.2",
+                "creator": {
+                    "name": "Flare Commander",
+                    "version": env!("CARGO_PKG_VERSION"),
+                },
+                "entries": state.entries.iter().map(TrafficEntry::har_entry).collect::<Vec<_>>(),
+            }
+        })
+    }
+
+    pub fn capture<F>(&self, build_request: F) -> Option<TrafficCapture>
+    where
+        F: Fn() -> RequestBuilder,
+    {
+        let max_body_size = {
+            let state = self.lock_state();
+            if !state.config.enabled {
+                return None;
+            }
+            state.config.max_body_size
+        };
+
+        let request = build_request().build().ok()?;
+        let request_headers = redact_headers(request.headers());
+        // Streamed bodies like multipart forms are not available before they are sent.
+        let request_body = request
+            .body()
+            .and_then(|body| body.as_bytes())
+            .and_then(|body| redact_body(request.url(), body, max_body_size));
+
+        Some(TrafficCapture {
+            started_at: Utc::now(),
+            started: Instant::now(),
+            method: request.method().to_string(),
+            url: request.url().to_string(),
+            request_headers,
+            request_body,
+        })
+    }
+
+    // The response body is consumed for the entry, so the response is rebuilt for the caller.
+    pub async fn record(
+        &self,
+        capture: TrafficCapture,
+        result: Result<Response, reqwest::Error>,
+    ) -> Result<Response, reqwest::Error> {
+        let response = match result {
+            Ok(response) => response,
+            Err(error) => {
+                self.push(capture.into_entry(None, HeaderMap::new(), Some(&error)));
+                return Err(error);
+            }
+        };
+
+        let status = response.status();
+        let version = response.version();
+        let headers = response.headers().clone();
+        let body = match response.bytes().await {
+            Ok(body) => body,
+            Err(error) => {
+                self.push(capture.into_entry(Some(status.as_u16()), headers, Some(&error)));
+                return Err(error);
+            }
+        };
+
+        let max_body_size = self.lock_state().config.max_body_size;
+        let mut entry = capture.into_entry(Some(status.as_u16()), headers.clone(), None);
+        entry.response_size = body.len();
+        entry.response_body = Url::parse(&entry.url)
+            .ok()
+            .and_then(|url| redact_body(&url, &body, max_body_size));
+        self.push(entry);
+
+        let mut rebuilt_response = http::Response::new(body);
+        *rebuilt_response.status_mut() = status;
+        *rebuilt_response.version_mut() = version;
+        *rebuilt_response.headers_mut() = headers;
+        Ok(Response::from(rebuilt_response))
+    }
+
+    fn push(&self, mut entry: TrafficEntry) {
+        let mut state = self.lock_state();
+        entry.id = state.next_id;
+        state.next_id += 1;
+        if state.entries.len() >= state.config.capacity {
+            state.entries.pop_front();
+        }
+        state.entries.push_back(entry);
+    }
+
+    fn lock_state(&self) -> MutexGuard<'_, TrafficRecorderState> {
+        self.state
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl TrafficCapture {
+    fn into_entry(
+        self,
+        status: Option<u16>,
+        response_headers: HeaderMap,
+        error: Option<&reqwest::Error>,
+    ) -> TrafficEntry {
+        TrafficEntry {
+            id: 0,
+            started_at: self.started_at,
+            method: self.method,
+            url: self.url,
+            request_headers: self.request_headers,
+            request_body: self.request_body,
+            status,
+            duration_ms: self.started.elapsed().as_millis() as u64,
+            ray_id: response_headers
+                .get(RAY_ID_HEADER)
+                .and_then(|ray_id| ray_id.to_str().ok())
+                .map(str::to_string),
+            response_headers: redact_headers(&response_headers),
+            response_size: 0,
+            response_body: None,
+            error: error.map(|error| error.to_string()),
+        }
+    }
+}
+
+fn normalize_config(config: TrafficRecorderConfig) -> TrafficRecorderConfig {
+    TrafficRecorderConfig {
+        capacity: config.capacity.max(1),
+        ..config
+    }
+}
+
+fn redact_headers(headers: &HeaderMap) -> Vec<TrafficHeader> {
+    headers
+        .iter()
+        .map(|(name, value)| TrafficHeader {
+            name: name.to_string(),
+            value: if value.is_sensitive() || CREDENTIAL_HEADERS.contains(&name.as_str()) {
+                REDACTED.to_string()
+            } else {
+                value.to_str().unwrap_or(REDACTED).to_string()
+            },
+        })
+        .collect()
+}
+
+// Values are never recorded: the bodies of value endpoints are dropped completely and the
+// `value` fields of JSON bodies, like bulk writes or created tokens, are replaced.
+fn redact_body(url: &Url, body: &[u8], max_body_size: usize) -> Option<String> {
+    if body.is_empty() {
+        return None;
+    }
+
+    let path = url.path();
+    if path.contains("/values/") || path.ends_with("/value") {
+        return Some(REDACTED.to_string());
+    }
+
+    let body = match serde_json::from_slice::<Value>(body) {
+        Ok(json_body) => redact_json(json_body).to_string(),
+        Err(_) => String::from_utf8_lossy(body).to_string(),
+    };
+
+    Some(truncate(body, max_body_size))
+}
+
+fn redact_json(value: Value) -> Value {
+    match value {
+        Value::Object(object) => Value::Object(
+            object
+                .into_iter()
+                .map(|(name, value)| {
+                    let value = match (name.as_str(), value) {
+                        ("value", _) => json!(REDACTED),
+                        // Bulk reads answer with the values by key, the keys are kept.
+                        ("values", Value::Object(values)) => Value::Object(
+                            values
+                                .into_iter()
+                                .map(|(key, _)| (key, json!(REDACTED)))
+                                .collect::<Map<_, _>>(),
+                        ),
+                        (_, value) => redact_json(value),
+                    };
+                    (name, value)
+                })
+                .collect(),
+        ),
+        Value::Array(items) => Value::Array(items.into_iter().map(redact_json).collect()),
+        value => value,
+    }
+}
+
+fn truncate(mut text: String, max_size: usize) -> String {
+    if text.len() <= max_size {
+        return text;
+    }
+
+    let mut end = max_size;
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    let truncated_size = text.len() - end;
+    text.truncate(end);
+    text.push_str(&format!("... ({truncated_size} bytes truncated)"));
+    text
+}
+
+fn har_headers(headers: &[TrafficHeader]) -> Vec<Value> {
+    headers
+        .iter()
+        .map(|header| json!({ "name": header.name, "value": header.value }))
+        .collect()
+}
+
+fn content_type(headers: &[TrafficHeader]) -> String {
+    headers
+        .iter()
+        .find(|header| header.name == CONTENT_TYPE.as_str())
+        .map(|header| header.value.clone())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod test {
+    use super::{REDACTED, TrafficQuery, TrafficRecorder, TrafficRecorderConfig};
+    use crate::cloudflare::common::{Credentials, RAY_ID_HEADER, Transport};
+    use crate::cloudflare::kv::KvError;
+    use serde_json::{Value, json};
+    use std::sync::Arc;
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    const BULK_GET_PATH: &str =
+        "/client/v4/accounts/account_id/storage/kv/namespaces/namespace_id/bulk/get";
+
+    #[tokio::test]
+    async fn should_record_requests_without_credentials_and_values() -> Result<(), KvError> {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path(BULK_GET_PATH))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .insert_header(RAY_ID_HEADER, "8f2b3c4d5e6f7a8b-FRA")
+                    .set_body_json(json!({
+                        "result": { "values": { "key1": "secret value" } },
+                    })),
+            )
+            .mount(&mock_server)
+            .await;
+
+        let traffic_recorder = Arc::new(TrafficRecorder::new(TrafficRecorderConfig {
+            enabled: true,
+            ..TrafficRecorderConfig::default()
+        }));
+        let transport = create_transport(&mock_server, traffic_recorder.clone());
+
+        let body = json!({ "keys": ["key1"] });
+        let response: Value = transport
+            .post("/accounts/account_id/storage/kv/namespaces/namespace_id/bulk/get")
+            .json(&body)
+            .send::<KvError>()
+            .await?
+            .json()
+            .await?;
+        assert_eq!(response["result"]["values"]["key1"], "secret value");
+
+        let entries = traffic_recorder.entries(&TrafficQuery::default());
+        assert_eq!(entries.len(), 1);
+        let entry = &entries[0];
+        assert_eq!(entry.method, "POST");
+        assert_eq!(entry.status, Some(200));
+        assert_eq!(entry.ray_id, Some("8f2b3c4d5e6f7a8b-FRA".to_string()));
+        assert_eq!(entry.request_body, Some(r#"{"keys":["key1"]}"#.to_string()));
+        assert!(
+            entry
+                .request_headers
+                .iter()
+                .any(|header| header.name == "authorization" && header.value == REDACTED)
+        );
+        let response_body: Value = entry
+            .response_body
+            .as_deref()
+            .and_then(|body| serde_json::from_str(body).ok())
+            .unwrap_or_default();
+        assert_eq!(response_body["result"]["values"]["key1"], REDACTED);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn should_not_record_while_disabled() -> Result<(), KvError> {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path(BULK_GET_PATH))
+            .respond_with(ResponseTemplate::new(200))
+            .mount(&mock_server)
+            .await;
+
+        let traffic_recorder = Arc::new(TrafficRecorder::default());
+        let transport = create_transport(&mock_server, traffic_recorder.clone());
+        transport
+            .post("/accounts/account_id/storage/kv/namespaces/namespace_id/bulk/get")
+            .send::<KvError>()
+            .await?;
+
+        assert!(
+            traffic_recorder
+                .entries(&TrafficQuery::default())
+                .is_empty()
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn should_keep_only_the_newest_entries_and_export_them_as_har() -> Result<(), KvError> {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path(BULK_GET_PATH))
+            .respond_with(ResponseTemplate::new(404))
+            .mount(&mock_server)
+            .await;
+
+        let traffic_recorder = Arc::new(TrafficRecorder::new(TrafficRecorderConfig {
+            enabled: true,
+            capacity: 2,
+            ..TrafficRecorderConfig::default()
+        }));
+        let transport = create_transport(&mock_server, traffic_recorder.clone());
+        for _ in 0..3 {
+            let _ = transport
+                .post("/accounts/account_id/storage/kv/namespaces/namespace_id/bulk/get")
+                .without_retries()
+                .send::<KvError>()
+                .await;
+        }
+
+        let failed_entries = traffic_recorder.entries(&TrafficQuery {
+            failed_only: true,
+            ..TrafficQuery::default()
+        });
+        assert_eq!(
+            failed_entries
+                .iter()
+                .map(|entry| entry.id)
+                .collect::<Vec<_>>(),
+            vec![3, 2]
+        );
+
+        let har = traffic_recorder.export_har();
+        assert_eq!(har["log"]["version"], "1.2");
+        assert_eq!(har["log"]["entries"].as_array().map(Vec::len), Some(2));
+        assert_eq!(har["log"]["entries"][0]["response"]["status"], 404);
+
+        traffic_recorder.clear();
+        assert!(
+            traffic_recorder
+                .entries(&TrafficQuery::default())
+                .is_empty()
+        );
+        Ok(())
+    }
+
+    fn create_transport(
+        mock_server: &MockServer,
+        traffic_recorder: Arc<TrafficRecorder>,
+    ) -> Transport {
+        Transport::new(
+            Credentials::UserAuthToken {
+                token: "12345".into(),
+            },
+            Some(format!("{}/client/v4", mock_server.uri())),
+            None,
+            None,
+            None,
+            Some(traffic_recorder),
+        )
+    }
+}
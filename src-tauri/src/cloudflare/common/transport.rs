@@ -0,0 +1,175 @@
+use crate::cloudflare::common::{
+    API_URL, CloudflareApiError, Credentials, RateLimiter, RetryPolicy, TokenError,
+    TrafficRecorder, retry_after,
+};
+use log::debug;
+use reqwest::multipart::Form;
+use reqwest::{Method, RequestBuilder, Response, StatusCode};
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+// Every product client maps the errors of its API onto its own error type.
+pub trait ApiErrorMapping: From<reqwest::Error> + From<TokenError> {
+    fn rate_limited(retry_after: Option<Duration>, api_error: CloudflareApiError) -> Self;
+    fn from_api_error(api_error: CloudflareApiError) -> Self;
+}
+
+pub struct Transport {
+    api_url: String,
+    credentials: Credentials,
+    http_client: Arc<reqwest::Client>,
+    retry_policy: RetryPolicy,
+    rate_limiter: Arc<RateLimiter>,
+    traffic_recorder: Arc<TrafficRecorder>,
+}
+
+impl Transport {
+    pub fn new(
+        credentials: Credentials,
+        api_url: Option<String>,
+        http_client: Option<Arc<reqwest::Client>>,
+        retry_policy: Option<RetryPolicy>,
+        rate_limiter: Option<Arc<RateLimiter>>,
+        traffic_recorder: Option<Arc<TrafficRecorder>>,
+    ) -> Self {
+        Self {
+            api_url: api_url.unwrap_or(API_URL.to_string()),
+            credentials,
+            http_client: http_client.unwrap_or_default(),
+            retry_policy: retry_policy.unwrap_or_default(),
+            rate_limiter: rate_limiter.unwrap_or_default(),
+            traffic_recorder: traffic_recorder.unwrap_or_default(),
+        }
+    }
+
+    pub fn get(&self, path: &str) -> ApiRequest<'_> {
+        self.request(Method::GET, path)
+    }
+
+    pub fn post(&self, path: &str) -> ApiRequest<'_> {
+        self.request(Method::POST, path)
+    }
+
+    pub fn put(&self, path: &str) -> ApiRequest<'_> {
+        self.request(Method::PUT, path)
+    }
+
+    pub fn delete(&self, path: &str) -> ApiRequest<'_> {
+        self.request(Method::DELETE, path)
+    }
+
+    pub fn request(&self, method: Method, path: &str) -> ApiRequest<'_> {
+        ApiRequest {
+            transport: self,
+            method,
+            url: format!("{}{}", self.api_url, path),
+            customizations: vec![],
+            retry: true,
+        }
+    }
+}
+
+type Customization<'a> = Box<dyn Fn(RequestBuilder) -> RequestBuilder + Send + Sync + 'a>;
+
+pub struct ApiRequest<'a> {
+    transport: &'a Transport,
+    method: Method,
+    url: String,
+    customizations: Vec<Customization<'a>>,
+    retry: bool,
+}
+
+impl<'a> ApiRequest<'a> {
+    pub fn query<T: Serialize + Sync + ?Sized>(self, query: &'a T) -> Self {
+        self.customize(move |builder| builder.query(query))
+    }
+
+    pub fn json<T: Serialize + Sync + ?Sized>(self, body: &'a T) -> Self {
+        self.customize(move |builder| builder.json(body))
+    }
+
+    // A multipart body can only be sent once, so the form is built again on every attempt.
+    pub fn multipart<F>(self, build_form: F) -> Self
+    where
+        F: Fn() -> Form + Send + Sync + 'a,
+    {
+        self.customize(move |builder| builder.multipart(build_form()))
+    }
+
+    // For requests which are not safe to repeat, they are only rate limited but not retried.
+    pub fn without_retries(mut self) -> Self {
+        self.retry = false;
+        self
+    }
+
+    pub async fn send<E: ApiErrorMapping>(self) -> Result<Response, E> {
+        let transport = self.transport;
+        let headers = transport.credentials.headers()?;
+        let build_request = || {
+            self.customizations.iter().fold(
+                transport
+                    .http_client
+                    .request(self.method.clone(), &self.url)
+                    .headers(headers.clone()),
+                |builder, customization| customization(builder),
+            )
+        };
+
+        let capture = transport.traffic_recorder.capture(&build_request);
+        let started = Instant::now();
+        let result = if self.retry {
+            transport
+                .retry_policy
+                .send(&transport.rate_limiter, build_request)
+                .await
+        } else {
+            transport.rate_limiter.acquire().await;
+            build_request().send().await
+        };
+        let response = match capture {
+            Some(capture) => transport.traffic_recorder.record(capture, result).await?,
+            None => result?,
+        };
+        debug!(
+            "{} {} responded with {} after {:?}",
+            self.method,
+            self.url,
+            response.status(),
+            started.elapsed()
+        );
+
+        if response.status().is_success() {
+            return Ok(response);
+        }
+
+        let retry_after = retry_after(&response);
+        let api_error = CloudflareApiError::from_response(response).await;
+        if api_error.status == StatusCode::TOO_MANY_REQUESTS.as_u16() {
+            return Err(E::rate_limited(retry_after, api_error));
+        }
+
+        Err(E::from_api_error(api_error))
+    }
+
+    // Unwraps the response envelope, e.g. `ApiResponse<KvNamespace>` into `KvNamespace`.
+    pub async fn fetch<T, R, E>(self) -> Result<R, E>
+    where
+        T: DeserializeOwned,
+        R: From<T>,
+        E: ApiErrorMapping,
+    {
+        let response = self.send::<E>().await?;
+        let api_result: T = response.json().await?;
+        Ok(api_result.into())
+    }
+
+    fn customize<F>(mut self, customization: F) -> Self
+    where
+        F: Fn(RequestBuilder) -> RequestBuilder + Send + Sync + 'a,
+    {
+        self.customizations.push(Box::new(customization));
+        self
+    }
+}
@@ -4,8 +4,8 @@ use super::{
     KvValuesGetInput, KvValuesRaw, KvValuesResult,
 };
 use crate::cloudflare::common::{
-    API_URL, ApiCursorPaginatedResponse, ApiError, ApiErrorResponse, ApiPaginatedResponse,
-    ApiResponse, Credentials, TokenError,
+    ApiCursorPaginatedResponse, ApiPaginatedResponse, ApiResponse, Page, Transport,
+    paginate_cursor, paginate_pages,
 };
 use crate::cloudflare::kv::utils::url_encode_key;
 use crate::cloudflare::kv::{
@@ -14,70 +14,69 @@ use crate::cloudflare::kv::{
     KvNamespacesListInput, KvPairMetadata, KvPairMetadataGetInput, KvPairWriteInput,
 };
 use chrono::DateTime;
+use futures::Stream;
 use futures::future::try_join_all;
 use reqwest::multipart::{Form, Part};
-use reqwest::{Response, StatusCode};
-use serde::Deserialize;
 use std::collections::HashMap;
-use std::option::Option;
 use std::sync::Arc;
 use tokio::join;
 
+const MAX_NAMESPACES_PER_PAGE: u32 = 100;
+
+// The bulk read endpoint accepts up to 100 keys per request.
+const BULK_GET_KEY_LIMIT: usize = 100;
+
 pub struct KvClient {
-    api_url: Arc<String>,
-    credentials: Arc<Credentials>,
-    http_client: Arc<reqwest::Client>,
+    transport: Arc<Transport>,
 }
 
 impl KvClient {
-    pub fn new(
-        credentials: Arc<Credentials>,
-        api_url: Option<Arc<String>>,
-        http_client: Option<Arc<reqwest::Client>>,
-    ) -> Self {
-        Self {
-            api_url: api_url.unwrap_or(Arc::new(API_URL.to_string())),
-            credentials,
-            http_client: http_client.unwrap_or_default(),
-        }
+    pub fn new(transport: Arc<Transport>) -> Self {
+        Self { transport }
     }
 
     pub async fn list_namespaces(
         &self,
         input: KvNamespacesListInput,
     ) -> Result<KvNamespaces, KvError> {
-        let url = format!(
-            "{}/accounts/{}/storage/kv/namespaces",
-            self.api_url, input.account_id,
-        );
-
+        let path = format!("/accounts/{}/storage/kv/namespaces", input.account_id);
         let query_parameters: HashMap<String, String> = input.into();
-        let response = self
-            .http_client
-            .get(&url)
-            .headers(self.credentials.headers())
-            .query(&query_parameters)
-            .send()
-            .await?;
 
-        self.handle_api_response::<ApiPaginatedResponse<Vec<KvNamespace>>, KvNamespaces>(response)
+        self.transport
+            .get(&path)
+            .query(&query_parameters)
+            .fetch::<ApiPaginatedResponse<Vec<KvNamespace>>, _, _>()
             .await
     }
 
-    pub async fn get_namespace(&self, input: KvNamespaceGetInput) -> Result<KvNamespace, KvError> {
-        let url = format!(
-            "{}/accounts/{}/storage/kv/namespaces/{}",
-            self.api_url, input.account_id, input.namespace_id
-        );
-
-        let response = self
-            .http_client
-            .get(&url)
-            .headers(self.credentials.headers())
-            .send()
-            .await?;
+    // The page of the input is ignored, every page is requested until all namespaces are listed.
+    pub fn list_all_namespaces(
+        &self,
+        input: KvNamespacesListInput,
+    ) -> impl Stream<Item = Result<KvNamespace, KvError>> + Send + '_ {
+        paginate_pages(move |page| {
+            let input = KvNamespacesListInput {
+                page: Some(page),
+                per_page: Some(input.per_page.unwrap_or(MAX_NAMESPACES_PER_PAGE)),
+                ..input.clone()
+            };
+            async move {
+                let namespaces = self.list_namespaces(input).await?;
+                Ok(Page::from_page_info(
+                    namespaces.items,
+                    &namespaces.page_info,
+                ))
+            }
+        })
+    }
 
-        self.handle_api_response::<ApiResponse<KvNamespace>, KvNamespace>(response)
+    pub async fn get_namespace(&self, input: KvNamespaceGetInput) -> Result<KvNamespace, KvError> {
+        self.transport
+            .get(&format!(
+                "/accounts/{}/storage/kv/namespaces/{}",
+                input.account_id, input.namespace_id
+            ))
+            .fetch::<ApiResponse<KvNamespace>, _, _>()
             .await
     }
 
@@ -85,20 +84,16 @@ impl KvClient {
         &self,
         input: KvNamespaceCreateInput,
     ) -> Result<KvNamespace, KvError> {
-        let url = format!(
-            "{}/accounts/{}/storage/kv/namespaces",
-            self.api_url, input.account_id
-        );
-
-        let response = self
-            .http_client
-            .post(&url)
-            .headers(self.credentials.headers())
-            .json(&HashMap::from([("title", input.title)]))
-            .send()
-            .await?;
-
-        self.handle_api_response::<ApiResponse<KvNamespace>, KvNamespace>(response)
+        // Creating a namespace is not safe to repeat.
+        let body = HashMap::from([("title", input.title)]);
+        self.transport
+            .post(&format!(
+                "/accounts/{}/storage/kv/namespaces",
+                input.account_id
+            ))
+            .json(&body)
+            .without_retries()
+            .fetch::<ApiResponse<KvNamespace>, _, _>()
             .await
     }
 
@@ -106,48 +101,30 @@ impl KvClient {
         &self,
         input: KvNamespaceUpdateInput,
     ) -> Result<KvNamespace, KvError> {
-        let url = format!(
-            "{}/accounts/{}/storage/kv/namespaces/{}",
-            self.api_url, input.account_id, input.namespace_id
-        );
-
-        let response = self
-            .http_client
-            .put(&url)
-            .headers(self.credentials.headers())
-            .json(&HashMap::from([("title", input.title)]))
-            .send()
-            .await?;
-
-        self.handle_api_response::<ApiResponse<KvNamespace>, KvNamespace>(response)
+        let body = HashMap::from([("title", input.title)]);
+        self.transport
+            .put(&format!(
+                "/accounts/{}/storage/kv/namespaces/{}",
+                input.account_id, input.namespace_id
+            ))
+            .json(&body)
+            .fetch::<ApiResponse<KvNamespace>, _, _>()
             .await
     }
 
     pub async fn delete_namespace(&self, input: KvNamespaceDeleteInput) -> Result<(), KvError> {
-        let url = format!(
-            "{}/accounts/{}/storage/kv/namespaces/{}",
-            self.api_url, input.account_id, input.namespace_id
-        );
-
-        let response = self
-            .http_client
-            .delete(&url)
-            .headers(self.credentials.headers())
-            .send()
+        self.transport
+            .delete(&format!(
+                "/accounts/{}/storage/kv/namespaces/{}",
+                input.account_id, input.namespace_id
+            ))
+            .send::<KvError>()
             .await?;
 
-        match response.status() {
-            StatusCode::OK => Ok(()),
-            _ => Err(self.handle_api_error_response(response).await),
-        }
+        Ok(())
     }
 
     pub async fn list_keys(&self, input: KvKeysListInput) -> Result<KvKeys, KvError> {
-        let url = format!(
-            "{}/accounts/{}/storage/kv/namespaces/{}/keys",
-            self.api_url, input.account_id, input.namespace_id
-        );
-
         let limit = input.limit.map_or(Some("1000".to_string()), |l| {
             if l < 10 {
                 Some("10".to_string())
@@ -155,80 +132,80 @@ impl KvClient {
                 Some(l.to_string())
             }
         });
-        let response = self
-            .http_client
-            .get(&url)
-            .headers(self.credentials.headers())
-            .query(&[
-                ("limit", limit),
-                ("cursor", input.cursor),
-                ("prefix", input.prefix),
-            ])
-            .send()
-            .await?;
-
-        self.handle_api_response::<ApiCursorPaginatedResponse<Vec<KvKey>>, KvKeys>(response)
+        let query_parameters = [
+            ("limit", limit),
+            ("cursor", input.cursor),
+            ("prefix", input.prefix),
+        ];
+
+        self.transport
+            .get(&format!(
+                "/accounts/{}/storage/kv/namespaces/{}/keys",
+                input.account_id, input.namespace_id
+            ))
+            .query(&query_parameters)
+            .fetch::<ApiCursorPaginatedResponse<Vec<KvKey>>, _, _>()
             .await
     }
 
+    // The cursor of the input is ignored, the keys are listed from the start.
+    pub fn list_all_keys(
+        &self,
+        input: KvKeysListInput,
+    ) -> impl Stream<Item = Result<KvKey, KvError>> + Send + '_ {
+        paginate_cursor(move |cursor| {
+            let input = KvKeysListInput {
+                cursor,
+                ..input.clone()
+            };
+            async move {
+                let keys = self.list_keys(input).await?;
+                Ok(Page::from_cursor(keys.keys, keys.cursor))
+            }
+        })
+    }
+
     pub async fn get_kv_pair(&self, input: KvPairGetInput) -> Result<KvPair, KvError> {
-        let url = format!(
-            "{}/accounts/{}/storage/kv/namespaces/{}/values/{}",
-            self.api_url,
+        let path = format!(
+            "/accounts/{}/storage/kv/namespaces/{}/values/{}",
             input.account_id,
             input.namespace_id,
             url_encode_key(&input.key)
         );
 
-        let get_val_req = self
-            .http_client
-            .get(&url)
-            .headers(self.credentials.headers());
+        let get_val_req = self.transport.get(&path).send::<KvError>();
         let get_metadata_req = self.get_kv_pair_metadata(input.clone().into());
-        let (resp_result, metadata_result) = join!(get_val_req.send(), get_metadata_req);
+        let (resp_result, metadata_result) = join!(get_val_req, get_metadata_req);
 
         let response = resp_result?;
-        match response.status() {
-            StatusCode::OK => {
-                let expiration = response
-                    .headers()
-                    .get("expiration")
-                    .and_then(|header_val| header_val.to_str().ok())
-                    .and_then(|str_val| str_val.parse::<i64>().ok())
-                    .and_then(|timestamp| DateTime::from_timestamp(timestamp, 0));
-                let value = response.bytes().await?.to_vec();
-
-                Ok(KvPair {
-                    key: input.key,
-                    value,
-                    expiration,
-                    metadata: metadata_result?,
-                })
-            }
-            _ => Err(self.handle_api_error_response(response).await),
-        }
+        let expiration = response
+            .headers()
+            .get("expiration")
+            .and_then(|header_val| header_val.to_str().ok())
+            .and_then(|str_val| str_val.parse::<i64>().ok())
+            .and_then(|timestamp| DateTime::from_timestamp(timestamp, 0));
+        let value = response.bytes().await?.to_vec();
+
+        Ok(KvPair {
+            key: input.key,
+            value,
+            expiration,
+            metadata: metadata_result?,
+        })
     }
 
     pub async fn get_kv_pair_metadata(
         &self,
         input: KvPairMetadataGetInput,
     ) -> Result<KvPairMetadata, KvError> {
-        let url = format!(
-            "{}/accounts/{}/storage/kv/namespaces/{}/metadata/{}",
-            self.api_url,
-            input.account_id,
-            input.namespace_id,
-            url_encode_key(&input.key)
-        );
-
-        let response = self
-            .http_client
-            .get(&url)
-            .headers(self.credentials.headers())
-            .send()
-            .await?;
-
-        self.handle_api_response::<ApiResponse<KvPairMetadata>, KvPairMetadata>(response)
+        self.transport
+            .get(&format!(
+                "/accounts/{}/storage/kv/namespaces/{}/metadata/{}",
+                input.account_id,
+                input.namespace_id,
+                url_encode_key(&input.key)
+            ))
+            .fetch::<ApiResponse<KvPairMetadata>, _, _>()
             .await
     }
 
@@ -242,6 +219,7 @@ impl KvClient {
                 KvValuesResult::WithMetadata(values_with_metadata) => Ok(values_with_metadata
                     .values
                     .iter()
+                    .filter_map(|(key, value)| Some((key, value.as_ref()?)))
                     .map(|(key, value)| {
                         let mut byte_value: Vec<u8> = vec![];
                         if let Some(value_str) = value.value.as_str() {
@@ -258,7 +236,7 @@ impl KvClient {
                     .collect()),
             },
             Err(error) => match error {
-                KvError::NonTextValue => {
+                KvError::NonTextValue(_) => {
                     let kv_pair_futures: Vec<_> = input
                         .keys
                         .iter()
@@ -281,31 +259,92 @@ impl KvClient {
         }
     }
 
+    // Reads the keys through the bulk endpoint, where missing keys come back as null and are left
+    // out of the result. The endpoint rejects a whole chunk for a single non-text value, so such a
+    // chunk is halved until the non-text keys are found and read one by one.
+    pub async fn find_kv_pairs(&self, input: KvPairsGetInput) -> Result<Vec<KvPair>, KvError> {
+        let mut found_pairs = HashMap::new();
+        let mut chunks: Vec<Vec<String>> = input
+            .keys
+            .chunks(BULK_GET_KEY_LIMIT)
+            .rev()
+            .map(<[String]>::to_vec)
+            .collect();
+        while let Some(keys) = chunks.pop() {
+            let chunk_input = KvPairsGetInput {
+                account_id: input.account_id.clone(),
+                namespace_id: input.namespace_id.clone(),
+                keys,
+            };
+            match self.get_kv_values(chunk_input.clone().into()).await {
+                Ok(KvValuesResult::WithMetadata(values)) => {
+                    found_pairs.extend(values.values.into_iter().filter_map(|(key, value)| {
+                        let value = value?;
+                        let text = value.value.as_str()?.to_string();
+                        Some((
+                            key.clone(),
+                            KvPair {
+                                key,
+                                value: text.into_bytes(),
+                                expiration: value.expiration,
+                                metadata: value.metadata,
+                            },
+                        ))
+                    }))
+                }
+                Ok(KvValuesResult::Raw(_)) => {
+                    return Err(KvError::Unknown("Cannot handle raw KV values.".to_string()));
+                }
+                Err(KvError::NonTextValue(_)) if chunk_input.keys.len() > 1 => {
+                    let mut keys = chunk_input.keys;
+                    let second_half = keys.split_off(keys.len() / 2);
+                    chunks.push(second_half);
+                    chunks.push(keys);
+                }
+                Err(KvError::NonTextValue(_)) => {
+                    let key = chunk_input.keys[0].clone();
+                    match self
+                        .get_kv_pair(KvPairGetInput {
+                            account_id: chunk_input.account_id,
+                            namespace_id: chunk_input.namespace_id,
+                            key: key.clone(),
+                        })
+                        .await
+                    {
+                        Ok(pair) => {
+                            found_pairs.insert(key, pair);
+                        }
+                        Err(KvError::KeyNotFound(_)) => {}
+                        Err(error) => return Err(error),
+                    }
+                }
+                Err(error) => return Err(error),
+            }
+        }
+
+        Ok(input
+            .keys
+            .iter()
+            .filter_map(|key| found_pairs.remove(key))
+            .collect())
+    }
+
     pub async fn get_kv_values(&self, input: KvValuesGetInput) -> Result<KvValuesResult, KvError> {
-        let url = format!(
-            "{}/accounts/{}/storage/kv/namespaces/{}/bulk/get",
-            self.api_url, input.account_id, input.namespace_id
+        let path = format!(
+            "/accounts/{}/storage/kv/namespaces/{}/bulk/get",
+            input.account_id, input.namespace_id
         );
+        let request = self.transport.post(&path).json(&input);
 
-        let response = self
-            .http_client
-            .post(&url)
-            .json(&input)
-            .headers(self.credentials.headers())
-            .send()
-            .await?;
-
-        if let Some(with_metadata) = input.with_metadata {
-            if with_metadata {
-                let values_with_metadata = self
-                    .handle_api_response::<ApiResponse<KvValues>, KvValues>(response)
-                    .await?;
-                return Ok(KvValuesResult::WithMetadata(values_with_metadata));
-            }
+        if input.with_metadata == Some(true) {
+            let values_with_metadata = request
+                .fetch::<ApiResponse<KvValues>, KvValues, KvError>()
+                .await?;
+            return Ok(KvValuesResult::WithMetadata(values_with_metadata));
         }
 
-        let values_raw = self
-            .handle_api_response::<ApiResponse<KvValuesRaw>, KvValuesRaw>(response)
+        let values_raw = request
+            .fetch::<ApiResponse<KvValuesRaw>, KvValuesRaw, KvError>()
             .await?;
         Ok(KvValuesResult::Raw(values_raw))
     }
@@ -316,16 +355,15 @@ impl KvClient {
         match kv_pair_result {
             Ok(_) => Err(KvError::KeyAlreadyExists(input.key.clone())),
             Err(error) => match error {
-                KvError::KeyNotFound => self.write_kv_pair(input.into()).await,
+                KvError::KeyNotFound(_) => self.write_kv_pair(input.into()).await,
                 _ => Err(error),
             },
         }
     }
 
     pub async fn write_kv_pair(&self, input: KvPairWriteInput) -> Result<KvPair, KvError> {
-        let url = format!(
-            "{}/accounts/{}/storage/kv/namespaces/{}/values/{}",
-            self.api_url,
+        let path = format!(
+            "/accounts/{}/storage/kv/namespaces/{}/values/{}",
             input.account_id,
             input.namespace_id,
             url_encode_key(&input.key)
@@ -335,14 +373,10 @@ impl KvClient {
             .expiration
             .map(|expiration_date| expiration_date.timestamp().to_string());
         let expiration_ttl = input.expiration_ttl.map(|ttl| ttl.to_string());
-        let request = self
-            .http_client
-            .put(url)
-            .headers(self.credentials.headers())
-            .query(&[
-                ("expiration", expiration),
-                ("expiration_ttl", expiration_ttl),
-            ]);
+        let query_parameters = [
+            ("expiration", expiration),
+            ("expiration_ttl", expiration_ttl),
+        ];
 
         let value = input.value.unwrap_or_default();
         let mut metadata = String::from("null");
@@ -350,124 +384,70 @@ impl KvClient {
             metadata = serde_json::to_string(&metadata_value).unwrap_or_default();
         }
 
-        let value_as_bytes: Vec<u8> = Vec::from(value.clone());
-        let part = Part::bytes(value_as_bytes);
-        let form_data = Form::new().part("value", part).text("metadata", metadata);
-        let response = request.multipart(form_data).send().await?;
+        self.transport
+            .put(&path)
+            .query(&query_parameters)
+            .multipart(|| {
+                Form::new()
+                    .part("value", Part::bytes(value.clone()))
+                    .text("metadata", metadata.clone())
+            })
+            .send::<KvError>()
+            .await?;
 
-        match response.status() {
-            StatusCode::OK => Ok(KvPair {
-                key: input.key.to_string(),
-                value,
-                expiration: input.expiration,
-                metadata: input.metadata,
-            }),
-            _ => Err(self.handle_api_error_response(response).await),
-        }
+        Ok(KvPair {
+            key: input.key.to_string(),
+            value,
+            expiration: input.expiration,
+            metadata: input.metadata,
+        })
     }
 
     pub async fn write_kv_pairs(
-        self,
+        &self,
         input: KvPairsWriteInput,
     ) -> Result<KvPairsWriteResult, KvError> {
-        let url = format!(
-            "{}/accounts/{}/storage/kv/namespaces/{}/bulk",
-            self.api_url, input.account_id, input.namespace_id
-        );
-
         let pairs: Vec<KvPairBulkWriteInput> = input
             .pairs
             .into_iter()
             .map(|pair| pair.into_text_value())
             .collect();
 
-        let response = self
-            .http_client
-            .put(&url)
+        self.transport
+            .put(&format!(
+                "/accounts/{}/storage/kv/namespaces/{}/bulk",
+                input.account_id, input.namespace_id
+            ))
             .json(&pairs)
-            .headers(self.credentials.headers())
-            .send()
-            .await?;
-
-        let write_result = self
-            .handle_api_response::<ApiResponse<KvPairsWriteResult>, KvPairsWriteResult>(response)
-            .await?;
-
-        Ok(write_result)
+            .fetch::<ApiResponse<KvPairsWriteResult>, _, _>()
+            .await
     }
 
     pub async fn delete_kv_pairs(
         &self,
         input: KvPairsDeleteInput,
     ) -> Result<KvPairsDeleteResult, KvError> {
-        let url = format!(
-            "{}/accounts/{}/storage/kv/namespaces/{}/bulk/delete",
-            self.api_url, input.account_id, input.namespace_id,
-        );
-
-        let response = self
-            .http_client
-            .post(url)
-            .headers(self.credentials.headers())
+        self.transport
+            .post(&format!(
+                "/accounts/{}/storage/kv/namespaces/{}/bulk/delete",
+                input.account_id, input.namespace_id
+            ))
             .json(&input.keys)
-            .send()
-            .await?;
-
-        self.handle_api_response::<ApiResponse<KvPairsDeleteResult>, KvPairsDeleteResult>(response)
+            .fetch::<ApiResponse<KvPairsDeleteResult>, _, _>()
             .await
     }
-
-    async fn handle_api_response<T: for<'a> Deserialize<'a>, R: From<T>>(
-        &self,
-        response: Response,
-    ) -> Result<R, KvError> {
-        match response.status() {
-            StatusCode::OK => {
-                let api_result: T = response.json().await?;
-                Ok(api_result.into())
-            }
-            _ => Err(self.handle_api_error_response(response).await),
-        }
-    }
-
-    async fn handle_api_error_response(&self, response: Response) -> KvError {
-        let api_response_result = response.json::<ApiErrorResponse>().await;
-        match api_response_result {
-            Ok(api_response) => self.map_api_errors(api_response.errors),
-            Err(error) => error.into(),
-        }
-    }
-
-    fn map_api_errors(&self, errors: Vec<ApiError>) -> KvError {
-        if errors.is_empty() {
-            return KvError::Unknown("No errors in the response.".to_string());
-        }
-
-        let error = &errors[0];
-        match error.code {
-            10000 => KvError::Token(TokenError::Invalid),
-            10001 => KvError::Token(TokenError::Invalid),
-            10009 => KvError::KeyNotFound,
-            10013 => KvError::NamespaceNotFound,
-            10014 => KvError::NamespaceAlreadyExists(error.message.clone()),
-            10019 => KvError::NamespaceTitleMissing(error.message.clone()),
-            10029 => KvError::NonTextValue,
-            10033 => KvError::InvalidExpiration,
-            10147 => KvError::InvalidMetadata,
-            _ => KvError::Unknown(error.message.clone()),
-        }
-    }
 }
 
 #[cfg(test)]
 mod test {
-    use crate::cloudflare::common::Credentials;
+    use crate::cloudflare::common::{Credentials, Transport};
     use crate::cloudflare::kv::KvClient;
     use std::sync::Arc;
 
     mod list_namespaces {
         use crate::cloudflare::common::{
-            ApiError, ApiErrorResponse, ApiPaginatedResponse, OrderDirection, PageInfo, TokenError,
+            ApiError, ApiErrorResponse, ApiMessage, ApiPaginatedResponse, OrderDirection, PageInfo,
+            TokenError,
         };
         use crate::cloudflare::kv::kv_client::test::create_kv_client;
         use crate::cloudflare::kv::{
@@ -530,7 +510,7 @@ mod test {
         }
 
         #[tokio::test]
-        async fn should_respond_with_an_unknown_error_if_no_errors_are_available()
+        async fn should_respond_with_an_api_error_if_no_errors_are_available()
         -> Result<(), TokenError> {
             let account_id = "account_id".to_string();
             let mock_server = create_failing_mock_server(&account_id, vec![]).await;
@@ -549,19 +529,19 @@ mod test {
             assert!(namespaces_result.is_err());
 
             let error = namespaces_result.unwrap_err();
-            assert!(matches!(error, KvError::Unknown(_)));
+            assert!(matches!(error, KvError::Api(_)));
 
             let error_message = match error {
-                KvError::Unknown(message) => message,
+                KvError::Api(api_error) => api_error.message(),
                 _ => "".to_string(),
             };
-            assert_eq!(error_message, "No errors in the response.");
+            assert_eq!(error_message, "The request failed with status 400");
 
             Ok(())
         }
 
         #[tokio::test]
-        async fn should_respond_with_an_token_error_if_the_request_could_not_be_authenticated()
+        async fn should_respond_with_an_unauthorized_error_if_the_request_could_not_be_authenticated()
         -> Result<(), TokenError> {
             let account_id = "account_id".to_string();
             let error_message = "Unable to authenticate request";
@@ -588,13 +568,65 @@ mod test {
             assert!(namespaces_result.is_err());
 
             let error = namespaces_result.unwrap_err();
-            assert!(matches!(error, KvError::Token(_)));
+            assert!(matches!(
+                error,
+                KvError::Unauthorized(api_error) if api_error.message() == error_message
+            ));
 
-            let user_error = match error {
-                KvError::Token(error) => error,
-                _ => TokenError::Unknown("".to_string()),
+            Ok(())
+        }
+
+        #[tokio::test]
+        async fn should_keep_all_errors_messages_and_the_ray_id() -> Result<(), TokenError> {
+            let account_id = "account_id".to_string();
+            let mock_server = MockServer::start().await;
+            Mock::given(method("GET"))
+                .and(path(format!(
+                    "/client/v4/accounts/{account_id}/storage/kv/namespaces"
+                )))
+                .respond_with(
+                    ResponseTemplate::new(400)
+                        .insert_header("cf-ray", "8f2b3c4d5e6f7a8b-FRA")
+                        .set_body_json(ApiErrorResponse {
+                            errors: vec![
+                                ApiError {
+                                    code: 10021,
+                                    message: "Invalid per_page".to_string(),
+                                },
+                                ApiError {
+                                    code: 10022,
+                                    message: "Invalid page".to_string(),
+                                },
+                            ],
+                            messages: vec![ApiMessage {
+                                code: None,
+                                message: "Check the pagination parameters".to_string(),
+                            }],
+                        }),
+                )
+                .mount(&mock_server)
+                .await;
+
+            let kv = create_kv_client(mock_server.uri());
+            let namespaces_result = kv
+                .list_namespaces(KvNamespacesListInput {
+                    account_id: account_id.clone(),
+                    order_by: None,
+                    order_direction: None,
+                    page: Some(0),
+                    per_page: Some(0),
+                })
+                .await;
+
+            let api_error = match namespaces_result {
+                Err(KvError::Api(api_error)) => api_error,
+                _ => panic!("Expected an API error"),
             };
-            assert!(matches!(user_error, TokenError::Invalid));
+            assert_eq!(api_error.status, 400);
+            assert_eq!(api_error.ray_id, Some("8f2b3c4d5e6f7a8b-FRA".to_string()));
+            assert_eq!(api_error.errors.len(), 2);
+            assert_eq!(api_error.messages.len(), 1);
+            assert_eq!(api_error.message(), "Invalid per_page, Invalid page");
 
             Ok(())
         }
@@ -651,7 +683,10 @@ mod test {
                 .and(path(format!(
                     "/client/v4/accounts/{account_id}/storage/kv/namespaces"
                 )))
-                .respond_with(ResponseTemplate::new(400).set_body_json(ApiErrorResponse { errors }))
+                .respond_with(ResponseTemplate::new(400).set_body_json(ApiErrorResponse {
+                    errors,
+                    messages: vec![],
+                }))
                 .mount(&mock_server)
                 .await;
 
@@ -659,6 +694,66 @@ mod test {
         }
     }
 
+    mod list_all_namespaces {
+        use crate::cloudflare::common::{ApiPaginatedResponse, PageInfo};
+        use crate::cloudflare::kv::kv_client::test::create_kv_client;
+        use crate::cloudflare::kv::{KvError, KvNamespace, KvNamespacesListInput};
+        use futures::TryStreamExt;
+        use wiremock::matchers::{method, path, query_param};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        #[tokio::test]
+        async fn should_list_the_namespaces_of_every_page() -> Result<(), KvError> {
+            let mock_server = MockServer::start().await;
+            let namespaces: Vec<KvNamespace> = (1..=5)
+                .map(|index| KvNamespace {
+                    id: format!("namespace_id_{index}"),
+                    title: format!("namespace_title_{index}"),
+                    beta: Some(false),
+                    supports_url_encoding: Some(true),
+                })
+                .collect();
+
+            for (index, page) in namespaces.chunks(2).enumerate() {
+                Mock::given(method("GET"))
+                    .and(path("/client/v4/accounts/account_id/storage/kv/namespaces"))
+                    .and(query_param("page", (index + 1).to_string()))
+                    .and(query_param("per_page", "2"))
+                    .respond_with(
+                        ResponseTemplate::new(200).set_body_json(ApiPaginatedResponse::<
+                            Vec<KvNamespace>,
+                        > {
+                            result: page.to_vec(),
+                            result_info: PageInfo {
+                                total_count: namespaces.len(),
+                                count: page.len(),
+                                page: index + 1,
+                                per_page: 2,
+                            },
+                        }),
+                    )
+                    .expect(1)
+                    .mount(&mock_server)
+                    .await;
+            }
+
+            let kv = create_kv_client(mock_server.uri().to_string());
+            let all_namespaces: Vec<KvNamespace> = kv
+                .list_all_namespaces(KvNamespacesListInput {
+                    account_id: "account_id".to_string(),
+                    order_by: None,
+                    order_direction: None,
+                    page: None,
+                    per_page: Some(2),
+                })
+                .try_collect()
+                .await?;
+
+            assert_eq!(all_namespaces, namespaces);
+            Ok(())
+        }
+    }
+
     mod get_namespace {
         use crate::cloudflare::common::{ApiError, ApiErrorResponse, ApiResponse};
         use crate::cloudflare::kv::kv_client::test::create_kv_client;
@@ -712,7 +807,7 @@ mod test {
             assert!(namespace_result.is_err());
 
             let error = namespace_result.unwrap_err();
-            assert!(matches!(error, KvError::NamespaceNotFound));
+            assert!(matches!(error, KvError::NamespaceNotFound(_)));
 
             Ok(())
         }
@@ -751,7 +846,10 @@ mod test {
                     "/client/v4/accounts/{}/storage/kv/namespaces/{}",
                     input.account_id, input.namespace_id
                 )))
-                .respond_with(ResponseTemplate::new(400).set_body_json(ApiErrorResponse { errors }))
+                .respond_with(ResponseTemplate::new(400).set_body_json(ApiErrorResponse {
+                    errors,
+                    messages: vec![],
+                }))
                 .mount(&mock_server)
                 .await;
 
@@ -813,7 +911,7 @@ mod test {
 
             let error = created_namespace_result.unwrap_err();
             assert!(
-                matches!(error, KvError::NamespaceAlreadyExists(ref error_message) if error_message == expected_error_message)
+                matches!(error, KvError::NamespaceAlreadyExists(ref api_error) if api_error.message() == expected_error_message)
             );
 
             Ok(())
@@ -841,7 +939,7 @@ mod test {
 
             let error = created_namespace_result.unwrap_err();
             assert!(
-                matches!(error, KvError::NamespaceTitleMissing(ref error_message) if error_message == expected_error_message)
+                matches!(error, KvError::NamespaceTitleMissing(ref api_error) if api_error.message() == expected_error_message)
             );
 
             Ok(())
@@ -881,7 +979,10 @@ mod test {
                     "/client/v4/accounts/{}/storage/kv/namespaces",
                     input.account_id
                 )))
-                .respond_with(ResponseTemplate::new(400).set_body_json(ApiErrorResponse { errors }))
+                .respond_with(ResponseTemplate::new(400).set_body_json(ApiErrorResponse {
+                    errors,
+                    messages: vec![],
+                }))
                 .mount(&mock_server)
                 .await;
 
@@ -945,7 +1046,7 @@ mod test {
 
             let error = updated_namespace_result.unwrap_err();
             assert!(
-                matches!(error, KvError::NamespaceAlreadyExists(ref error_message) if error_message == expected_error_message)
+                matches!(error, KvError::NamespaceAlreadyExists(ref api_error) if api_error.message() == expected_error_message)
             );
 
             Ok(())
@@ -975,7 +1076,7 @@ mod test {
 
             let error = updated_namespace_result.unwrap_err();
             assert!(
-                matches!(error, KvError::NamespaceTitleMissing(ref error_message) if error_message == expected_error_message)
+                matches!(error, KvError::NamespaceTitleMissing(ref api_error) if api_error.message() == expected_error_message)
             );
 
             Ok(())
@@ -1003,7 +1104,7 @@ mod test {
             assert!(updated_namespace_result.is_err());
 
             let error = updated_namespace_result.unwrap_err();
-            assert!(matches!(error, KvError::NamespaceNotFound));
+            assert!(matches!(error, KvError::NamespaceNotFound(_)));
 
             Ok(())
         }
@@ -1042,7 +1143,10 @@ mod test {
                     "/client/v4/accounts/{}/storage/kv/namespaces/{}",
                     input.account_id, input.namespace_id
                 )))
-                .respond_with(ResponseTemplate::new(400).set_body_json(ApiErrorResponse { errors }))
+                .respond_with(ResponseTemplate::new(400).set_body_json(ApiErrorResponse {
+                    errors,
+                    messages: vec![],
+                }))
                 .mount(&mock_server)
                 .await;
 
@@ -1093,7 +1197,7 @@ mod test {
             assert!(delete_namespace_result.is_err());
 
             let error = delete_namespace_result.unwrap_err();
-            assert!(matches!(error, KvError::NamespaceNotFound));
+            assert!(matches!(error, KvError::NamespaceNotFound(_)));
 
             Ok(())
         }
@@ -1128,7 +1232,10 @@ mod test {
                     "/client/v4/accounts/{}/storage/kv/namespaces/{}",
                     input.account_id, input.namespace_id
                 )))
-                .respond_with(ResponseTemplate::new(400).set_body_json(ApiErrorResponse { errors }))
+                .respond_with(ResponseTemplate::new(400).set_body_json(ApiErrorResponse {
+                    errors,
+                    messages: vec![],
+                }))
                 .mount(&mock_server)
                 .await;
 
@@ -1287,7 +1394,7 @@ mod test {
             assert!(result.is_err());
 
             let error = result.unwrap_err();
-            assert!(matches!(error, KvError::NamespaceNotFound));
+            assert!(matches!(error, KvError::NamespaceNotFound(_)));
 
             Ok(())
         }
@@ -1341,7 +1448,10 @@ mod test {
                     "/client/v4/accounts/{}/storage/kv/namespaces/{}/keys",
                     input.account_id, input.namespace_id
                 )))
-                .respond_with(ResponseTemplate::new(400).set_body_json(ApiErrorResponse { errors }))
+                .respond_with(ResponseTemplate::new(400).set_body_json(ApiErrorResponse {
+                    errors,
+                    messages: vec![],
+                }))
                 .mount(&mock_server)
                 .await;
 
@@ -1349,6 +1459,76 @@ mod test {
         }
     }
 
+    mod list_all_keys {
+        use crate::cloudflare::common::{ApiCursorPaginatedResponse, CursorPageInfo};
+        use crate::cloudflare::kv::kv_client::test::create_kv_client;
+        use crate::cloudflare::kv::{KvError, KvKey, KvKeysListInput};
+        use futures::TryStreamExt;
+        use wiremock::matchers::{method, path, query_param, query_param_is_missing};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        #[tokio::test]
+        async fn should_follow_the_cursor_until_all_keys_are_listed() -> Result<(), KvError> {
+            let mock_server = MockServer::start().await;
+            let keys_path =
+                "/client/v4/accounts/my_account_id/storage/kv/namespaces/my_namespace/keys";
+            Mock::given(method("GET"))
+                .and(path(keys_path))
+                .and(query_param_is_missing("cursor"))
+                .respond_with(ResponseTemplate::new(200).set_body_json(
+                    ApiCursorPaginatedResponse::<Vec<KvKey>> {
+                        result: vec![create_key("key1"), create_key("key2")],
+                        result_info: CursorPageInfo {
+                            count: 2,
+                            cursor: Some("next_cursor".to_string()),
+                        },
+                    },
+                ))
+                .mount(&mock_server)
+                .await;
+            Mock::given(method("GET"))
+                .and(path(keys_path))
+                .and(query_param("cursor", "next_cursor"))
+                .respond_with(ResponseTemplate::new(200).set_body_json(
+                    ApiCursorPaginatedResponse::<Vec<KvKey>> {
+                        result: vec![create_key("key3")],
+                        result_info: CursorPageInfo {
+                            count: 1,
+                            cursor: Some("".to_string()),
+                        },
+                    },
+                ))
+                .mount(&mock_server)
+                .await;
+
+            let kv = create_kv_client(mock_server.uri());
+            let keys: Vec<KvKey> = kv
+                .list_all_keys(KvKeysListInput {
+                    account_id: "my_account_id".to_string(),
+                    namespace_id: "my_namespace".to_string(),
+                    cursor: None,
+                    limit: None,
+                    prefix: None,
+                })
+                .try_collect()
+                .await?;
+
+            assert_eq!(
+                keys,
+                vec![create_key("key1"), create_key("key2"), create_key("key3")]
+            );
+            Ok(())
+        }
+
+        fn create_key(name: &str) -> KvKey {
+            KvKey {
+                name: name.to_string(),
+                expiration: None,
+                metadata: None,
+            }
+        }
+    }
+
     mod get_kv_pair {
         use chrono::{DateTime, Utc};
         use serde_json::json;
@@ -1411,7 +1591,7 @@ mod test {
             assert!(result.is_err());
 
             let error = result.unwrap_err();
-            assert!(matches!(error, KvError::NamespaceNotFound));
+            assert!(matches!(error, KvError::NamespaceNotFound(_)));
 
             Ok(())
         }
@@ -1438,7 +1618,7 @@ mod test {
             assert!(result.is_err());
 
             let error = result.unwrap_err();
-            assert!(matches!(error, KvError::KeyNotFound));
+            assert!(matches!(error, KvError::KeyNotFound(_)));
 
             Ok(())
         }
@@ -1488,7 +1668,10 @@ mod test {
                     "/client/v4/accounts/{}/storage/kv/namespaces/{}/values/{}",
                     input.account_id, input.namespace_id, input.key
                 )))
-                .respond_with(ResponseTemplate::new(400).set_body_json(ApiErrorResponse { errors }))
+                .respond_with(ResponseTemplate::new(400).set_body_json(ApiErrorResponse {
+                    errors,
+                    messages: vec![],
+                }))
                 .mount(&mock_server)
                 .await;
 
@@ -1571,7 +1754,7 @@ mod test {
             assert!(result.is_err());
 
             let error = result.unwrap_err();
-            assert!(matches!(error, KvError::KeyNotFound));
+            assert!(matches!(error, KvError::KeyNotFound(_)));
 
             Ok(())
         }
@@ -1609,7 +1792,10 @@ mod test {
                     "/client/v4/accounts/{}/storage/kv/namespaces/{}/metadata/{}",
                     input.account_id, input.namespace_id, input.key
                 )))
-                .respond_with(ResponseTemplate::new(400).set_body_json(ApiErrorResponse { errors }))
+                .respond_with(ResponseTemplate::new(400).set_body_json(ApiErrorResponse {
+                    errors,
+                    messages: vec![],
+                }))
                 .mount(&mock_server)
                 .await;
 
@@ -1634,19 +1820,19 @@ mod test {
                 values: HashMap::from([
                     (
                         "key1".to_string(),
-                        KvValue {
+                        Some(KvValue {
                             value: "string value".into(),
                             metadata: Some(HashMap::from([("key".to_string(), "value".into())])),
                             expiration: None,
-                        },
+                        }),
                     ),
                     (
                         "key2".to_string(),
-                        KvValue {
+                        Some(KvValue {
                             value: "string value".into(),
                             metadata: None,
                             expiration: DateTime::from_timestamp(Utc::now().timestamp(), 0),
-                        },
+                        }),
                     ),
                 ]),
             };
@@ -1661,6 +1847,7 @@ mod test {
             let mut expected_kv_pairs = kv_values
                 .values
                 .iter()
+                .filter_map(|(k, v)| Some((k, v.as_ref()?)))
                 .map(|(k, v)| KvPair {
                     key: k.clone(),
                     value: v.value.clone().as_str().unwrap().as_bytes().to_vec(),
@@ -1752,6 +1939,7 @@ mod test {
                         code: 10029,
                         message: "bulk get keys: 'At least one of the requested keys corresponds to a non-text value'".to_string(),
                     }],
+                    messages: vec![],
                 }))
                 .mount(&mock_server)
                 .await;
@@ -1788,6 +1976,130 @@ mod test {
         }
     }
 
+    mod find_kv_pairs {
+        use crate::cloudflare::common::{ApiError, ApiErrorResponse, ApiResponse};
+        use crate::cloudflare::kv::kv_client::test::create_kv_client;
+        use crate::cloudflare::kv::{KvError, KvPair, KvPairMetadata, KvPairsGetInput};
+        use serde_json::{Value, json};
+        use std::collections::HashMap;
+        use wiremock::matchers::{body_partial_json, method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        const BULK_GET_PATH: &str =
+            "/client/v4/accounts/account_id/storage/kv/namespaces/namespace_id/bulk/get";
+
+        #[tokio::test]
+        async fn should_read_only_the_non_text_values_one_by_one() -> Result<(), KvError> {
+            let mock_server = MockServer::start().await;
+            for keys in [
+                vec!["text", "missing", "binary"],
+                vec!["missing", "binary"],
+                vec!["binary"],
+            ] {
+                mount_bulk_get_response(
+                    &mock_server,
+                    keys,
+                    ResponseTemplate::new(400).set_body_json(ApiErrorResponse {
+                        errors: vec![ApiError {
+                            code: 10029,
+                            message: "bulk get keys: 'At least one of the requested keys corresponds to a non-text value'".to_string(),
+                        }],
+                        messages: vec![],
+                    }),
+                )
+                .await;
+            }
+            mount_bulk_get_response(
+                &mock_server,
+                vec!["text"],
+                bulk_get_response(json!({
+                    "text": {"value": "text value", "metadata": {"owner": "team"}}
+                })),
+            )
+            .await;
+            mount_bulk_get_response(
+                &mock_server,
+                vec!["missing"],
+                bulk_get_response(json!({"missing": null})),
+            )
+            .await;
+            Mock::given(method("GET"))
+                .and(path(
+                    "/client/v4/accounts/account_id/storage/kv/namespaces/namespace_id/values/binary",
+                ))
+                .respond_with(ResponseTemplate::new(200).set_body_bytes(vec![0, 159, 146, 150]))
+                .expect(1)
+                .mount(&mock_server)
+                .await;
+            Mock::given(method("GET"))
+                .and(path(
+                    "/client/v4/accounts/account_id/storage/kv/namespaces/namespace_id/metadata/binary",
+                ))
+                .respond_with(
+                    ResponseTemplate::new(200)
+                        .set_body_json(ApiResponse::<KvPairMetadata> { result: None }),
+                )
+                .expect(1)
+                .mount(&mock_server)
+                .await;
+
+            let kv = create_kv_client(mock_server.uri());
+            let pairs = kv
+                .find_kv_pairs(KvPairsGetInput {
+                    account_id: "account_id".to_string(),
+                    namespace_id: "namespace_id".to_string(),
+                    keys: vec![
+                        "text".to_string(),
+                        "missing".to_string(),
+                        "binary".to_string(),
+                    ],
+                })
+                .await?;
+
+            assert_eq!(
+                pairs,
+                vec![
+                    KvPair {
+                        key: "text".to_string(),
+                        value: b"text value".to_vec(),
+                        metadata: Some(HashMap::from([("owner".to_string(), "team".into())])),
+                        expiration: None,
+                    },
+                    KvPair {
+                        key: "binary".to_string(),
+                        value: vec![0, 159, 146, 150],
+                        metadata: None,
+                        expiration: None,
+                    },
+                ]
+            );
+
+            Ok(())
+        }
+
+        async fn mount_bulk_get_response(
+            mock_server: &MockServer,
+            keys: Vec<&str>,
+            response_template: ResponseTemplate,
+        ) {
+            Mock::given(method("POST"))
+                .and(path(BULK_GET_PATH))
+                .and(body_partial_json(json!({ "keys": keys })))
+                .respond_with(response_template)
+                .mount(mock_server)
+                .await;
+        }
+
+        fn bulk_get_response(values: Value) -> ResponseTemplate {
+            ResponseTemplate::new(200).set_body_json(json!({
+                "success": true,
+                "errors": [],
+                "messages": [],
+                "result": {"values": values},
+            }))
+        }
+    }
+
     mod get_kv_values {
         use crate::cloudflare::common::{ApiError, ApiErrorResponse, ApiResponse};
         use crate::cloudflare::kv::kv_client::test::create_kv_client;
@@ -1845,19 +2157,19 @@ mod test {
                 values: HashMap::from([
                     (
                         "key1".to_string(),
-                        KvValue {
+                        Some(KvValue {
                             value: "string value".into(),
                             metadata: Some(HashMap::from([("key".to_string(), "value".into())])),
                             expiration: None,
-                        },
+                        }),
                     ),
                     (
                         "key2".to_string(),
-                        KvValue {
+                        Some(KvValue {
                             value: "string value".into(),
                             metadata: None,
                             expiration: DateTime::from_timestamp(Utc::now().timestamp(), 0),
-                        },
+                        }),
                     ),
                 ]),
             };
@@ -1913,7 +2225,7 @@ mod test {
             assert!(values_result.is_err());
 
             let error = values_result.unwrap_err();
-            assert!(matches!(error, KvError::NonTextValue));
+            assert!(matches!(error, KvError::NonTextValue(_)));
 
             Ok(())
         }
@@ -1958,7 +2270,10 @@ mod test {
                     "/client/v4/accounts/{}/storage/kv/namespaces/{}/bulk/get",
                     input.account_id, input.namespace_id,
                 )))
-                .respond_with(ResponseTemplate::new(400).set_body_json(ApiErrorResponse { errors }))
+                .respond_with(ResponseTemplate::new(400).set_body_json(ApiErrorResponse {
+                    errors,
+                    messages: vec![],
+                }))
                 .mount(&mock_server)
                 .await;
 
@@ -2041,6 +2356,7 @@ mod test {
                         code: 10009,
                         message: "get: 'key not found'".to_string(),
                     }],
+                    messages: vec![],
                 });
             Mock::given(method("GET"))
                 .and(path(format!(
@@ -2167,7 +2483,7 @@ mod test {
             assert!(result.is_err());
 
             let error = result.unwrap_err();
-            assert!(matches!(error, KvError::NamespaceNotFound));
+            assert!(matches!(error, KvError::NamespaceNotFound(_)));
 
             Ok(())
         }
@@ -2199,7 +2515,7 @@ mod test {
             assert!(result.is_err());
 
             let error = result.unwrap_err();
-            assert!(matches!(error, KvError::InvalidMetadata));
+            assert!(matches!(error, KvError::InvalidMetadata(_)));
 
             Ok(())
         }
@@ -2231,7 +2547,7 @@ mod test {
             assert!(result.is_err());
 
             let error = result.unwrap_err();
-            assert!(matches!(error, KvError::InvalidExpiration));
+            assert!(matches!(error, KvError::InvalidExpiration(_)));
 
             Ok(())
         }
@@ -2270,7 +2586,10 @@ mod test {
                     "/client/v4/accounts/{}/storage/kv/namespaces/{}/values/{}",
                     input.account_id, input.namespace_id, input.key,
                 )))
-                .respond_with(ResponseTemplate::new(400).set_body_json(ApiErrorResponse { errors }))
+                .respond_with(ResponseTemplate::new(400).set_body_json(ApiErrorResponse {
+                    errors,
+                    messages: vec![],
+                }))
                 .mount(&mock_server)
                 .await;
 
@@ -2446,7 +2765,7 @@ mod test {
             assert!(delete_result.is_err());
 
             let error = delete_result.unwrap_err();
-            assert!(matches!(error, KvError::NamespaceNotFound));
+            assert!(matches!(error, KvError::NamespaceNotFound(_)));
 
             Ok(())
         }
@@ -2479,7 +2798,10 @@ mod test {
         ) -> MockServer {
             let mock_server = MockServer::start().await;
             let response_template_value =
-                ResponseTemplate::new(400).set_body_json(ApiErrorResponse { errors });
+                ResponseTemplate::new(400).set_body_json(ApiErrorResponse {
+                    errors,
+                    messages: vec![],
+                });
 
             Mock::given(method("POST"))
                 .and(path(format!(
@@ -2494,13 +2816,195 @@ mod test {
         }
     }
 
+    mod retries {
+        use crate::cloudflare::common::{
+            ApiError, ApiErrorResponse, ApiResponse, Credentials, RetryPolicy, Transport,
+        };
+        use crate::cloudflare::kv::{
+            KvClient, KvError, KvNamespace, KvNamespaceCreateInput, KvNamespaceGetInput,
+        };
+        use std::sync::Arc;
+        use std::time::Duration;
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        #[tokio::test]
+        async fn should_retry_after_a_rate_limit() -> Result<(), KvError> {
+            let namespace = KvNamespace {
+                id: "12345".to_string(),
+                title: "MyNamespace".to_string(),
+                beta: Some(false),
+                supports_url_encoding: Some(false),
+            };
+            let mock_server = MockServer::start().await;
+            let namespace_path = "/client/v4/accounts/my_account_id/storage/kv/namespaces/12345";
+
+            Mock::given(method("GET"))
+                .and(path(namespace_path))
+                .respond_with(ResponseTemplate::new(429).insert_header("retry-after", "0"))
+                .up_to_n_times(2)
+                .with_priority(1)
+                .mount(&mock_server)
+                .await;
+            Mock::given(method("GET"))
+                .and(path(namespace_path))
+                .respond_with(ResponseTemplate::new(200).set_body_json(ApiResponse {
+                    result: namespace.clone(),
+                }))
+                .with_priority(2)
+                .mount(&mock_server)
+                .await;
+
+            let kv = create_kv_client_with_fast_retries(mock_server.uri());
+            let fetched_namespace = kv
+                .get_namespace(KvNamespaceGetInput {
+                    account_id: "my_account_id".to_string(),
+                    namespace_id: namespace.id.clone(),
+                })
+                .await?;
+
+            assert_eq!(fetched_namespace, namespace);
+
+            Ok(())
+        }
+
+        #[tokio::test]
+        async fn should_retry_after_a_server_error() -> Result<(), KvError> {
+            let namespace = KvNamespace {
+                id: "12345".to_string(),
+                title: "MyNamespace".to_string(),
+                beta: Some(false),
+                supports_url_encoding: Some(false),
+            };
+            let mock_server = MockServer::start().await;
+            let namespace_path = "/client/v4/accounts/my_account_id/storage/kv/namespaces/12345";
+
+            Mock::given(method("GET"))
+                .and(path(namespace_path))
+                .respond_with(ResponseTemplate::new(503))
+                .up_to_n_times(1)
+                .with_priority(1)
+                .mount(&mock_server)
+                .await;
+            Mock::given(method("GET"))
+                .and(path(namespace_path))
+                .respond_with(ResponseTemplate::new(200).set_body_json(ApiResponse {
+                    result: namespace.clone(),
+                }))
+                .with_priority(2)
+                .mount(&mock_server)
+                .await;
+
+            let kv = create_kv_client_with_fast_retries(mock_server.uri());
+            let fetched_namespace = kv
+                .get_namespace(KvNamespaceGetInput {
+                    account_id: "my_account_id".to_string(),
+                    namespace_id: namespace.id.clone(),
+                })
+                .await?;
+
+            assert_eq!(fetched_namespace, namespace);
+
+            Ok(())
+        }
+
+        #[tokio::test]
+        async fn should_respond_with_rate_limited_error_if_retries_are_exhausted()
+        -> Result<(), KvError> {
+            let mock_server = MockServer::start().await;
+
+            Mock::given(method("GET"))
+                .and(path(
+                    "/client/v4/accounts/my_account_id/storage/kv/namespaces/12345",
+                ))
+                .respond_with(
+                    ResponseTemplate::new(429)
+                        .insert_header("retry-after", "0")
+                        .set_body_json(ApiErrorResponse {
+                            errors: vec![ApiError {
+                                code: 971,
+                                message: "Please wait and consider throttling your request speed"
+                                    .to_string(),
+                            }],
+                            messages: vec![],
+                        }),
+                )
+                .expect(3)
+                .mount(&mock_server)
+                .await;
+
+            let kv = create_kv_client_with_fast_retries(mock_server.uri());
+            let namespace_result = kv
+                .get_namespace(KvNamespaceGetInput {
+                    account_id: "my_account_id".to_string(),
+                    namespace_id: "12345".to_string(),
+                })
+                .await;
+
+            assert!(matches!(
+                namespace_result,
+                Err(KvError::RateLimited(Some(retry_after), api_error))
+                    if retry_after == Duration::ZERO && api_error.code() == Some(971)
+            ));
+
+            Ok(())
+        }
+
+        #[tokio::test]
+        async fn should_not_retry_a_namespace_creation() -> Result<(), KvError> {
+            let mock_server = MockServer::start().await;
+
+            Mock::given(method("POST"))
+                .and(path(
+                    "/client/v4/accounts/my_account_id/storage/kv/namespaces",
+                ))
+                .respond_with(ResponseTemplate::new(503))
+                .expect(1)
+                .mount(&mock_server)
+                .await;
+
+            let kv = create_kv_client_with_fast_retries(mock_server.uri());
+            let namespace_result = kv
+                .create_namespace(KvNamespaceCreateInput {
+                    account_id: "my_account_id".to_string(),
+                    title: "MyNamespace".to_string(),
+                })
+                .await;
+
+            assert!(namespace_result.is_err());
+
+            Ok(())
+        }
+
+        fn create_kv_client_with_fast_retries(host_url: String) -> KvClient {
+            KvClient::new(Arc::new(Transport::new(
+                Credentials::UserAuthToken {
+                    token: "12345".into(),
+                },
+                Some(format!("{host_url}/client/v4")),
+                None,
+                Some(RetryPolicy {
+                    max_retries: 2,
+                    initial_backoff: Duration::from_millis(1),
+                    max_backoff: Duration::from_millis(5),
+                    jitter: false,
+                }),
+                None,
+                None,
+            )))
+        }
+    }
+
     fn create_kv_client(host_url: String) -> KvClient {
-        KvClient::new(
-            Arc::new(Credentials::UserAuthToken {
-                token: "12345".to_string(),
-            }),
-            Some(Arc::new(format!("{host_url}/client/v4"))),
+        KvClient::new(Arc::new(Transport::new(
+            Credentials::UserAuthToken {
+                token: "12345".into(),
+            },
+            Some(format!("{host_url}/client/v4")),
+            None,
+            None,
+            None,
             None,
-        )
+        )))
     }
 }
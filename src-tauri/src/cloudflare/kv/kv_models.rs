@@ -1,18 +1,23 @@
 use crate::cloudflare::common::{
-    ApiCursorPaginatedResponse, ApiPaginatedResponse, ApiResponse, OrderDirection, PageInfo,
-    TokenError,
+    ApiCursorPaginatedResponse, ApiErrorMapping, ApiPaginatedResponse, ApiResponse,
+    CloudflareApiError, OrderDirection, PageInfo, TokenError,
 };
 
 use base64::Engine;
 use base64::prelude::BASE64_STANDARD;
 use chrono::serde::ts_seconds_option;
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, TimeDelta, Utc};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use std::collections::HashMap;
 use std::error::Error;
 use std::fmt;
 use std::fmt::Display;
+use std::time::Duration;
+
+// The bulk write endpoint accepts up to 10,000 keys and 100 MB per request.
+pub const BULK_WRITE_KEY_LIMIT: usize = 10_000;
+const BULK_WRITE_SIZE_LIMIT: usize = 100_000_000;
 
 #[derive(Debug, PartialEq, Clone, Default, Serialize, Deserialize)]
 pub struct KvNamespacesListInput {
@@ -224,7 +229,8 @@ impl From<ApiResponse<KvValuesRaw>> for KvValuesRaw {
 
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 pub struct KvValues {
-    pub values: HashMap<String, KvValue>,
+    // Keys which do not exist are null.
+    pub values: HashMap<String, Option<KvValue>>,
 }
 
 impl From<ApiResponse<KvValues>> for KvValues {
@@ -258,6 +264,14 @@ pub struct KvPair {
     pub expiration: Option<DateTime<Utc>>,
 }
 
+impl KvPair {
+    // The API rejects expirations which are less than a minute away.
+    pub fn expires_too_soon(&self, now: DateTime<Utc>) -> bool {
+        self.expiration
+            .is_some_and(|expiration| expiration < now + TimeDelta::seconds(60))
+    }
+}
+
 pub type KvPairMetadata = Option<HashMap<String, Value>>;
 
 impl From<KvPairGetInput> for KvPairMetadataGetInput {
@@ -353,6 +367,17 @@ pub struct KvPairBulkWriteInput {
 }
 
 impl KvPairBulkWriteInput {
+    // The bytes which end up stored, Base64 text is decoded.
+    pub fn value_bytes(&self) -> Vec<u8> {
+        match &self.value {
+            KvPairValue::Text(text) if self.base64 == Some(true) => BASE64_STANDARD
+                .decode(text)
+                .unwrap_or_else(|_| text.as_bytes().to_vec()),
+            KvPairValue::Text(text) => text.as_bytes().to_vec(),
+            KvPairValue::Binary(binary) => binary.clone(),
+        }
+    }
+
     pub fn into_text_value(self) -> Self {
         match self.value {
             KvPairValue::Text(_) => self,
@@ -375,6 +400,40 @@ impl KvPairBulkWriteInput {
     }
 }
 
+// Splits the writes into requests within the limits of the bulk endpoint. The values are converted
+// to text first, so their size is measured as they are sent. Pairs which exceed the size limit on
+// their own are returned separately.
+pub fn bulk_write_chunks(
+    pairs: Vec<KvPairBulkWriteInput>,
+) -> (Vec<Vec<KvPairBulkWriteInput>>, Vec<KvPairBulkWriteInput>) {
+    let mut chunks = vec![];
+    let mut too_large = vec![];
+    let mut chunk = vec![];
+    // The brackets of the JSON array.
+    let mut chunk_size = 2;
+    for pair in pairs {
+        let pair = pair.into_text_value();
+        // The pair and the comma before it.
+        let pair_size = serde_json::to_vec(&pair).map_or(0, |json| json.len()) + 1;
+        if pair_size + 2 > BULK_WRITE_SIZE_LIMIT {
+            too_large.push(pair);
+            continue;
+        }
+
+        if chunk.len() == BULK_WRITE_KEY_LIMIT || chunk_size + pair_size > BULK_WRITE_SIZE_LIMIT {
+            chunks.push(std::mem::take(&mut chunk));
+            chunk_size = 2;
+        }
+        chunk_size += pair_size;
+        chunk.push(pair);
+    }
+    if !chunk.is_empty() {
+        chunks.push(chunk);
+    }
+
+    (chunks, too_large)
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 #[serde(untagged)]
 pub enum KvPairValue {
@@ -415,19 +474,23 @@ impl From<ApiResponse<KvPairsDeleteResult>> for KvPairsDeleteResult {
 
 #[derive(Debug)]
 pub enum KvError {
-    NamespaceAlreadyExists(String),
-    NamespaceNotFound,
-    NamespaceTitleMissing(String),
+    NamespaceAlreadyExists(CloudflareApiError),
+    NamespaceNotFound(CloudflareApiError),
+    NamespaceTitleMissing(CloudflareApiError),
 
-    KeyNotFound,
+    KeyNotFound(CloudflareApiError),
     KeyAlreadyExists(String),
-    InvalidMetadata,
-    InvalidExpiration,
+    InvalidMetadata(CloudflareApiError),
+    InvalidExpiration(CloudflareApiError),
 
-    NonTextValue,
+    NonTextValue(CloudflareApiError),
 
     Token(TokenError),
+    Unauthorized(CloudflareApiError),
+
+    RateLimited(Option<Duration>, CloudflareApiError),
 
+    Api(CloudflareApiError),
     Reqwest(reqwest::Error),
     Unknown(String),
 }
@@ -448,3 +511,68 @@ impl From<reqwest::Error> for KvError {
         KvError::Reqwest(error)
     }
 }
+
+impl ApiErrorMapping for KvError {
+    fn rate_limited(retry_after: Option<Duration>, api_error: CloudflareApiError) -> Self {
+        KvError::RateLimited(retry_after, api_error)
+    }
+
+    fn from_api_error(api_error: CloudflareApiError) -> Self {
+        match api_error.code() {
+            Some(10000) => KvError::Unauthorized(api_error),
+            Some(10001) => KvError::Unauthorized(api_error),
+            Some(10009) => KvError::KeyNotFound(api_error),
+            Some(10013) => KvError::NamespaceNotFound(api_error),
+            Some(10014) => KvError::NamespaceAlreadyExists(api_error),
+            Some(10019) => KvError::NamespaceTitleMissing(api_error),
+            Some(10029) => KvError::NonTextValue(api_error),
+            Some(10033) => KvError::InvalidExpiration(api_error),
+            Some(10147) => KvError::InvalidMetadata(api_error),
+            _ => KvError::Api(api_error),
+        }
+    }
+}
+
+impl From<TokenError> for KvError {
+    fn from(error: TokenError) -> Self {
+        KvError::Token(error)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{
+        BULK_WRITE_KEY_LIMIT, BULK_WRITE_SIZE_LIMIT, KvPairBulkWriteInput, KvPairValue,
+        bulk_write_chunks,
+    };
+
+    #[test]
+    fn should_split_bulk_writes_by_key_count_and_size() {
+        let mut pairs: Vec<KvPairBulkWriteInput> = (0..BULK_WRITE_KEY_LIMIT + 1)
+            .map(|index| create_pair(&format!("key{index}"), b"value".to_vec()))
+            .collect();
+        pairs.push(create_pair("large1", vec![b'a'; BULK_WRITE_SIZE_LIMIT / 2]));
+        pairs.push(create_pair("large2", vec![b'a'; BULK_WRITE_SIZE_LIMIT / 2]));
+        pairs.push(create_pair("too_large", vec![b'a'; BULK_WRITE_SIZE_LIMIT]));
+
+        let (chunks, too_large) = bulk_write_chunks(pairs);
+
+        let chunk_lengths: Vec<usize> = chunks.iter().map(Vec::len).collect();
+        assert_eq!(chunk_lengths, vec![BULK_WRITE_KEY_LIMIT, 2, 1]);
+        assert_eq!(chunks[1][1].key, "large1");
+        assert_eq!(chunks[2][0].key, "large2");
+        assert_eq!(too_large.len(), 1);
+        assert_eq!(too_large[0].key, "too_large");
+    }
+
+    fn create_pair(key: &str, value: Vec<u8>) -> KvPairBulkWriteInput {
+        KvPairBulkWriteInput {
+            key: key.to_string(),
+            value: KvPairValue::Binary(value),
+            expiration: None,
+            expiration_ttl: None,
+            metadata: None,
+            base64: None,
+        }
+    }
+}
@@ -1,5 +1,7 @@
 use crate::cloudflare::account::AccountClient;
-use crate::cloudflare::common::{Credentials, API_URL};
+use crate::cloudflare::common::{
+    Credentials, RateLimiter, RetryPolicy, TrafficRecorder, Transport,
+};
 use crate::cloudflare::kv::KvClient;
 use crate::cloudflare::user::UserClient;
 use std::sync::Arc;
@@ -8,6 +10,7 @@ pub(crate) mod kv;
 
 pub(crate) mod account;
 pub(crate) mod common;
+pub(crate) mod oauth;
 
 pub(crate) mod user;
 
@@ -18,23 +21,27 @@ pub struct Cloudflare {
 }
 
 impl Cloudflare {
-    pub fn new(credentials: Credentials, api_url: Option<String>) -> Self {
-        let api_url = Arc::new(api_url.unwrap_or(API_URL.to_string()));
-        let credentials = Arc::new(credentials);
-        let http_client = Arc::new(reqwest::Client::new());
+    pub fn new(
+        credentials: Credentials,
+        api_url: Option<String>,
+        http_client: Option<Arc<reqwest::Client>>,
+        retry_policy: Option<RetryPolicy>,
+        rate_limiter: Option<Arc<RateLimiter>>,
+        traffic_recorder: Option<Arc<TrafficRecorder>>,
+    ) -> Self {
+        let transport = Arc::new(Transport::new(
+            credentials,
+            api_url,
+            http_client,
+            retry_policy,
+            rate_limiter,
+            traffic_recorder,
+        ));
 
         Self {
-            accounts: AccountClient::new(
-                credentials.clone(),
-                Some(api_url.clone()),
-                Some(http_client.clone()),
-            ),
-            kv: KvClient::new(
-                credentials.clone(),
-                Some(api_url.clone()),
-                Some(http_client.clone()),
-            ),
-            user: UserClient::new(credentials, Some(api_url), Some(http_client)),
+            accounts: AccountClient::new(transport.clone()),
+            kv: KvClient::new(transport.clone()),
+            user: UserClient::new(transport),
         }
     }
 }
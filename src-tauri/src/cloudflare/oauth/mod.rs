@@ -0,0 +1,5 @@
+mod oauth_client;
+mod oauth_models;
+
+pub use oauth_client::OAuthClient;
+pub use oauth_models::*;
@@ -0,0 +1,124 @@
+use crate::cloudflare::common::{OAUTH_TOKEN_URL, Secret};
+use crate::cloudflare::oauth::{OAuthError, OAuthErrorResponse, OAuthToken};
+use reqwest::StatusCode;
+use std::sync::Arc;
+
+pub struct OAuthClient {
+    token_url: Arc<String>,
+    client_id: String,
+    http_client: Arc<reqwest::Client>,
+}
+
+impl OAuthClient {
+    pub fn new(
+        client_id: &str,
+        token_url: Option<Arc<String>>,
+        http_client: Option<Arc<reqwest::Client>>,
+    ) -> Self {
+        Self {
+            token_url: token_url.unwrap_or(Arc::new(OAUTH_TOKEN_URL.to_string())),
+            client_id: client_id.to_string(),
+            http_client: http_client.unwrap_or_default(),
+        }
+    }
+
+    // The refresh token is rotated on every refresh, so a refresh is never retried.
+    pub async fn refresh_token(&self, refresh_token: &Secret) -> Result<OAuthToken, OAuthError> {
+        let response = self
+            .http_client
+            .post(self.token_url.as_str())
+            .form(&[
+                ("grant_type", "refresh_token"),
+                ("refresh_token", refresh_token.expose()),
+                ("client_id", &self.client_id),
+            ])
+            .send()
+            .await?;
+
+        match response.status() {
+            StatusCode::OK => Ok(response.json::<OAuthToken>().await?),
+            _ => {
+                let error_response = response.json::<OAuthErrorResponse>().await?;
+                match error_response.error.as_str() {
+                    "invalid_grant" => Err(OAuthError::InvalidGrant),
+                    _ => Err(OAuthError::Unknown(
+                        error_response
+                            .error_description
+                            .unwrap_or(error_response.error),
+                    )),
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::cloudflare::common::Secret;
+    use crate::cloudflare::oauth::{OAuthClient, OAuthError};
+    use serde_json::json;
+    use std::sync::Arc;
+    use wiremock::matchers::{body_string_contains, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[tokio::test]
+    async fn should_refresh_a_token() -> Result<(), OAuthError> {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/oauth2/token"))
+            .and(body_string_contains("grant_type=refresh_token"))
+            .and(body_string_contains("refresh_token=old-refresh-token"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "access_token": "new-access-token",
+                "expires_in": 3600,
+                "refresh_token": "new-refresh-token",
+                "scope": "account:read workers_kv:write",
+                "token_type": "bearer"
+            })))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let oauth_client = create_oauth_client(&mock_server.uri());
+        let token = oauth_client
+            .refresh_token(&Secret::from("old-refresh-token"))
+            .await?;
+
+        assert_eq!(token.access_token.expose(), "new-access-token");
+        assert_eq!(token.expires_in, 3600);
+        assert_eq!(
+            token.refresh_token.as_ref().map(Secret::expose),
+            Some("new-refresh-token")
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn should_respond_with_an_invalid_grant_error() {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/oauth2/token"))
+            .respond_with(ResponseTemplate::new(400).set_body_json(json!({
+                "error": "invalid_grant",
+                "error_description": "The refresh token is invalid"
+            })))
+            .mount(&mock_server)
+            .await;
+
+        let oauth_client = create_oauth_client(&mock_server.uri());
+        let result = oauth_client
+            .refresh_token(&Secret::from("old-refresh-token"))
+            .await;
+
+        assert!(matches!(result, Err(OAuthError::InvalidGrant)));
+    }
+
+    pub fn create_oauth_client(host_url: &str) -> OAuthClient {
+        OAuthClient::new(
+            "client-id",
+            Some(Arc::new(format!("{}/oauth2/token", host_url))),
+            None,
+        )
+    }
+}
@@ -0,0 +1,44 @@
+use crate::cloudflare::common::Secret;
+use serde::Deserialize;
+use std::error::Error;
+use std::fmt;
+use std::fmt::{Display, Formatter};
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct OAuthToken {
+    pub access_token: Secret,
+    pub expires_in: i64,
+    pub refresh_token: Option<Secret>,
+    pub scope: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct OAuthErrorResponse {
+    pub error: String,
+    pub error_description: Option<String>,
+}
+
+#[derive(Debug)]
+pub enum OAuthError {
+    InvalidGrant,
+    Reqwest(reqwest::Error),
+    Unknown(String),
+}
+
+impl From<reqwest::Error> for OAuthError {
+    fn from(error: reqwest::Error) -> Self {
+        OAuthError::Reqwest(error)
+    }
+}
+
+impl Error for OAuthError {}
+
+impl Display for OAuthError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match &self {
+            OAuthError::Reqwest(err) => write!(f, "Reqwest error: {}", err),
+            OAuthError::Unknown(message) => write!(f, "OAuthError: {}", message),
+            _ => write!(f, "OAuthError: {:?}", self),
+        }
+    }
+}
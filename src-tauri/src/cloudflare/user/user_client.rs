@@ -1,76 +1,179 @@
 use crate::cloudflare::common::{
-    ApiError, ApiErrorResponse, ApiResponse, Credentials, Token, TokenError, API_URL,
+    ApiPaginatedResponse, ApiResponse, CreatedToken, PermissionGroup, Secret, Token,
+    TokenCreateInput, TokenDetails, Tokens, TokensListInput, Transport,
 };
-use crate::cloudflare::user::UserError;
-use reqwest::{Response, StatusCode};
-use serde::Deserialize;
+use crate::cloudflare::user::{Membership, Memberships, MembershipsListInput, UserError};
+use std::collections::HashMap;
 use std::sync::Arc;
 
 pub struct UserClient {
-    api_url: Arc<String>,
-    credentials: Arc<Credentials>,
-    http_client: Arc<reqwest::Client>,
+    transport: Arc<Transport>,
 }
 
 impl UserClient {
-    pub fn new(
-        credentials: Arc<Credentials>,
-        api_url: Option<Arc<String>>,
-        http_client: Option<Arc<reqwest::Client>>,
-    ) -> Self {
-        Self {
-            api_url: api_url.unwrap_or(Arc::new(API_URL.to_string())),
-            credentials,
-            http_client: http_client.unwrap_or_default(),
-        }
+    pub fn new(transport: Arc<Transport>) -> Self {
+        Self { transport }
     }
 
     pub async fn verify_token(&self) -> Result<Token, UserError> {
-        let url = format!("{}/user/tokens/verify", self.api_url);
+        self.transport
+            .get("/user/tokens/verify")
+            .fetch::<ApiResponse<Token>, _, _>()
+            .await
+    }
 
-        let response = self
-            .http_client
-            .get(&url)
-            .headers(self.credentials.headers())
-            .send()
-            .await?;
+    pub async fn get_token_details(&self, token_id: &str) -> Result<TokenDetails, UserError> {
+        self.transport
+            .get(&format!("/user/tokens/{}", token_id))
+            .fetch::<ApiResponse<TokenDetails>, _, _>()
+            .await
+    }
+
+    pub async fn list_tokens(&self, input: TokensListInput) -> Result<Tokens, UserError> {
+        let query_parameters: HashMap<String, String> = input.into();
+        self.transport
+            .get("/user/tokens")
+            .query(&query_parameters)
+            .fetch::<ApiPaginatedResponse<Vec<TokenDetails>>, _, _>()
+            .await
+    }
 
-        self.handle_api_response::<ApiResponse<Token>, Token>(response)
+    pub async fn list_token_permission_groups(&self) -> Result<Vec<PermissionGroup>, UserError> {
+        self.transport
+            .get("/user/tokens/permission_groups")
+            .fetch::<ApiResponse<Vec<PermissionGroup>>, _, _>()
             .await
     }
 
-    async fn handle_api_response<T: for<'a> Deserialize<'a>, R: From<T>>(
+    pub async fn create_token(&self, input: TokenCreateInput) -> Result<CreatedToken, UserError> {
+        // Creating a token is not safe to repeat.
+        self.transport
+            .post("/user/tokens")
+            .json(&input)
+            .without_retries()
+            .fetch::<ApiResponse<CreatedToken>, _, _>()
+            .await
+    }
+
+    pub async fn roll_token(&self, token_id: &str) -> Result<Secret, UserError> {
+        // A repeated roll would invalidate the value of the previous one.
+        let body = HashMap::<String, String>::new();
+        self.transport
+            .put(&format!("/user/tokens/{}/value", token_id))
+            .json(&body)
+            .without_retries()
+            .fetch::<ApiResponse<Secret>, _, _>()
+            .await
+    }
+
+    pub async fn delete_token(&self, token_id: &str) -> Result<(), UserError> {
+        self.transport
+            .delete(&format!("/user/tokens/{}", token_id))
+            .send::<UserError>()
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn list_memberships(
         &self,
-        response: Response,
-    ) -> Result<R, UserError> {
-        match response.status() {
-            StatusCode::OK => {
-                let api_result: T = response.json().await?;
-                Ok(api_result.into())
-            }
-            _ => Err(self.handle_api_error_response(response).await),
-        }
+        input: MembershipsListInput,
+    ) -> Result<Memberships, UserError> {
+        let query_parameters: HashMap<String, String> = input.into();
+        self.transport
+            .get("/memberships")
+            .query(&query_parameters)
+            .fetch::<ApiPaginatedResponse<Vec<Membership>>, _, _>()
+            .await
     }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::cloudflare::common::{Credentials, Transport};
+    use std::sync::Arc;
+
+    mod create_token {
+        use crate::cloudflare::common::{TokenCreateInput, TokenStatus};
+        use crate::cloudflare::user::UserError;
+        use crate::cloudflare::user::user_client::test::create_user_client;
+        use serde_json::json;
+        use wiremock::matchers::{body_json, method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        #[tokio::test]
+        async fn should_create_a_token_and_return_its_value() -> Result<(), UserError> {
+            let mock_server = MockServer::start().await;
+            Mock::given(method("POST"))
+                .and(path("/client/v4/user/tokens"))
+                .and(body_json(json!({ "name": "Contractor", "policies": [] })))
+                .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                    "result": {
+                        "id": "token-id",
+                        "name": "Contractor",
+                        "status": "active",
+                        "value": "new-token-value"
+                    }
+                })))
+                .expect(1)
+                .mount(&mock_server)
+                .await;
 
-    async fn handle_api_error_response(&self, response: Response) -> UserError {
-        let api_response_result = response.json::<ApiErrorResponse>().await;
-        match api_response_result {
-            Ok(api_response) => self.map_api_errors(api_response.errors),
-            Err(error) => error.into(),
+            let user_client = create_user_client(&mock_server.uri());
+            let created_token = user_client
+                .create_token(TokenCreateInput {
+                    name: "Contractor".to_string(),
+                    policies: vec![],
+                    not_before: None,
+                    expires_on: None,
+                    condition: None,
+                })
+                .await?;
+
+            assert_eq!(created_token.details.id, "token-id");
+            assert_eq!(created_token.details.status, TokenStatus::Active);
+            assert_eq!(created_token.value.expose(), "new-token-value");
+
+            Ok(())
         }
     }
 
-    fn map_api_errors(&self, errors: Vec<ApiError>) -> UserError {
-        if errors.is_empty() {
-            return UserError::Unknown("No errors in the response.".to_string());
-        }
+    mod roll_token {
+        use crate::cloudflare::user::UserError;
+        use crate::cloudflare::user::user_client::test::create_user_client;
+        use serde_json::json;
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        #[tokio::test]
+        async fn should_roll_a_token_without_retrying() -> Result<(), UserError> {
+            let mock_server = MockServer::start().await;
+            Mock::given(method("PUT"))
+                .and(path("/client/v4/user/tokens/token-id/value"))
+                .respond_with(ResponseTemplate::new(500).set_body_json(json!({ "errors": [] })))
+                .expect(1)
+                .mount(&mock_server)
+                .await;
 
-        let error = &errors[0];
-        match error.code {
-            1000 => UserError::Token(TokenError::Invalid),
-            1001 => UserError::Token(TokenError::Invalid),
-            6003 => UserError::Token(TokenError::Invalid),
-            _ => UserError::Unknown(error.message.clone()),
+            let user_client = create_user_client(&mock_server.uri());
+            let result = user_client.roll_token("token-id").await;
+
+            assert!(result.is_err());
+
+            Ok(())
         }
     }
+
+    pub fn create_user_client(host_url: &str) -> super::UserClient {
+        super::UserClient::new(Arc::new(Transport::new(
+            Credentials::UserAuthToken {
+                token: "12345".into(),
+            },
+            Some(format!("{}/client/v4", host_url)),
+            None,
+            None,
+            None,
+            None,
+        )))
+    }
 }
@@ -1,14 +1,102 @@
-use crate::cloudflare::common::TokenError;
+use crate::cloudflare::account::Account;
+use crate::cloudflare::common::{
+    ApiErrorMapping, ApiPaginatedResponse, CloudflareApiError, OrderDirection, PageInfo, TokenError,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::error::Error;
 use std::fmt;
 use std::fmt::{Display, Formatter};
+use std::time::Duration;
+
+#[derive(Debug, PartialEq, Clone, Default, Serialize, Deserialize)]
+pub struct MembershipsListInput {
+    pub account_name: Option<String>,
+    pub status: Option<MembershipStatus>,
+    pub order_direction: Option<OrderDirection>,
+    pub page: Option<u32>,
+    pub per_page: Option<u32>,
+}
+
+impl From<MembershipsListInput> for HashMap<String, String> {
+    fn from(value: MembershipsListInput) -> Self {
+        let mut map = HashMap::new();
+        if let Some(account_name) = value.account_name {
+            map.insert("account.name".to_string(), account_name);
+        }
+
+        if let Some(status) = value.status {
+            map.insert("status".to_string(), status.to_string());
+        }
+
+        if let Some(order_direction) = value.order_direction {
+            map.insert("direction".to_string(), order_direction.to_string());
+        }
+
+        if let Some(page) = value.page {
+            map.insert("page".to_string(), page.to_string());
+        }
+
+        if let Some(per_page) = value.per_page {
+            map.insert("per_page".to_string(), per_page.to_string());
+        }
+
+        map
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct Memberships {
+    pub items: Vec<Membership>,
+    pub page_info: PageInfo,
+}
+
+impl From<ApiPaginatedResponse<Vec<Membership>>> for Memberships {
+    fn from(value: ApiPaginatedResponse<Vec<Membership>>) -> Self {
+        Self {
+            items: value.result,
+            page_info: value.result_info,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct Membership {
+    pub id: String,
+    pub account: Account,
+    #[serde(default)]
+    pub roles: Vec<String>,
+    pub status: MembershipStatus,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+#[serde(rename_all = "lowercase")]
+pub enum MembershipStatus {
+    Accepted,
+    Pending,
+    Rejected,
+}
+
+impl Display for MembershipStatus {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let str = match self {
+            MembershipStatus::Accepted => "accepted".to_string(),
+            MembershipStatus::Pending => "pending".to_string(),
+            MembershipStatus::Rejected => "rejected".to_string(),
+        };
+        write!(f, "{}", str)
+    }
+}
 
 #[derive(Debug)]
 pub enum UserError {
     Token(TokenError),
+    Unauthorized(CloudflareApiError),
+
+    RateLimited(Option<Duration>, CloudflareApiError),
 
+    Api(CloudflareApiError),
     Reqwest(reqwest::Error),
-    Unknown(String),
 }
 
 impl From<reqwest::Error> for UserError {
@@ -17,6 +105,27 @@ impl From<reqwest::Error> for UserError {
     }
 }
 
+impl From<TokenError> for UserError {
+    fn from(err: TokenError) -> Self {
+        UserError::Token(err)
+    }
+}
+
+impl ApiErrorMapping for UserError {
+    fn rate_limited(retry_after: Option<Duration>, api_error: CloudflareApiError) -> Self {
+        UserError::RateLimited(retry_after, api_error)
+    }
+
+    fn from_api_error(api_error: CloudflareApiError) -> Self {
+        match api_error.code() {
+            Some(1000) => UserError::Unauthorized(api_error),
+            Some(1001) => UserError::Unauthorized(api_error),
+            Some(6003) => UserError::Unauthorized(api_error),
+            _ => UserError::Api(api_error),
+        }
+    }
+}
+
 impl Error for UserError {}
 
 impl Display for UserError {
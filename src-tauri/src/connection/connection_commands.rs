@@ -0,0 +1,98 @@
+use crate::cloudflare::common::{ConnectionSettings, ConnectionSettingsError};
+use crate::session::session_registry::SessionRegistry;
+use log::error;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::ErrorKind;
+use std::path::Path;
+use std::sync::Arc;
+use tauri::{AppHandle, Manager, State};
+
+pub const CONNECTION_SETTINGS_FILE_NAME: &str = "connection-settings.json";
+
+#[tauri::command]
+pub fn get_connection_settings(sessions: State<'_, Arc<SessionRegistry>>) -> ConnectionSettings {
+    sessions.connection_settings()
+}
+
+#[tauri::command]
+pub fn update_connection_settings(
+    app: AppHandle,
+    sessions: State<'_, Arc<SessionRegistry>>,
+    settings: ConnectionSettings,
+) -> Result<ConnectionSettings, ConnectionCommandError> {
+    sessions.apply_connection_settings(settings.clone())?;
+
+    let settings_path = app
+        .path()
+        .app_config_dir()
+        .map_err(|error| {
+            error!("The app config directory could not be resolved: {error}");
+            ConnectionCommandError {
+                kind: ConnectionCommandErrorKind::Unknown,
+                message: "Connection settings could not be saved".to_string(),
+            }
+        })?
+        .join(CONNECTION_SETTINGS_FILE_NAME);
+    save_connection_settings(&settings_path, &settings)?;
+
+    Ok(settings)
+}
+
+pub fn load_connection_settings(
+    path: &Path,
+) -> Result<ConnectionSettings, ConnectionSettingsError> {
+    match fs::read(path) {
+        Ok(content) => Ok(serde_json::from_slice(&content)?),
+        Err(error) if error.kind() == ErrorKind::NotFound => Ok(ConnectionSettings::default()),
+        Err(error) => Err(error.into()),
+    }
+}
+
+fn save_connection_settings(
+    path: &Path,
+    settings: &ConnectionSettings,
+) -> Result<(), ConnectionSettingsError> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    let temporary_path = path.with_extension("tmp");
+    fs::write(&temporary_path, serde_json::to_vec_pretty(settings)?)?;
+    fs::rename(&temporary_path, path)?;
+    Ok(())
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ConnectionCommandError {
+    kind: ConnectionCommandErrorKind,
+    message: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub enum ConnectionCommandErrorKind {
+    InvalidSettings,
+    Unknown,
+}
+
+impl From<ConnectionSettingsError> for ConnectionCommandError {
+    fn from(error: ConnectionSettingsError) -> Self {
+        match error {
+            ConnectionSettingsError::InvalidApiUrl(_)
+            | ConnectionSettingsError::InvalidProxyUrl(_)
+            | ConnectionSettingsError::InvalidCaBundle(_) => ConnectionCommandError {
+                kind: ConnectionCommandErrorKind::InvalidSettings,
+                message: error.to_string(),
+            },
+            ConnectionSettingsError::Io(_)
+            | ConnectionSettingsError::Serialization(_)
+            | ConnectionSettingsError::Reqwest(_) => {
+                error!("A connection settings error occurred: {error}");
+                ConnectionCommandError {
+                    kind: ConnectionCommandErrorKind::Unknown,
+                    message: error.to_string(),
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1 @@
+pub mod connection_commands;
@@ -0,0 +1,31 @@
+use crate::cloudflare::common::WRANGLER_OAUTH_CLIENT_ID;
+use crate::cloudflare::oauth::OAuthClient;
+use crate::credentials::credentials_discovery::{
+    DiscoveredCredentialsSummary, discover_from_environment, discover_from_wrangler_config,
+    wrangler_config_path,
+};
+use crate::credentials::credentials_registry::CredentialsRegistry;
+use chrono::Utc;
+use log::warn;
+use std::sync::Arc;
+use tauri::{AppHandle, Manager};
+
+// Only summaries are returned, `login` and `create_profile` accept their ids instead of secrets.
+#[tauri::command]
+pub async fn discover_credentials(app: AppHandle) -> Vec<DiscoveredCredentialsSummary> {
+    let mut discovered_credentials = discover_from_environment(|name| std::env::var(name).ok());
+
+    if let Some(config_path) = wrangler_config_path() {
+        let oauth_client = OAuthClient::new(WRANGLER_OAUTH_CLIENT_ID, None, None);
+        match discover_from_wrangler_config(&config_path, &oauth_client, Utc::now()).await {
+            Ok(wrangler_credentials) => discovered_credentials.extend(wrangler_credentials),
+            Err(error) => warn!(
+                "The wrangler config {} could not be read: {error}",
+                config_path.display()
+            ),
+        }
+    }
+
+    app.state::<Arc<CredentialsRegistry>>()
+        .replace(discovered_credentials)
+}
@@ -0,0 +1,383 @@
+use crate::cloudflare::common::{Credentials, Secret};
+use crate::cloudflare::oauth::OAuthClient;
+use chrono::{DateTime, SecondsFormat, TimeDelta, Utc};
+use log::warn;
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::fmt;
+use std::fmt::{Display, Formatter};
+use std::fs;
+use std::path::{Path, PathBuf};
+use toml_edit::{DocumentMut, value};
+
+pub const API_TOKEN_VARIABLE: &str = "CLOUDFLARE_API_TOKEN";
+pub const ACCOUNT_ID_VARIABLE: &str = "CLOUDFLARE_ACCOUNT_ID";
+pub const API_KEY_VARIABLE: &str = "CLOUDFLARE_API_KEY";
+pub const EMAIL_VARIABLE: &str = "CLOUDFLARE_EMAIL";
+
+const WRANGLER_CONFIG_PATH: &str = ".wrangler/config/default.toml";
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct DiscoveredCredentials {
+    pub source: CredentialsSource,
+    pub account_id: Option<String>,
+    pub credentials: Credentials,
+    pub expires_on: Option<DateTime<Utc>>,
+    pub expired: bool,
+}
+
+// The secrets of discovered credentials never leave the backend, the frontend refers to them by id.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DiscoveredCredentialsSummary {
+    pub id: String,
+    pub source: CredentialsSource,
+    pub credentials_type: String,
+    pub hint: String,
+    pub account_id: Option<String>,
+    pub expires_on: Option<DateTime<Utc>>,
+    pub expired: bool,
+}
+
+impl DiscoveredCredentials {
+    pub fn summarize(&self, id: String) -> DiscoveredCredentialsSummary {
+        DiscoveredCredentialsSummary {
+            id,
+            source: self.source.clone(),
+            credentials_type: self.credentials.type_name().to_string(),
+            hint: self.credentials.hint(),
+            account_id: self.account_id.clone(),
+            expires_on: self.expires_on,
+            expired: self.expired,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub enum CredentialsSource {
+    Environment,
+    WranglerOAuth,
+    WranglerApiToken,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct WranglerConfig {
+    oauth_token: Option<Secret>,
+    expiration_time: Option<DateTime<Utc>>,
+    refresh_token: Option<Secret>,
+    api_token: Option<Secret>,
+}
+
+pub fn discover_from_environment<F>(variable: F) -> Vec<DiscoveredCredentials>
+where
+    F: Fn(&str) -> Option<String>,
+{
+    let account_id = variable(ACCOUNT_ID_VARIABLE);
+    let mut discovered_credentials = vec![];
+
+    if let Some(token) = variable(API_TOKEN_VARIABLE) {
+        discovered_credentials.push(DiscoveredCredentials {
+            source: CredentialsSource::Environment,
+            account_id: account_id.clone(),
+            credentials: Credentials::UserAuthToken {
+                token: token.into(),
+            },
+            expires_on: None,
+            expired: false,
+        });
+    }
+
+    if let (Some(key), Some(email)) = (variable(API_KEY_VARIABLE), variable(EMAIL_VARIABLE)) {
+        discovered_credentials.push(DiscoveredCredentials {
+            source: CredentialsSource::Environment,
+            account_id,
+            credentials: Credentials::UserAuthKey {
+                email,
+                key: key.into(),
+            },
+            expires_on: None,
+            expired: false,
+        });
+    }
+
+    discovered_credentials
+}
+
+// Wrangler keeps using the legacy config in the home directory as long as it exists.
+pub fn wrangler_config_path() -> Option<PathBuf> {
+    let legacy_path = dirs::home_dir().map(|home_dir| home_dir.join(WRANGLER_CONFIG_PATH));
+    let path =
+        dirs::preference_dir().map(|preference_dir| preference_dir.join(WRANGLER_CONFIG_PATH));
+
+    legacy_path
+        .into_iter()
+        .chain(path)
+        .find(|path| path.exists())
+}
+
+pub async fn discover_from_wrangler_config(
+    path: &Path,
+    oauth_client: &OAuthClient,
+    now: DateTime<Utc>,
+) -> Result<Vec<DiscoveredCredentials>, DiscoveryError> {
+    let content = fs::read_to_string(path)?;
+    let config: WranglerConfig = toml::from_str(&content)?;
+    let mut discovered_credentials = vec![];
+
+    if let Some(oauth_token) = config.oauth_token {
+        let mut token = oauth_token;
+        let mut expires_on = config.expiration_time;
+
+        if expires_on.is_some_and(|expires_on| expires_on <= now)
+            && let Some(refresh_token) = &config.refresh_token
+        {
+            match oauth_client.refresh_token(refresh_token).await {
+                Ok(refreshed_token) => {
+                    let refreshed_expires_on = now + TimeDelta::seconds(refreshed_token.expires_in);
+                    // The refresh token is rotated, so wrangler has to get the new one as well.
+                    update_wrangler_config(
+                        path,
+                        &content,
+                        &refreshed_token.access_token,
+                        refreshed_token.refresh_token.as_ref(),
+                        refreshed_expires_on,
+                    )?;
+                    token = refreshed_token.access_token;
+                    expires_on = Some(refreshed_expires_on);
+                }
+                Err(error) => warn!("The wrangler OAuth token could not be refreshed: {error}"),
+            }
+        }
+
+        discovered_credentials.push(DiscoveredCredentials {
+            source: CredentialsSource::WranglerOAuth,
+            account_id: None,
+            credentials: Credentials::UserAuthToken { token },
+            expires_on,
+            expired: expires_on.is_some_and(|expires_on| expires_on <= now),
+        });
+    }
+
+    if let Some(api_token) = config.api_token {
+        discovered_credentials.push(DiscoveredCredentials {
+            source: CredentialsSource::WranglerApiToken,
+            account_id: None,
+            credentials: Credentials::UserAuthToken { token: api_token },
+            expires_on: None,
+            expired: false,
+        });
+    }
+
+    Ok(discovered_credentials)
+}
+
+// Only the token fields are replaced, so the comments, order and everything else wrangler keeps in
+// the config survive. The file is replaced at once, so wrangler never reads a partial config.
+fn update_wrangler_config(
+    path: &Path,
+    content: &str,
+    access_token: &Secret,
+    refresh_token: Option<&Secret>,
+    expires_on: DateTime<Utc>,
+) -> Result<(), DiscoveryError> {
+    let mut config: DocumentMut = content.parse()?;
+    config["oauth_token"] = value(access_token.expose());
+    config["expiration_time"] = value(expires_on.to_rfc3339_opts(SecondsFormat::Millis, true));
+    if let Some(refresh_token) = refresh_token {
+        config["refresh_token"] = value(refresh_token.expose());
+    }
+
+    let temporary_path = path.with_extension("tmp");
+    fs::write(&temporary_path, config.to_string())?;
+    fs::rename(&temporary_path, path)?;
+    Ok(())
+}
+
+#[derive(Debug)]
+pub enum DiscoveryError {
+    Io(std::io::Error),
+    InvalidConfig(toml::de::Error),
+    UneditableConfig(toml_edit::TomlError),
+}
+
+impl From<std::io::Error> for DiscoveryError {
+    fn from(error: std::io::Error) -> Self {
+        DiscoveryError::Io(error)
+    }
+}
+
+impl From<toml::de::Error> for DiscoveryError {
+    fn from(error: toml::de::Error) -> Self {
+        DiscoveryError::InvalidConfig(error)
+    }
+}
+
+impl From<toml_edit::TomlError> for DiscoveryError {
+    fn from(error: toml_edit::TomlError) -> Self {
+        DiscoveryError::UneditableConfig(error)
+    }
+}
+
+impl Error for DiscoveryError {}
+
+impl Display for DiscoveryError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match &self {
+            DiscoveryError::Io(err) => write!(f, "IO error: {}", err),
+            DiscoveryError::InvalidConfig(err) => write!(f, "Invalid config: {}", err),
+            DiscoveryError::UneditableConfig(err) => write!(f, "Uneditable config: {}", err),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{
+        CredentialsSource, DiscoveryError, discover_from_environment, discover_from_wrangler_config,
+    };
+    use crate::cloudflare::common::Credentials;
+    use crate::cloudflare::oauth::OAuthClient;
+    use crate::test_support::create_temp_dir;
+    use chrono::{TimeDelta, TimeZone, Utc};
+    use serde_json::json;
+    use std::collections::HashMap;
+    use std::fs;
+    use std::path::PathBuf;
+    use std::sync::Arc;
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[test]
+    fn should_discover_credentials_from_the_environment() {
+        let variables = HashMap::from([
+            ("CLOUDFLARE_API_TOKEN", "api-token"),
+            ("CLOUDFLARE_ACCOUNT_ID", "12345"),
+            ("CLOUDFLARE_API_KEY", "api-key"),
+            ("CLOUDFLARE_EMAIL", "test@example.com"),
+        ]);
+
+        let discovered_credentials =
+            discover_from_environment(|name| variables.get(name).map(|value| value.to_string()));
+
+        assert_eq!(discovered_credentials.len(), 2);
+        assert_eq!(
+            discovered_credentials[0].credentials,
+            Credentials::UserAuthToken {
+                token: "api-token".into()
+            }
+        );
+        assert_eq!(
+            discovered_credentials[0].account_id,
+            Some("12345".to_string())
+        );
+        assert_eq!(
+            discovered_credentials[1].credentials,
+            Credentials::UserAuthKey {
+                email: "test@example.com".to_string(),
+                key: "api-key".into()
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn should_discover_a_valid_wrangler_oauth_token() -> Result<(), DiscoveryError> {
+        let now = Utc.with_ymd_and_hms(2025, 1, 1, 0, 0, 0).unwrap();
+        let config_path = create_config(
+            r#"
+            oauth_token = "oauth-token"
+            expiration_time = "2025-01-01T01:00:00.000Z"
+            refresh_token = "refresh-token"
+            "#,
+        )?;
+        let oauth_client = OAuthClient::new("client-id", None, None);
+
+        let discovered_credentials =
+            discover_from_wrangler_config(&config_path, &oauth_client, now).await?;
+
+        assert_eq!(discovered_credentials.len(), 1);
+        assert_eq!(
+            discovered_credentials[0].source,
+            CredentialsSource::WranglerOAuth
+        );
+        assert_eq!(
+            discovered_credentials[0].credentials,
+            Credentials::UserAuthToken {
+                token: "oauth-token".into()
+            }
+        );
+        assert!(!discovered_credentials[0].expired);
+
+        fs::remove_dir_all(config_path.parent().unwrap())?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn should_refresh_an_expired_wrangler_oauth_token() -> Result<(), DiscoveryError> {
+        let now = Utc.with_ymd_and_hms(2025, 1, 1, 0, 0, 0).unwrap();
+        let config_path = create_config(
+            r#"
+            # Written by wrangler
+            oauth_token = "expired-oauth-token"
+            expiration_time = "2024-12-31T23:00:00.000Z"
+            refresh_token = "refresh-token"
+            scopes = ["account:read"]
+            "#,
+        )?;
+
+        let mock_server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/oauth2/token"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "access_token": "new-oauth-token",
+                "expires_in": 3600,
+                "refresh_token": "new-refresh-token",
+                "token_type": "bearer"
+            })))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+        let oauth_client = OAuthClient::new(
+            "client-id",
+            Some(Arc::new(format!("{}/oauth2/token", mock_server.uri()))),
+            None,
+        );
+
+        let discovered_credentials =
+            discover_from_wrangler_config(&config_path, &oauth_client, now).await?;
+
+        assert_eq!(
+            discovered_credentials[0].credentials,
+            Credentials::UserAuthToken {
+                token: "new-oauth-token".into()
+            }
+        );
+        assert_eq!(
+            discovered_credentials[0].expires_on,
+            Some(now + TimeDelta::hours(1))
+        );
+        assert!(!discovered_credentials[0].expired);
+
+        let content = fs::read_to_string(&config_path)?;
+        let config: toml::Table = toml::from_str(&content)?;
+        assert!(content.contains("# Written by wrangler"));
+        assert!(content.contains("scopes = [\"account:read\"]"));
+        assert_eq!(config["oauth_token"].as_str(), Some("new-oauth-token"));
+        assert_eq!(config["refresh_token"].as_str(), Some("new-refresh-token"));
+        assert_eq!(
+            config["expiration_time"].as_str(),
+            Some("2025-01-01T01:00:00.000Z")
+        );
+        assert!(!config_path.with_extension("tmp").exists());
+
+        fs::remove_dir_all(config_path.parent().unwrap())?;
+        Ok(())
+    }
+
+    fn create_config(content: &str) -> Result<PathBuf, DiscoveryError> {
+        let directory = create_temp_dir();
+        fs::create_dir_all(&directory)?;
+
+        let config_path = directory.join("default.toml");
+        fs::write(&config_path, content)?;
+        Ok(config_path)
+    }
+}
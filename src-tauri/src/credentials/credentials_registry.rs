@@ -0,0 +1,110 @@
+use crate::cloudflare::common::Credentials;
+use crate::credentials::credentials_discovery::{
+    DiscoveredCredentials, DiscoveredCredentialsSummary,
+};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::sync::{Mutex, MutexGuard};
+use uuid::Uuid;
+
+// Credentials are either entered by the user or refer to discovered ones by their id.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(untagged)]
+pub enum CredentialsInput {
+    Discovered { discovered_credentials_id: String },
+    Entered(Credentials),
+}
+
+// Keeps the discovered credentials until the next discovery replaces them, so ids of an earlier
+// discovery are not resolved anymore.
+#[derive(Default)]
+pub struct CredentialsRegistry {
+    discovered_credentials: Mutex<HashMap<String, Credentials>>,
+}
+
+impl CredentialsRegistry {
+    pub fn replace(
+        &self,
+        discovered_credentials: Vec<DiscoveredCredentials>,
+    ) -> Vec<DiscoveredCredentialsSummary> {
+        let mut registered_credentials = self.lock_discovered_credentials();
+        registered_credentials.clear();
+        discovered_credentials
+            .into_iter()
+            .map(|discovered| {
+                let id = Uuid::new_v4().to_string();
+                let summary = discovered.summarize(id.clone());
+                registered_credentials.insert(id, discovered.credentials);
+                summary
+            })
+            .collect()
+    }
+
+    pub fn resolve(&self, input: CredentialsInput) -> Option<Credentials> {
+        match input {
+            CredentialsInput::Discovered {
+                discovered_credentials_id,
+            } => self
+                .lock_discovered_credentials()
+                .get(&discovered_credentials_id)
+                .cloned(),
+            CredentialsInput::Entered(credentials) => Some(credentials),
+        }
+    }
+
+    fn lock_discovered_credentials(&self) -> MutexGuard<'_, HashMap<String, Credentials>> {
+        self.discovered_credentials
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{CredentialsInput, CredentialsRegistry};
+    use crate::cloudflare::common::Credentials;
+    use crate::credentials::credentials_discovery::{CredentialsSource, DiscoveredCredentials};
+    use serde_json::json;
+
+    #[test]
+    fn should_only_expose_a_hint_of_discovered_credentials() {
+        let registry = CredentialsRegistry::default();
+        let credentials = Credentials::UserAuthToken {
+            token: "discovered-api-token".into(),
+        };
+
+        let summaries = registry.replace(vec![DiscoveredCredentials {
+            source: CredentialsSource::Environment,
+            account_id: None,
+            credentials: credentials.clone(),
+            expires_on: None,
+            expired: false,
+        }]);
+
+        let serialized_summaries = serde_json::to_string(&summaries).unwrap();
+        assert!(!serialized_summaries.contains("discovered-api-token"));
+        assert_eq!(summaries[0].hint, "***oken");
+        assert_eq!(summaries[0].credentials_type, "UserAuthToken");
+
+        let input: CredentialsInput =
+            serde_json::from_value(json!({"discovered_credentials_id": summaries[0].id})).unwrap();
+        assert_eq!(registry.resolve(input.clone()), Some(credentials));
+
+        registry.replace(vec![]);
+        assert_eq!(registry.resolve(input), None);
+    }
+
+    #[test]
+    fn should_resolve_entered_credentials_as_they_are() {
+        let input: CredentialsInput =
+            serde_json::from_value(json!({"type": "UserAuthToken", "token": "entered-token"}))
+                .unwrap();
+
+        assert_eq!(
+            CredentialsRegistry::default().resolve(input),
+            Some(Credentials::UserAuthToken {
+                token: "entered-token".into()
+            })
+        );
+    }
+}
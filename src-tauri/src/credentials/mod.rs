@@ -0,0 +1,3 @@
+pub mod credentials_commands;
+pub mod credentials_discovery;
+pub mod credentials_registry;
@@ -0,0 +1,83 @@
+use crate::cross_account::cross_account_search::{
+    CrossAccountResult, ProfileClient, ProfileKeyMatch, ProfileNamespace, find_key,
+    list_profile_namespaces,
+};
+use crate::environment::environment_labels::NamespaceEnvironments;
+use crate::profile::profile_commands::ProfileCommandError;
+use crate::profile::profile_models::ProfileVaultError;
+use crate::profile::profile_vault::ProfileVault;
+use crate::session::session_registry::{SessionProfile, SessionRegistry};
+use std::collections::HashSet;
+use std::sync::Arc;
+use tauri::State;
+
+#[tauri::command]
+pub async fn list_namespaces_across_profiles(
+    vault: State<'_, Arc<ProfileVault>>,
+    sessions: State<'_, Arc<SessionRegistry>>,
+    environments: State<'_, Arc<NamespaceEnvironments>>,
+) -> Result<CrossAccountResult<ProfileNamespace>, ProfileCommandError> {
+    let clients = profile_clients(&vault, &sessions)?;
+    Ok(list_profile_namespaces(&clients, &environments).await)
+}
+
+// Titles are matched case-insensitively, so `-Staging` and `-staging` are found alike.
+#[tauri::command]
+pub async fn search_namespaces_across_profiles(
+    vault: State<'_, Arc<ProfileVault>>,
+    sessions: State<'_, Arc<SessionRegistry>>,
+    environments: State<'_, Arc<NamespaceEnvironments>>,
+    query: String,
+) -> Result<CrossAccountResult<ProfileNamespace>, ProfileCommandError> {
+    let clients = profile_clients(&vault, &sessions)?;
+    let mut listing = list_profile_namespaces(&clients, &environments).await;
+    let query = query.trim().to_lowercase();
+    listing.items.retain(|profile_namespace| {
+        profile_namespace
+            .namespace
+            .namespace
+            .title
+            .to_lowercase()
+            .contains(&query)
+    });
+    Ok(listing)
+}
+
+#[tauri::command]
+pub async fn find_key_across_profiles(
+    vault: State<'_, Arc<ProfileVault>>,
+    sessions: State<'_, Arc<SessionRegistry>>,
+    environments: State<'_, Arc<NamespaceEnvironments>>,
+    key: String,
+) -> Result<CrossAccountResult<ProfileKeyMatch>, ProfileCommandError> {
+    let clients = profile_clients(&vault, &sessions)?;
+    Ok(find_key(&clients, &environments, &key).await)
+}
+
+// Profiles of the same account would list the same namespaces, only the first one is used. The
+// clients only read, so no sessions are opened for them.
+fn profile_clients(
+    vault: &ProfileVault,
+    sessions: &SessionRegistry,
+) -> Result<Vec<ProfileClient>, ProfileVaultError> {
+    let mut account_ids = HashSet::new();
+    let mut clients = vec![];
+    for summary in vault.list()? {
+        if !account_ids.insert(summary.account_id.clone()) {
+            continue;
+        }
+
+        let profile = vault.get(&summary.id)?;
+        clients.push(ProfileClient {
+            profile: SessionProfile {
+                id: profile.id,
+                name: profile.name,
+                environment: profile.environment,
+            },
+            account_id: profile.account_id,
+            cloudflare: sessions.create_client(profile.credentials, profile.api_url),
+        });
+    }
+
+    Ok(clients)
+}
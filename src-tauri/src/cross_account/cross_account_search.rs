@@ -0,0 +1,295 @@
+use crate::cloudflare::Cloudflare;
+use crate::cloudflare::kv::{KvKey, KvKeysListInput, KvNamespace, KvNamespacesListInput};
+use crate::environment::environment_commands::label_namespace;
+use crate::environment::environment_labels::{LabelledNamespace, NamespaceEnvironments};
+use crate::kv::kv_commands::KvCommandError;
+use crate::session::session_registry::SessionProfile;
+use futures::{StreamExt, TryStreamExt, stream};
+use serde::Serialize;
+use std::sync::Arc;
+
+const PROFILE_CONCURRENCY: usize = 4;
+const NAMESPACE_CONCURRENCY: usize = 8;
+
+// The key listing is filtered by the key as prefix, so a few keys are enough to find an exact match.
+const KEY_SEARCH_LIMIT: u32 = 10;
+
+pub struct ProfileClient {
+    pub profile: SessionProfile,
+    pub account_id: String,
+    pub cloudflare: Arc<Cloudflare>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ProfileNamespace {
+    pub profile: SessionProfile,
+    pub account_id: String,
+    pub namespace: LabelledNamespace,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ProfileKeyMatch {
+    pub profile: SessionProfile,
+    pub account_id: String,
+    pub namespace: LabelledNamespace,
+    pub key: KvKey,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ProfileFailure {
+    pub profile: SessionProfile,
+    pub account_id: String,
+    pub namespace_id: Option<String>,
+    pub error: KvCommandError,
+}
+
+// An account which could not be read does not hide the results of the others.
+#[derive(Debug, Serialize)]
+pub struct CrossAccountResult<T> {
+    pub items: Vec<T>,
+    pub failures: Vec<ProfileFailure>,
+}
+
+pub async fn list_profile_namespaces(
+    clients: &[ProfileClient],
+    environments: &NamespaceEnvironments,
+) -> CrossAccountResult<ProfileNamespace> {
+    let (namespaces, failures) = list_namespaces_by_client(clients).await;
+    CrossAccountResult {
+        items: namespaces
+            .into_iter()
+            .map(|(client, namespace)| ProfileNamespace {
+                profile: client.profile.clone(),
+                account_id: client.account_id.clone(),
+                namespace: label_namespace(
+                    environments,
+                    client.profile.environment,
+                    &client.account_id,
+                    namespace,
+                ),
+            })
+            .collect(),
+        failures,
+    }
+}
+
+pub async fn find_key(
+    clients: &[ProfileClient],
+    environments: &NamespaceEnvironments,
+    key: &str,
+) -> CrossAccountResult<ProfileKeyMatch> {
+    let (namespaces, failures) = list_namespaces_by_client(clients).await;
+    let results: Vec<_> = stream::iter(namespaces)
+        .map(|(client, namespace)| async move {
+            let keys = client
+                .cloudflare
+                .kv
+                .list_keys(KvKeysListInput {
+                    account_id: client.account_id.clone(),
+                    namespace_id: namespace.id.clone(),
+                    cursor: None,
+                    limit: Some(KEY_SEARCH_LIMIT),
+                    prefix: Some(key.to_string()),
+                })
+                .await;
+            (client, namespace, keys)
+        })
+        .buffered(NAMESPACE_CONCURRENCY)
+        .collect()
+        .await;
+
+    let mut matches = CrossAccountResult {
+        items: vec![],
+        failures,
+    };
+    for (client, namespace, keys) in results {
+        match keys {
+            Ok(keys) => {
+                if let Some(found_key) = keys.keys.into_iter().find(|found| found.name == key) {
+                    matches.items.push(ProfileKeyMatch {
+                        profile: client.profile.clone(),
+                        account_id: client.account_id.clone(),
+                        namespace: label_namespace(
+                            environments,
+                            client.profile.environment,
+                            &client.account_id,
+                            namespace,
+                        ),
+                        key: found_key,
+                    });
+                }
+            }
+            Err(error) => matches.failures.push(ProfileFailure {
+                profile: client.profile.clone(),
+                account_id: client.account_id.clone(),
+                namespace_id: Some(namespace.id),
+                error: error.into(),
+            }),
+        }
+    }
+
+    matches
+}
+
+async fn list_namespaces_by_client(
+    clients: &[ProfileClient],
+) -> (Vec<(&ProfileClient, KvNamespace)>, Vec<ProfileFailure>) {
+    let results: Vec<_> = stream::iter(clients)
+        .map(|client| async move {
+            let namespaces: Result<Vec<KvNamespace>, _> = client
+                .cloudflare
+                .kv
+                .list_all_namespaces(KvNamespacesListInput {
+                    account_id: client.account_id.clone(),
+                    ..KvNamespacesListInput::default()
+                })
+                .try_collect()
+                .await;
+            (client, namespaces)
+        })
+        .buffered(PROFILE_CONCURRENCY)
+        .collect()
+        .await;
+
+    let mut namespaces_by_client = vec![];
+    let mut failures = vec![];
+    for (client, namespaces) in results {
+        match namespaces {
+            Ok(namespaces) => namespaces_by_client
+                .extend(namespaces.into_iter().map(|namespace| (client, namespace))),
+            Err(error) => failures.push(ProfileFailure {
+                profile: client.profile.clone(),
+                account_id: client.account_id.clone(),
+                namespace_id: None,
+                error: error.into(),
+            }),
+        }
+    }
+
+    (namespaces_by_client, failures)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{ProfileClient, find_key};
+    use crate::cloudflare::common::{
+        ApiCursorPaginatedResponse, ApiError, ApiErrorResponse, ApiPaginatedResponse,
+        CursorPageInfo, PageInfo,
+    };
+    use crate::cloudflare::kv::{KvKey, KvNamespace};
+    use crate::environment::environment_labels::NamespaceEnvironments;
+    use crate::session::session_registry::SessionProfile;
+    use crate::test_support::{create_cloudflare, create_temp_dir};
+    use std::sync::Arc;
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[tokio::test]
+    async fn should_find_the_key_in_every_readable_account() {
+        let mock_server = MockServer::start().await;
+        let namespaces = vec![
+            create_namespace("namespace1"),
+            create_namespace("namespace2"),
+        ];
+        Mock::given(method("GET"))
+            .and(path("/client/v4/accounts/account1/storage/kv/namespaces"))
+            .respond_with(
+                ResponseTemplate::new(200).set_body_json(
+                    ApiPaginatedResponse::<Vec<KvNamespace>> {
+                        result: namespaces.clone(),
+                        result_info: PageInfo {
+                            total_count: 2,
+                            count: 2,
+                            page: 1,
+                            per_page: 100,
+                        },
+                    },
+                ),
+            )
+            .mount(&mock_server)
+            .await;
+        for (namespace_id, keys) in [
+            (
+                "namespace1",
+                vec![create_key("config"), create_key("config:old")],
+            ),
+            ("namespace2", vec![create_key("config:new")]),
+        ] {
+            Mock::given(method("GET"))
+                .and(path(format!(
+                    "/client/v4/accounts/account1/storage/kv/namespaces/{namespace_id}/keys"
+                )))
+                .respond_with(ResponseTemplate::new(200).set_body_json(
+                    ApiCursorPaginatedResponse::<Vec<KvKey>> {
+                        result_info: CursorPageInfo {
+                            count: keys.len(),
+                            cursor: None,
+                        },
+                        result: keys,
+                    },
+                ))
+                .mount(&mock_server)
+                .await;
+        }
+        Mock::given(method("GET"))
+            .and(path("/client/v4/accounts/account2/storage/kv/namespaces"))
+            .respond_with(ResponseTemplate::new(400).set_body_json(ApiErrorResponse {
+                errors: vec![ApiError {
+                    code: 10001,
+                    message: "Unable to authenticate request".to_string(),
+                }],
+                messages: vec![],
+            }))
+            .mount(&mock_server)
+            .await;
+
+        let clients = vec![
+            create_client(&mock_server, "profile1", "account1"),
+            create_client(&mock_server, "profile2", "account2"),
+        ];
+        let environments =
+            NamespaceEnvironments::new(create_temp_dir().join("namespace-environments.json"));
+        let matches = find_key(&clients, &environments, "config").await;
+
+        assert_eq!(matches.items.len(), 1);
+        assert_eq!(matches.items[0].profile.id, "profile1");
+        assert_eq!(matches.items[0].namespace.namespace, namespaces[0]);
+        assert_eq!(matches.items[0].namespace.environment, None);
+        assert_eq!(matches.items[0].key, create_key("config"));
+        assert_eq!(matches.failures.len(), 1);
+        assert_eq!(matches.failures[0].account_id, "account2");
+    }
+
+    fn create_client(
+        mock_server: &MockServer,
+        profile_id: &str,
+        account_id: &str,
+    ) -> ProfileClient {
+        ProfileClient {
+            profile: SessionProfile {
+                id: profile_id.to_string(),
+                name: profile_id.to_string(),
+                environment: None,
+            },
+            account_id: account_id.to_string(),
+            cloudflare: Arc::new(create_cloudflare(mock_server)),
+        }
+    }
+
+    fn create_namespace(id: &str) -> KvNamespace {
+        KvNamespace {
+            id: id.to_string(),
+            title: format!("{id}_title"),
+            beta: None,
+            supports_url_encoding: None,
+        }
+    }
+
+    fn create_key(name: &str) -> KvKey {
+        KvKey {
+            name: name.to_string(),
+            metadata: None,
+            expiration: None,
+        }
+    }
+}
@@ -0,0 +1,2 @@
+pub mod cross_account_commands;
+pub mod cross_account_search;
@@ -0,0 +1,101 @@
+use crate::audit::audit_log::AuditEntry;
+use crate::cloudflare::kv::{KvClient, KvNamespace, KvNamespaceGetInput};
+use crate::environment::environment_labels::{
+    Environment, EnvironmentError, LabelledNamespace, NamespaceEnvironment, NamespaceEnvironments,
+    target_environment,
+};
+use crate::kv::kv_commands::KvCommandError;
+use log::warn;
+use std::sync::Arc;
+use tauri::State;
+
+#[tauri::command]
+pub fn list_namespace_environments(
+    environments: State<'_, Arc<NamespaceEnvironments>>,
+    account_id: String,
+) -> Result<Vec<NamespaceEnvironment>, KvCommandError> {
+    Ok(environments.list(&account_id)?)
+}
+
+#[tauri::command]
+pub fn set_namespace_environment(
+    environments: State<'_, Arc<NamespaceEnvironments>>,
+    input: NamespaceEnvironment,
+) -> Result<(), KvCommandError> {
+    Ok(environments.set(input)?)
+}
+
+pub fn label_namespaces(
+    environments: &NamespaceEnvironments,
+    profile_environment: Option<Environment>,
+    account_id: &str,
+    namespaces: Vec<KvNamespace>,
+) -> Vec<LabelledNamespace> {
+    namespaces
+        .into_iter()
+        .map(|namespace| label_namespace(environments, profile_environment, account_id, namespace))
+        .collect()
+}
+
+// The namespace gets its own label or else the environment of the profile.
+pub fn label_namespace(
+    environments: &NamespaceEnvironments,
+    profile_environment: Option<Environment>,
+    account_id: &str,
+    namespace: KvNamespace,
+) -> LabelledNamespace {
+    let namespace_environment = match environments.get(account_id, &namespace.id) {
+        Ok(environment) => environment,
+        Err(error) => {
+            warn!(
+                "The environment of the namespace {} could not be read: {error}",
+                namespace.id
+            );
+            None
+        }
+    };
+    LabelledNamespace {
+        namespace,
+        environment: target_environment(profile_environment, namespace_environment),
+    }
+}
+
+// The title is part of the error, since production and staging namespaces often only differ by a
+// suffix. It is recorded as well, so the audit log shows which namespace was meant.
+pub async fn acknowledge_production(
+    environments: &NamespaceEnvironments,
+    kv: &KvClient,
+    audit_entry: &mut AuditEntry,
+    acknowledged: bool,
+) -> Result<(), KvCommandError> {
+    let profile_environment = audit_entry
+        .profile
+        .as_ref()
+        .and_then(|profile| profile.environment);
+    let namespace_environment = match &audit_entry.namespace_id {
+        Some(namespace_id) => environments.get(&audit_entry.account_id, namespace_id)?,
+        None => None,
+    };
+    audit_entry.environment = target_environment(profile_environment, namespace_environment);
+    if audit_entry.environment != Some(Environment::Production) {
+        return Ok(());
+    }
+
+    let title = match &audit_entry.namespace_id {
+        Some(namespace_id) => {
+            kv.get_namespace(KvNamespaceGetInput {
+                account_id: audit_entry.account_id.clone(),
+                namespace_id: namespace_id.clone(),
+            })
+            .await?
+            .title
+        }
+        None => audit_entry.namespace_title.clone().unwrap_or_default(),
+    };
+    if !acknowledged {
+        return Err(EnvironmentError::AcknowledgementRequired(title).into());
+    }
+
+    audit_entry.namespace_title.get_or_insert(title);
+    Ok(())
+}
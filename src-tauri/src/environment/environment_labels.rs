@@ -0,0 +1,328 @@
+use crate::cloudflare::common::PageInfo;
+use crate::cloudflare::kv::KvNamespace;
+use serde::{Deserialize, Deserializer, Serialize};
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fmt;
+use std::fmt::{Display, Formatter};
+use std::fs;
+use std::io::ErrorKind;
+use std::path::PathBuf;
+use std::sync::{Mutex, MutexGuard};
+
+pub const NAMESPACE_ENVIRONMENTS_FILE_NAME: &str = "namespace-environments.json";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Environment {
+    Development,
+    Staging,
+    Production,
+}
+
+impl Environment {
+    pub fn from_label(label: &str) -> Option<Self> {
+        match label.trim().to_lowercase().as_str() {
+            "dev" | "development" => Some(Environment::Development),
+            "stage" | "staging" => Some(Environment::Staging),
+            "prod" | "production" => Some(Environment::Production),
+            _ => None,
+        }
+    }
+}
+
+// Profiles were saved with a free text environment before, unknown labels are dropped.
+pub fn deserialize_environment_label<'de, D>(
+    deserializer: D,
+) -> Result<Option<Environment>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let label: Option<String> = Option::deserialize(deserializer)?;
+    Ok(label.as_deref().and_then(Environment::from_label))
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NamespaceEnvironment {
+    pub account_id: String,
+    pub namespace_id: String,
+    pub environment: Option<Environment>,
+}
+
+// The environment is a local label, Cloudflare never sends it. It is flattened into the namespace,
+// so the frontend gets one object.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LabelledNamespace {
+    #[serde(flatten)]
+    pub namespace: KvNamespace,
+    pub environment: Option<Environment>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LabelledNamespaces {
+    pub items: Vec<LabelledNamespace>,
+    pub page_info: PageInfo,
+}
+
+// Commands which change something remotely take the acknowledgement next to the API input, which
+// stays as Cloudflare expects it.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct AcknowledgedInput<T> {
+    #[serde(flatten)]
+    pub input: T,
+
+    // Set once the user confirmed a change in a production namespace.
+    #[serde(default, rename = "acknowledge_production")]
+    pub acknowledged: bool,
+}
+
+// A namespace without a label takes the environment of the profile it is used with.
+pub fn target_environment(
+    profile_environment: Option<Environment>,
+    namespace_environment: Option<Environment>,
+) -> Option<Environment> {
+    namespace_environment.or(profile_environment)
+}
+
+// The labels are local settings, loaded on first use.
+pub struct NamespaceEnvironments {
+    path: PathBuf,
+    environments: Mutex<Option<BTreeMap<(String, String), Environment>>>,
+}
+
+type EnvironmentsByNamespace = BTreeMap<(String, String), Environment>;
+
+impl NamespaceEnvironments {
+    pub fn new(path: PathBuf) -> Self {
+        Self {
+            path,
+            environments: Mutex::new(None),
+        }
+    }
+
+    pub fn get(
+        &self,
+        account_id: &str,
+        namespace_id: &str,
+    ) -> Result<Option<Environment>, EnvironmentError> {
+        let mut environments = self.lock_environments();
+        Ok(self
+            .load(&mut environments)?
+            .get(&(account_id.to_string(), namespace_id.to_string()))
+            .copied())
+    }
+
+    pub fn list(&self, account_id: &str) -> Result<Vec<NamespaceEnvironment>, EnvironmentError> {
+        let mut environments = self.lock_environments();
+        Ok(self
+            .load(&mut environments)?
+            .iter()
+            .filter(|((label_account_id, _), _)| label_account_id == account_id)
+            .map(
+                |((account_id, namespace_id), environment)| NamespaceEnvironment {
+                    account_id: account_id.clone(),
+                    namespace_id: namespace_id.clone(),
+                    environment: Some(*environment),
+                },
+            )
+            .collect())
+    }
+
+    // A namespace without environment loses its label.
+    pub fn set(&self, label: NamespaceEnvironment) -> Result<(), EnvironmentError> {
+        let mut environments = self.lock_environments();
+        let loaded_environments = self.load(&mut environments)?;
+        let namespace = (label.account_id, label.namespace_id);
+        match label.environment {
+            Some(environment) => loaded_environments.insert(namespace, environment),
+            None => loaded_environments.remove(&namespace),
+        };
+
+        let labels: Vec<NamespaceEnvironment> = loaded_environments
+            .iter()
+            .map(
+                |((account_id, namespace_id), environment)| NamespaceEnvironment {
+                    account_id: account_id.clone(),
+                    namespace_id: namespace_id.clone(),
+                    environment: Some(*environment),
+                },
+            )
+            .collect();
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let temporary_path = self.path.with_extension("tmp");
+        fs::write(&temporary_path, serde_json::to_vec_pretty(&labels)?)?;
+        fs::rename(&temporary_path, &self.path)?;
+        Ok(())
+    }
+
+    fn load<'a>(
+        &self,
+        environments: &'a mut Option<EnvironmentsByNamespace>,
+    ) -> Result<&'a mut EnvironmentsByNamespace, EnvironmentError> {
+        if environments.is_none() {
+            let labels: Vec<NamespaceEnvironment> = match fs::read(&self.path) {
+                Ok(content) => serde_json::from_slice(&content)?,
+                Err(error) if error.kind() == ErrorKind::NotFound => vec![],
+                Err(error) => return Err(error.into()),
+            };
+            *environments = Some(
+                labels
+                    .into_iter()
+                    .filter_map(|label| {
+                        let environment = label.environment?;
+                        Some(((label.account_id, label.namespace_id), environment))
+                    })
+                    .collect(),
+            );
+        }
+
+        Ok(environments.get_or_insert_default())
+    }
+
+    fn lock_environments(&self) -> MutexGuard<'_, Option<EnvironmentsByNamespace>> {
+        self.environments
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+#[derive(Debug)]
+pub enum EnvironmentError {
+    AcknowledgementRequired(String),
+    Io(std::io::Error),
+    Serialization(serde_json::Error),
+}
+
+impl From<std::io::Error> for EnvironmentError {
+    fn from(error: std::io::Error) -> Self {
+        EnvironmentError::Io(error)
+    }
+}
+
+impl From<serde_json::Error> for EnvironmentError {
+    fn from(error: serde_json::Error) -> Self {
+        EnvironmentError::Serialization(error)
+    }
+}
+
+impl Error for EnvironmentError {}
+
+impl Display for EnvironmentError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match &self {
+            EnvironmentError::AcknowledgementRequired(title) => write!(
+                f,
+                "The namespace {title} is tagged as production, acknowledge the change to continue"
+            ),
+            EnvironmentError::Io(err) => write!(f, "IO error: {}", err),
+            EnvironmentError::Serialization(err) => write!(f, "Serialization error: {}", err),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{
+        AcknowledgedInput, Environment, EnvironmentError, LabelledNamespace, NamespaceEnvironment,
+        NamespaceEnvironments, target_environment,
+    };
+    use crate::cloudflare::kv::{KvNamespace, KvPairWriteInput};
+    use crate::test_support::create_temp_dir;
+    use chrono::{TimeZone, Utc};
+    use serde_json::json;
+    use std::fs;
+
+    #[test]
+    fn should_keep_the_labels_after_a_restart() -> Result<(), EnvironmentError> {
+        let directory = create_temp_dir();
+        let path = directory.join("namespace-environments.json");
+        let label = |namespace_id: &str, environment| NamespaceEnvironment {
+            account_id: "account_id".to_string(),
+            namespace_id: namespace_id.to_string(),
+            environment,
+        };
+
+        let environments = NamespaceEnvironments::new(path.clone());
+        environments.set(label("namespace1", Some(Environment::Production)))?;
+        environments.set(label("namespace2", Some(Environment::Staging)))?;
+        environments.set(label("namespace2", None))?;
+
+        let restarted = NamespaceEnvironments::new(path);
+        assert_eq!(
+            restarted.get("account_id", "namespace1")?,
+            Some(Environment::Production)
+        );
+        assert_eq!(restarted.get("account_id", "namespace2")?, None);
+        assert_eq!(
+            restarted.list("account_id")?,
+            vec![label("namespace1", Some(Environment::Production))]
+        );
+
+        fs::remove_dir_all(directory)?;
+        Ok(())
+    }
+
+    #[test]
+    fn should_prefer_the_label_of_the_namespace() {
+        assert_eq!(
+            target_environment(Some(Environment::Production), Some(Environment::Staging)),
+            Some(Environment::Staging)
+        );
+        assert_eq!(
+            target_environment(Some(Environment::Production), None),
+            Some(Environment::Production)
+        );
+        assert_eq!(
+            Environment::from_label(" Prod "),
+            Some(Environment::Production)
+        );
+        assert_eq!(Environment::from_label("qa"), None);
+    }
+
+    #[test]
+    fn should_read_the_acknowledgement_next_to_the_api_input() {
+        let input: AcknowledgedInput<KvPairWriteInput> = serde_json::from_value(json!({
+            "account_id": "account_id",
+            "namespace_id": "namespace_id",
+            "key": "key",
+            "value": [118, 97, 108, 117, 101],
+            "expiration": 1735689600,
+            "expiration_ttl": null,
+            "metadata": {"owner": "team"},
+            "acknowledge_production": true
+        }))
+        .unwrap();
+
+        assert!(input.acknowledged);
+        assert_eq!(input.input.value, Some(b"value".to_vec()));
+        assert_eq!(
+            input.input.expiration,
+            Some(Utc.with_ymd_and_hms(2025, 1, 1, 0, 0, 0).unwrap())
+        );
+    }
+
+    #[test]
+    fn should_flatten_the_label_into_the_namespace() {
+        let namespace = LabelledNamespace {
+            namespace: KvNamespace {
+                id: "namespace_id".to_string(),
+                title: "sessions".to_string(),
+                beta: None,
+                supports_url_encoding: None,
+            },
+            environment: Some(Environment::Production),
+        };
+
+        assert_eq!(
+            serde_json::to_value(namespace).unwrap(),
+            json!({
+                "id": "namespace_id",
+                "title": "sessions",
+                "beta": null,
+                "supports_url_encoding": null,
+                "environment": "Production"
+            })
+        );
+    }
+}
@@ -0,0 +1,2 @@
+pub mod environment_commands;
+pub mod environment_labels;
@@ -0,0 +1,211 @@
+use crate::audit::audit_log::{AuditEntry, AuditLog, AuditOperation, AuditOutcome, AuditedKey};
+use crate::cache::kv_cache::{KvCache, namespace_cache_prefix};
+use crate::cloudflare::kv::{
+    KvClient, KvError, KvPair, KvPairGetInput, KvPairWriteInput, KvPairsGetInput,
+};
+use crate::environment::environment_commands::acknowledge_production;
+use crate::environment::environment_labels::NamespaceEnvironments;
+use crate::history::value_history::{
+    KvKeyHistory, KvValueDiff, KvValueVersion, KvVersionCause, ValueHistory, ValueHistoryConfig,
+    diff_versions, restorable_pair,
+};
+use crate::kv::kv_commands::KvCommandError;
+use crate::session::session_registry::SessionRegistry;
+use chrono::Utc;
+use log::warn;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tauri::State;
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct KvHistoryListInput {
+    pub account_id: String,
+    pub namespace_id: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct KvVersionsListInput {
+    pub account_id: String,
+    pub namespace_id: String,
+    pub key: String,
+}
+
+// Without a `to_version_id` the version is compared with the current value.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct KvVersionsDiffInput {
+    pub account_id: String,
+    pub namespace_id: String,
+    pub key: String,
+    pub from_version_id: String,
+    pub to_version_id: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct KvVersionRestoreInput {
+    pub account_id: String,
+    pub namespace_id: String,
+    pub key: String,
+    pub version_id: String,
+
+    #[serde(default)]
+    pub acknowledge_production: bool,
+}
+
+#[tauri::command]
+pub fn get_kv_history_config(history: State<'_, Arc<ValueHistory>>) -> ValueHistoryConfig {
+    history.config()
+}
+
+#[tauri::command]
+pub fn update_kv_history_config(
+    history: State<'_, Arc<ValueHistory>>,
+    config: ValueHistoryConfig,
+) -> Result<ValueHistoryConfig, KvCommandError> {
+    Ok(history.reconfigure(config)?)
+}
+
+#[tauri::command]
+pub fn list_kv_history(
+    history: State<'_, Arc<ValueHistory>>,
+    input: KvHistoryListInput,
+) -> Result<Vec<KvKeyHistory>, KvCommandError> {
+    Ok(history.list_keys(&input.account_id, &input.namespace_id)?)
+}
+
+#[tauri::command]
+pub fn list_kv_versions(
+    history: State<'_, Arc<ValueHistory>>,
+    input: KvVersionsListInput,
+) -> Result<Vec<KvValueVersion>, KvCommandError> {
+    Ok(history.versions(&input.account_id, &input.namespace_id, &input.key)?)
+}
+
+#[tauri::command]
+pub async fn diff_kv_versions(
+    sessions: State<'_, Arc<SessionRegistry>>,
+    history: State<'_, Arc<ValueHistory>>,
+    session_id: String,
+    input: KvVersionsDiffInput,
+) -> Result<KvValueDiff, KvCommandError> {
+    let from = history.version(
+        &input.account_id,
+        &input.namespace_id,
+        &input.key,
+        &input.from_version_id,
+    )?;
+    let to = match &input.to_version_id {
+        Some(to_version_id) => Some(
+            history
+                .version(
+                    &input.account_id,
+                    &input.namespace_id,
+                    &input.key,
+                    to_version_id,
+                )?
+                .pair,
+        ),
+        None => {
+            let cloudflare_client = sessions.get(&session_id)?;
+            let input = KvPairGetInput {
+                account_id: input.account_id,
+                namespace_id: input.namespace_id,
+                key: input.key,
+            };
+            match cloudflare_client.kv.get_kv_pair(input).await {
+                Ok(pair) => Some(pair),
+                Err(KvError::KeyNotFound(_)) => None,
+                Err(error) => return Err(error.into()),
+            }
+        }
+    };
+
+    Ok(diff_versions(Some(from.pair), to))
+}
+
+// The current value is recorded as well, so a restore can be undone like any other write. The
+// returned pair shows whether the expiration of the version was dropped.
+#[tauri::command]
+pub async fn restore_kv_version(
+    sessions: State<'_, Arc<SessionRegistry>>,
+    history: State<'_, Arc<ValueHistory>>,
+    cache: State<'_, Arc<KvCache>>,
+    audit_log: State<'_, Arc<AuditLog>>,
+    environments: State<'_, Arc<NamespaceEnvironments>>,
+    session_id: String,
+    input: KvVersionRestoreInput,
+) -> Result<KvPair, KvCommandError> {
+    let cloudflare_client = sessions.get_writable(&session_id)?;
+    let mut audit_entry = AuditEntry::new(
+        sessions.profile(&session_id),
+        AuditOperation::KeyWrite,
+        &input.account_id,
+        Some(&input.namespace_id),
+    );
+    acknowledge_production(
+        &environments,
+        &cloudflare_client.kv,
+        &mut audit_entry,
+        input.acknowledge_production,
+    )
+    .await?;
+    let version = history.version(
+        &input.account_id,
+        &input.namespace_id,
+        &input.key,
+        &input.version_id,
+    )?;
+    record_previous_versions(
+        &history,
+        &cloudflare_client.kv,
+        KvPairsGetInput {
+            account_id: input.account_id.clone(),
+            namespace_id: input.namespace_id.clone(),
+            keys: vec![input.key.clone()],
+        },
+        KvVersionCause::Overwritten,
+    )
+    .await?;
+
+    let pair = restorable_pair(version.pair, Utc::now());
+    audit_entry.keys = vec![AuditedKey::new(&input.key, Some(&pair.value))];
+    let cache_prefix = namespace_cache_prefix(&input.account_id, &input.namespace_id);
+    let result = cloudflare_client
+        .kv
+        .write_kv_pair(KvPairWriteInput {
+            account_id: input.account_id,
+            namespace_id: input.namespace_id,
+            key: input.key,
+            value: Some(pair.value),
+            expiration: pair.expiration,
+            expiration_ttl: None,
+            metadata: pair.metadata,
+        })
+        .await;
+    if let Err(error) = cache.invalidate(&cache_prefix) {
+        warn!("The cache entries {cache_prefix} could not be invalidated: {error}");
+    }
+    audit_entry.outcome = AuditOutcome::of(&result, |_| AuditOutcome::Succeeded);
+    audit_log.record(audit_entry);
+    Ok(result?)
+}
+
+// Nothing is changed remotely if the previous values could not be read or recorded.
+pub async fn record_previous_versions(
+    history: &ValueHistory,
+    kv: &KvClient,
+    input: KvPairsGetInput,
+    cause: KvVersionCause,
+) -> Result<(), KvCommandError> {
+    if !history.is_enabled() || input.keys.is_empty() {
+        return Ok(());
+    }
+
+    let previous_pairs = kv.find_kv_pairs(input.clone()).await?;
+    history.record(
+        &input.account_id,
+        &input.namespace_id,
+        previous_pairs,
+        cause,
+    )?;
+    Ok(())
+}
@@ -0,0 +1,2 @@
+pub mod history_commands;
+pub mod value_history;
@@ -0,0 +1,634 @@
+use crate::cloudflare::kv::KvPair;
+use chrono::{DateTime, TimeDelta, Utc};
+use log::warn;
+use ring::digest::{SHA256, digest};
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::fmt;
+use std::fmt::{Display, Formatter};
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, MutexGuard};
+use uuid::Uuid;
+
+pub const VALUE_HISTORY_DIRECTORY_NAME: &str = "kv-history";
+
+// Lines are only diffed when the changed part of both values is at most this many line pairs.
+const MAX_DIFF_LINE_PAIRS: usize = 1_000_000;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ValueHistoryConfig {
+    pub enabled: bool,
+    pub max_versions_per_key: usize,
+    pub max_age_days: u32,
+}
+
+impl Default for ValueHistoryConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            max_versions_per_key: 20,
+            max_age_days: 30,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum KvVersionCause {
+    Overwritten,
+    Deleted,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct KvValueVersion {
+    pub id: String,
+    pub recorded_at: DateTime<Utc>,
+    pub cause: KvVersionCause,
+    pub pair: KvPair,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct KvKeyHistory {
+    pub key: String,
+    pub version_count: usize,
+    pub last_recorded_at: DateTime<Utc>,
+    pub last_cause: KvVersionCause,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum KvValueDiffLineKind {
+    Unchanged,
+    Added,
+    Removed,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct KvValueDiffLine {
+    pub kind: KvValueDiffLineKind,
+    pub text: String,
+}
+
+// A missing side means the key did not exist. The lines are only diffed if both values are text.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct KvValueDiff {
+    pub from: Option<KvPair>,
+    pub to: Option<KvPair>,
+    pub lines: Option<Vec<KvValueDiffLine>>,
+    pub metadata_changed: bool,
+    pub expiration_changed: bool,
+}
+
+// Keeps the values which were overwritten or deleted, newest first. Every key has its own file
+// in the directory of its namespace, so the keys of a namespace can be listed after they were
+// deleted remotely.
+pub struct ValueHistory {
+    directory: PathBuf,
+    config: Mutex<ValueHistoryConfig>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct StoredKeyHistory {
+    key: String,
+    versions: Vec<KvValueVersion>,
+}
+
+impl ValueHistory {
+    pub fn new(directory: PathBuf, config: ValueHistoryConfig) -> Self {
+        Self {
+            directory,
+            config: Mutex::new(config),
+        }
+    }
+
+    pub fn config(&self) -> ValueHistoryConfig {
+        self.lock_config().clone()
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.lock_config().enabled
+    }
+
+    // A smaller retention limit removes the versions beyond it right away.
+    pub fn reconfigure(
+        &self,
+        config: ValueHistoryConfig,
+    ) -> Result<ValueHistoryConfig, ValueHistoryError> {
+        let mut current_config = self.lock_config();
+        *current_config = config;
+        for namespace_directory in read_directory(&self.directory)? {
+            if !namespace_directory.is_dir() {
+                continue;
+            }
+            for path in history_files(&namespace_directory)? {
+                let Some(mut history) = read_history(&path)? else {
+                    continue;
+                };
+                prune(&mut history.versions, &current_config);
+                write_history(&path, &history)?;
+            }
+        }
+
+        Ok(current_config.clone())
+    }
+
+    pub fn record(
+        &self,
+        account_id: &str,
+        namespace_id: &str,
+        previous_pairs: Vec<KvPair>,
+        cause: KvVersionCause,
+    ) -> Result<(), ValueHistoryError> {
+        let config = self.lock_config();
+        if !config.enabled {
+            return Ok(());
+        }
+
+        let recorded_at = Utc::now();
+        for pair in previous_pairs {
+            let path = self.key_path(account_id, namespace_id, &pair.key);
+            let mut history = read_history(&path)?.unwrap_or_else(|| StoredKeyHistory {
+                key: pair.key.clone(),
+                versions: vec![],
+            });
+            history.versions.insert(
+                0,
+                KvValueVersion {
+                    id: Uuid::new_v4().to_string(),
+                    recorded_at,
+                    cause: cause.clone(),
+                    pair,
+                },
+            );
+            prune(&mut history.versions, &config);
+            write_history(&path, &history)?;
+        }
+
+        Ok(())
+    }
+
+    pub fn list_keys(
+        &self,
+        account_id: &str,
+        namespace_id: &str,
+    ) -> Result<Vec<KvKeyHistory>, ValueHistoryError> {
+        let _config = self.lock_config();
+        let mut keys = vec![];
+        for path in history_files(&self.namespace_directory(account_id, namespace_id))? {
+            let Some(history) = read_history(&path)? else {
+                continue;
+            };
+            if let Some(last_version) = history.versions.first() {
+                keys.push(KvKeyHistory {
+                    key: history.key.clone(),
+                    version_count: history.versions.len(),
+                    last_recorded_at: last_version.recorded_at,
+                    last_cause: last_version.cause.clone(),
+                });
+            }
+        }
+
+        keys.sort_by(|a, b| b.last_recorded_at.cmp(&a.last_recorded_at));
+        Ok(keys)
+    }
+
+    pub fn versions(
+        &self,
+        account_id: &str,
+        namespace_id: &str,
+        key: &str,
+    ) -> Result<Vec<KvValueVersion>, ValueHistoryError> {
+        let _config = self.lock_config();
+        let path = self.key_path(account_id, namespace_id, key);
+        Ok(read_history(&path)?
+            .map(|history| history.versions)
+            .unwrap_or_default())
+    }
+
+    pub fn version(
+        &self,
+        account_id: &str,
+        namespace_id: &str,
+        key: &str,
+        version_id: &str,
+    ) -> Result<KvValueVersion, ValueHistoryError> {
+        self.versions(account_id, namespace_id, key)?
+            .into_iter()
+            .find(|version| version.id == version_id)
+            .ok_or_else(|| ValueHistoryError::VersionNotFound(version_id.to_string()))
+    }
+
+    fn namespace_directory(&self, account_id: &str, namespace_id: &str) -> PathBuf {
+        self.directory
+            .join(hash(&format!("{account_id}/{namespace_id}")))
+    }
+
+    fn key_path(&self, account_id: &str, namespace_id: &str, key: &str) -> PathBuf {
+        self.namespace_directory(account_id, namespace_id)
+            .join(format!("{}.json", hash(key)))
+    }
+
+    // Also serializes the access to the files.
+    fn lock_config(&self) -> MutexGuard<'_, ValueHistoryConfig> {
+        self.config
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+// A version whose expiration passed in the meantime, or is about to, is restored without it, as
+// the API would reject the write.
+pub fn restorable_pair(pair: KvPair, now: DateTime<Utc>) -> KvPair {
+    if pair.expires_too_soon(now) {
+        KvPair {
+            expiration: None,
+            ..pair
+        }
+    } else {
+        pair
+    }
+}
+
+pub fn diff_versions(from: Option<KvPair>, to: Option<KvPair>) -> KvValueDiff {
+    let text = |pair: &Option<KvPair>| match pair {
+        Some(pair) => String::from_utf8(pair.value.clone()).ok(),
+        None => Some(String::new()),
+    };
+    let lines = match (text(&from), text(&to)) {
+        (Some(from_text), Some(to_text)) => Some(diff_lines(&from_text, &to_text)),
+        _ => None,
+    };
+
+    KvValueDiff {
+        lines,
+        metadata_changed: from.as_ref().map(|pair| &pair.metadata)
+            != to.as_ref().map(|pair| &pair.metadata),
+        expiration_changed: from.as_ref().map(|pair| pair.expiration)
+            != to.as_ref().map(|pair| pair.expiration),
+        from,
+        to,
+    }
+}
+
+// Longest common subsequence of the lines between the common prefix and suffix.
+fn diff_lines(from: &str, to: &str) -> Vec<KvValueDiffLine> {
+    let from_lines: Vec<&str> = from.lines().collect();
+    let to_lines: Vec<&str> = to.lines().collect();
+    let prefix_length = from_lines
+        .iter()
+        .zip(&to_lines)
+        .take_while(|(a, b)| a == b)
+        .count();
+    let suffix_length = from_lines[prefix_length..]
+        .iter()
+        .rev()
+        .zip(to_lines[prefix_length..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let removed = &from_lines[prefix_length..from_lines.len() - suffix_length];
+    let added = &to_lines[prefix_length..to_lines.len() - suffix_length];
+
+    let line = |kind: KvValueDiffLineKind, text: &str| KvValueDiffLine {
+        kind,
+        text: text.to_string(),
+    };
+    let mut lines: Vec<KvValueDiffLine> = from_lines[..prefix_length]
+        .iter()
+        .map(|text| line(KvValueDiffLineKind::Unchanged, text))
+        .collect();
+
+    if removed.len() * added.len() > MAX_DIFF_LINE_PAIRS {
+        lines.extend(
+            removed
+                .iter()
+                .map(|text| line(KvValueDiffLineKind::Removed, text)),
+        );
+        lines.extend(
+            added
+                .iter()
+                .map(|text| line(KvValueDiffLineKind::Added, text)),
+        );
+    } else {
+        let mut common = vec![vec![0usize; added.len() + 1]; removed.len() + 1];
+        for i in (0..removed.len()).rev() {
+            for j in (0..added.len()).rev() {
+                common[i][j] = if removed[i] == added[j] {
+                    common[i + 1][j + 1] + 1
+                } else {
+                    common[i + 1][j].max(common[i][j + 1])
+                };
+            }
+        }
+
+        let (mut i, mut j) = (0, 0);
+        while i < removed.len() || j < added.len() {
+            if i < removed.len() && j < added.len() && removed[i] == added[j] {
+                lines.push(line(KvValueDiffLineKind::Unchanged, removed[i]));
+                i += 1;
+                j += 1;
+            } else if j < added.len()
+                && (i == removed.len() || common[i][j + 1] >= common[i + 1][j])
+            {
+                lines.push(line(KvValueDiffLineKind::Added, added[j]));
+                j += 1;
+            } else {
+                lines.push(line(KvValueDiffLineKind::Removed, removed[i]));
+                i += 1;
+            }
+        }
+    }
+
+    lines.extend(
+        from_lines[from_lines.len() - suffix_length..]
+            .iter()
+            .map(|text| line(KvValueDiffLineKind::Unchanged, text)),
+    );
+    lines
+}
+
+fn prune(versions: &mut Vec<KvValueVersion>, config: &ValueHistoryConfig) {
+    let oldest_recorded_at = Utc::now() - TimeDelta::days(config.max_age_days as i64);
+    versions.retain(|version| version.recorded_at >= oldest_recorded_at);
+    versions.truncate(config.max_versions_per_key);
+}
+
+fn hash(name: &str) -> String {
+    digest(&SHA256, name.as_bytes())
+        .as_ref()
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect()
+}
+
+fn read_directory(directory: &Path) -> Result<Vec<PathBuf>, ValueHistoryError> {
+    match fs::read_dir(directory) {
+        Ok(entries) => Ok(entries
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .collect()),
+        Err(error) if error.kind() == ErrorKind::NotFound => Ok(vec![]),
+        Err(error) => Err(error.into()),
+    }
+}
+
+fn history_files(namespace_directory: &Path) -> Result<Vec<PathBuf>, ValueHistoryError> {
+    Ok(read_directory(namespace_directory)?
+        .into_iter()
+        .filter(|path| {
+            path.extension()
+                .is_some_and(|extension| extension == "json")
+        })
+        .collect())
+}
+
+// Unreadable files are skipped instead of blocking every later write of their key.
+fn read_history(path: &Path) -> Result<Option<StoredKeyHistory>, ValueHistoryError> {
+    let content = match fs::read(path) {
+        Ok(content) => content,
+        Err(error) if error.kind() == ErrorKind::NotFound => return Ok(None),
+        Err(error) => return Err(error.into()),
+    };
+
+    match serde_json::from_slice(&content) {
+        Ok(history) => Ok(Some(history)),
+        Err(error) => {
+            warn!("The history file {path:?} could not be read and is replaced: {error}");
+            Ok(None)
+        }
+    }
+}
+
+fn write_history(path: &Path, history: &StoredKeyHistory) -> Result<(), ValueHistoryError> {
+    if history.versions.is_empty() {
+        return match fs::remove_file(path) {
+            Err(error) if error.kind() != ErrorKind::NotFound => Err(error.into()),
+            _ => Ok(()),
+        };
+    }
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let temporary_path = path.with_extension("tmp");
+    fs::write(&temporary_path, serde_json::to_vec(history)?)?;
+    fs::rename(&temporary_path, path)?;
+    Ok(())
+}
+
+#[derive(Debug)]
+pub enum ValueHistoryError {
+    VersionNotFound(String),
+    Io(std::io::Error),
+    Serialization(serde_json::Error),
+}
+
+impl From<std::io::Error> for ValueHistoryError {
+    fn from(error: std::io::Error) -> Self {
+        ValueHistoryError::Io(error)
+    }
+}
+
+impl From<serde_json::Error> for ValueHistoryError {
+    fn from(error: serde_json::Error) -> Self {
+        ValueHistoryError::Serialization(error)
+    }
+}
+
+impl Error for ValueHistoryError {}
+
+impl Display for ValueHistoryError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match &self {
+            ValueHistoryError::VersionNotFound(version_id) => {
+                write!(f, "The version {version_id} does not exist")
+            }
+            ValueHistoryError::Io(err) => write!(f, "IO error: {}", err),
+            ValueHistoryError::Serialization(err) => write!(f, "Serialization error: {}", err),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{
+        KvValueDiffLine, KvValueDiffLineKind, KvVersionCause, ValueHistory, ValueHistoryConfig,
+        ValueHistoryError, diff_versions, restorable_pair,
+    };
+    use crate::cloudflare::kv::KvPair;
+    use crate::test_support::create_temp_dir;
+    use chrono::{TimeDelta, Utc};
+    use std::fs;
+    use std::path::PathBuf;
+
+    #[test]
+    fn should_keep_the_newest_versions_of_a_key() -> Result<(), ValueHistoryError> {
+        let directory = create_history_directory();
+        let history = ValueHistory::new(
+            directory.clone(),
+            ValueHistoryConfig {
+                max_versions_per_key: 2,
+                ..ValueHistoryConfig::default()
+            },
+        );
+        for value in ["value1", "value2", "value3"] {
+            history.record(
+                "account_id",
+                "namespace_id",
+                vec![pair("key1", value)],
+                KvVersionCause::Overwritten,
+            )?;
+        }
+        history.record(
+            "account_id",
+            "namespace_id",
+            vec![pair("key2", "value1")],
+            KvVersionCause::Deleted,
+        )?;
+
+        let restarted_history = ValueHistory::new(directory.clone(), history.config());
+        let versions = restarted_history.versions("account_id", "namespace_id", "key1")?;
+        let values: Vec<&[u8]> = versions
+            .iter()
+            .map(|version| version.pair.value.as_slice())
+            .collect();
+        assert_eq!(values, vec![b"value3".as_slice(), b"value2".as_slice()]);
+
+        let keys = restarted_history.list_keys("account_id", "namespace_id")?;
+        assert_eq!(keys.len(), 2);
+        let deleted_key = keys.iter().find(|key| key.key == "key2").unwrap();
+        assert_eq!(deleted_key.last_cause, KvVersionCause::Deleted);
+        assert!(
+            restarted_history
+                .list_keys("account_id", "other_namespace_id")?
+                .is_empty()
+        );
+
+        fs::remove_dir_all(directory.parent().unwrap())?;
+        Ok(())
+    }
+
+    #[test]
+    fn should_prune_the_versions_when_the_retention_shrinks() -> Result<(), ValueHistoryError> {
+        let directory = create_history_directory();
+        let history = ValueHistory::new(directory.clone(), ValueHistoryConfig::default());
+        history.record(
+            "account_id",
+            "namespace_id",
+            vec![pair("key1", "value1"), pair("key2", "value2")],
+            KvVersionCause::Deleted,
+        )?;
+        let version_id = history.versions("account_id", "namespace_id", "key1")?[0]
+            .id
+            .clone();
+
+        history.reconfigure(ValueHistoryConfig {
+            max_versions_per_key: 0,
+            ..ValueHistoryConfig::default()
+        })?;
+
+        assert!(history.list_keys("account_id", "namespace_id")?.is_empty());
+        assert!(matches!(
+            history.version("account_id", "namespace_id", "key1", &version_id),
+            Err(ValueHistoryError::VersionNotFound(_))
+        ));
+
+        fs::remove_dir_all(directory.parent().unwrap())?;
+        Ok(())
+    }
+
+    #[test]
+    fn should_not_record_when_disabled() -> Result<(), ValueHistoryError> {
+        let directory = create_history_directory();
+        let history = ValueHistory::new(
+            directory.clone(),
+            ValueHistoryConfig {
+                enabled: false,
+                ..ValueHistoryConfig::default()
+            },
+        );
+        history.record(
+            "account_id",
+            "namespace_id",
+            vec![pair("key1", "value1")],
+            KvVersionCause::Overwritten,
+        )?;
+
+        assert!(history.list_keys("account_id", "namespace_id")?.is_empty());
+        assert!(!directory.exists());
+        Ok(())
+    }
+
+    #[test]
+    fn should_diff_the_lines_of_text_values() {
+        let diff = diff_versions(
+            Some(pair("key1", "a\nb\nc\nd")),
+            Some(pair("key1", "a\nc\ne\nd")),
+        );
+
+        assert_eq!(
+            diff.lines.unwrap(),
+            vec![
+                line(KvValueDiffLineKind::Unchanged, "a"),
+                line(KvValueDiffLineKind::Removed, "b"),
+                line(KvValueDiffLineKind::Unchanged, "c"),
+                line(KvValueDiffLineKind::Added, "e"),
+                line(KvValueDiffLineKind::Unchanged, "d"),
+            ]
+        );
+        assert!(!diff.metadata_changed);
+        assert!(!diff.expiration_changed);
+    }
+
+    #[test]
+    fn should_not_diff_the_lines_of_binary_values() {
+        let binary_pair = KvPair {
+            value: vec![0xff, 0xfe],
+            ..pair("key1", "")
+        };
+
+        let diff = diff_versions(Some(binary_pair), None);
+
+        assert_eq!(diff.lines, None);
+        assert!(diff.metadata_changed);
+    }
+
+    #[test]
+    fn should_restore_a_version_without_its_passed_expiration() {
+        let now = Utc::now();
+        let expired = KvPair {
+            expiration: Some(now - TimeDelta::days(1)),
+            ..pair("key1", "value1")
+        };
+        let expiring = KvPair {
+            expiration: Some(now + TimeDelta::seconds(30)),
+            ..pair("key1", "value1")
+        };
+        let valid = KvPair {
+            expiration: Some(now + TimeDelta::hours(1)),
+            ..pair("key1", "value1")
+        };
+
+        assert_eq!(restorable_pair(expired, now).expiration, None);
+        assert_eq!(restorable_pair(expiring, now).expiration, None);
+        assert_eq!(restorable_pair(valid.clone(), now), valid);
+    }
+
+    fn pair(key: &str, value: &str) -> KvPair {
+        KvPair {
+            key: key.to_string(),
+            value: value.as_bytes().to_vec(),
+            metadata: None,
+            expiration: None,
+        }
+    }
+
+    fn line(kind: KvValueDiffLineKind, text: &str) -> KvValueDiffLine {
+        KvValueDiffLine {
+            kind,
+            text: text.to_string(),
+        }
+    }
+
+    fn create_history_directory() -> PathBuf {
+        create_temp_dir().join("kv-history")
+    }
+}
@@ -0,0 +1,166 @@
+use crate::cloudflare::kv::{KvClient, KvError, KvKey, KvKeysListInput};
+use chrono::{DateTime, Utc};
+use futures::TryStreamExt;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::pin::pin;
+
+// Folders of huge namespaces are counted from the first keys only.
+const MAX_TREE_KEYS: u64 = 100_000;
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct KvKeyTreeInput {
+    pub account_id: String,
+    pub namespace_id: String,
+    pub prefix: Option<String>,
+    pub delimiter: Option<String>,
+}
+
+// The prefix of a folder is the prefix to list its content with.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct KvKeyFolder {
+    pub name: String,
+    pub prefix: String,
+    pub key_count: u64,
+}
+
+// The key counts of the folders are lower bounds if the tree is not complete.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct KvKeyTree {
+    pub prefix: String,
+    pub delimiter: String,
+    pub folders: Vec<KvKeyFolder>,
+    pub keys: Vec<KvKey>,
+    pub complete: bool,
+    pub listed_at: DateTime<Utc>,
+}
+
+pub struct KeyTreeBuilder {
+    prefix: String,
+    delimiter: String,
+    folder_key_counts: BTreeMap<String, u64>,
+    keys: Vec<KvKey>,
+    key_count: u64,
+}
+
+impl KeyTreeBuilder {
+    pub fn new(prefix: &str, delimiter: &str) -> Self {
+        Self {
+            prefix: prefix.to_string(),
+            delimiter: delimiter.to_string(),
+            folder_key_counts: BTreeMap::new(),
+            keys: vec![],
+            key_count: 0,
+        }
+    }
+
+    pub fn add_key(&mut self, key: KvKey) {
+        self.key_count += 1;
+        let Some(relative_name) = key.name.strip_prefix(&self.prefix) else {
+            return;
+        };
+
+        match relative_name.split_once(&self.delimiter) {
+            Some((folder_name, _)) => {
+                *self
+                    .folder_key_counts
+                    .entry(folder_name.to_string())
+                    .or_default() += 1
+            }
+            None => self.keys.push(key),
+        }
+    }
+
+    pub fn finish(self, complete: bool) -> KvKeyTree {
+        let folders = self
+            .folder_key_counts
+            .into_iter()
+            .map(|(name, key_count)| KvKeyFolder {
+                prefix: format!("{}{name}{}", self.prefix, self.delimiter),
+                name,
+                key_count,
+            })
+            .collect();
+
+        KvKeyTree {
+            prefix: self.prefix,
+            delimiter: self.delimiter,
+            folders,
+            keys: self.keys,
+            complete,
+            listed_at: Utc::now(),
+        }
+    }
+}
+
+pub async fn build_key_tree(
+    kv: &KvClient,
+    account_id: &str,
+    namespace_id: &str,
+    prefix: &str,
+    delimiter: &str,
+) -> Result<KvKeyTree, KvError> {
+    let mut builder = KeyTreeBuilder::new(prefix, delimiter);
+    let mut keys = pin!(kv.list_all_keys(KvKeysListInput {
+        account_id: account_id.to_string(),
+        namespace_id: namespace_id.to_string(),
+        cursor: None,
+        limit: None,
+        prefix: Some(prefix.to_string()).filter(|prefix| !prefix.is_empty()),
+    }));
+    while let Some(key) = keys.try_next().await? {
+        if builder.key_count == MAX_TREE_KEYS {
+            return Ok(builder.finish(false));
+        }
+        builder.add_key(key);
+    }
+
+    Ok(builder.finish(true))
+}
+
+#[cfg(test)]
+mod test {
+    use super::{KeyTreeBuilder, KvKeyFolder};
+    use crate::cloudflare::kv::KvKey;
+
+    #[test]
+    fn should_group_the_keys_into_folders() {
+        let mut builder = KeyTreeBuilder::new("tenant:42:", ":");
+        for name in [
+            "tenant:42:settings",
+            "tenant:42:user:7:profile",
+            "tenant:42:user:7:avatar",
+            "tenant:42:user:8:profile",
+            "tenant:42:group:1",
+        ] {
+            builder.add_key(create_key(name));
+        }
+
+        let tree = builder.finish(true);
+
+        assert_eq!(
+            tree.folders,
+            vec![
+                KvKeyFolder {
+                    name: "group".to_string(),
+                    prefix: "tenant:42:group:".to_string(),
+                    key_count: 1,
+                },
+                KvKeyFolder {
+                    name: "user".to_string(),
+                    prefix: "tenant:42:user:".to_string(),
+                    key_count: 3,
+                },
+            ]
+        );
+        assert_eq!(tree.keys, vec![create_key("tenant:42:settings")]);
+    }
+
+    fn create_key(name: &str) -> KvKey {
+        KvKey {
+            name: name.to_string(),
+            metadata: None,
+            expiration: None,
+        }
+    }
+}
@@ -1,4 +1,10 @@
-use crate::cloudflare::common::Credentials;
+use crate::audit::audit_log::{AuditEntry, AuditLog, AuditOperation, AuditOutcome, AuditedKey};
+use crate::bulk::namespace_backups::NamespaceBackupError;
+use crate::cache::kv_cache::{
+    KvCache, keys_cache_key, namespace_cache_prefix, namespaces_cache_key, namespaces_cache_prefix,
+    value_cache_key,
+};
+use crate::cloudflare::common::{CloudflareApiError, retry_after_ms};
 use crate::cloudflare::kv::{
     KvError, KvKeys, KvKeysListInput, KvNamespace, KvNamespaceCreateInput, KvNamespaceGetInput,
     KvNamespaces, KvNamespacesListInput, KvPair, KvPairCreateInput, KvPairGetInput,
@@ -6,135 +12,630 @@ use crate::cloudflare::kv::{
     KvPairsWriteResult,
 };
 use crate::cloudflare::kv::{KvNamespaceDeleteInput, KvNamespaceUpdateInput};
-use crate::cloudflare::Cloudflare;
+use crate::environment::environment_commands::{
+    acknowledge_production, label_namespace, label_namespaces,
+};
+use crate::environment::environment_labels::{
+    AcknowledgedInput, Environment, EnvironmentError, LabelledNamespace, LabelledNamespaces,
+    NamespaceEnvironments,
+};
+use crate::history::history_commands::record_previous_versions;
+use crate::history::value_history::{KvVersionCause, ValueHistory, ValueHistoryError};
+use crate::kv::key_tree::{KvKeyTree, KvKeyTreeInput, build_key_tree};
+use crate::kv::staging_area::StagingError;
+use crate::protection::protected_namespaces::{
+    ConfirmedInput, ProtectedNamespaces, ProtectionError,
+};
+use crate::protection::protection_commands::confirm_deletion;
+use crate::session::session_registry::{SessionError, SessionRegistry};
+use crate::statistics::namespace_statistics::DEFAULT_KEY_DELIMITER;
 
-use log::error;
+use futures::TryStreamExt;
+use log::{debug, error, warn};
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
+use std::future::Future;
+use std::sync::Arc;
+use tauri::State;
 
 #[tauri::command]
 pub async fn list_namespaces(
-    credentials: Credentials,
+    sessions: State<'_, Arc<SessionRegistry>>,
+    cache: State<'_, Arc<KvCache>>,
+    environments: State<'_, Arc<NamespaceEnvironments>>,
+    session_id: String,
+    input: KvNamespacesListInput,
+) -> Result<LabelledNamespaces, KvCommandError> {
+    let cloudflare_client = sessions.get(&session_id)?;
+    let account_id = input.account_id.clone();
+    let cache_key = namespaces_cache_key(&input.account_id, &input);
+    let namespaces: KvNamespaces = read_through(&cache, cache_key, move || async move {
+        cloudflare_client.kv.list_namespaces(input).await
+    })
+    .await?;
+    Ok(LabelledNamespaces {
+        items: label_namespaces(
+            &environments,
+            profile_environment(&sessions, &session_id),
+            &account_id,
+            namespaces.items,
+        ),
+        page_info: namespaces.page_info,
+    })
+}
+
+#[tauri::command]
+pub async fn list_all_namespaces(
+    sessions: State<'_, Arc<SessionRegistry>>,
+    cache: State<'_, Arc<KvCache>>,
+    environments: State<'_, Arc<NamespaceEnvironments>>,
+    session_id: String,
     input: KvNamespacesListInput,
-) -> Result<KvNamespaces, KvCommandError> {
-    let cloudflare_client = Cloudflare::new(credentials, None);
-    let kv = cloudflare_client.kv;
-    Ok(kv.list_namespaces(input).await?)
+) -> Result<Vec<LabelledNamespace>, KvCommandError> {
+    let cloudflare_client = sessions.get(&session_id)?;
+    let account_id = input.account_id.clone();
+    let cache_key = namespaces_cache_key(&input.account_id, &("all", &input));
+    let namespaces: Vec<KvNamespace> = read_through(&cache, cache_key, move || async move {
+        cloudflare_client
+            .kv
+            .list_all_namespaces(input)
+            .try_collect()
+            .await
+    })
+    .await?;
+    Ok(label_namespaces(
+        &environments,
+        profile_environment(&sessions, &session_id),
+        &account_id,
+        namespaces,
+    ))
 }
 
 #[tauri::command]
 pub async fn get_namespace(
-    credentials: Credentials,
+    sessions: State<'_, Arc<SessionRegistry>>,
+    environments: State<'_, Arc<NamespaceEnvironments>>,
+    session_id: String,
     input: KvNamespaceGetInput,
-) -> Result<KvNamespace, KvCommandError> {
-    let cloudflare_client = Cloudflare::new(credentials, None);
-    let kv = cloudflare_client.kv;
-    Ok(kv.get_namespace(input).await?)
+) -> Result<LabelledNamespace, KvCommandError> {
+    let cloudflare_client = sessions.get(&session_id)?;
+    let account_id = input.account_id.clone();
+    let namespace = cloudflare_client.kv.get_namespace(input).await?;
+    Ok(label_namespace(
+        &environments,
+        profile_environment(&sessions, &session_id),
+        &account_id,
+        namespace,
+    ))
 }
 
 #[tauri::command]
 pub async fn create_namespace(
-    credentials: Credentials,
-    input: KvNamespaceCreateInput,
-) -> Result<KvNamespace, KvCommandError> {
-    let cloudflare_client = Cloudflare::new(credentials, None);
-    let kv = cloudflare_client.kv;
-    Ok(kv.create_namespace(input).await?)
+    sessions: State<'_, Arc<SessionRegistry>>,
+    cache: State<'_, Arc<KvCache>>,
+    audit_log: State<'_, Arc<AuditLog>>,
+    environments: State<'_, Arc<NamespaceEnvironments>>,
+    session_id: String,
+    input: AcknowledgedInput<KvNamespaceCreateInput>,
+) -> Result<LabelledNamespace, KvCommandError> {
+    let cloudflare_client = sessions.get_writable(&session_id)?;
+    let AcknowledgedInput {
+        input,
+        acknowledged,
+    } = input;
+    let mut audit_entry = AuditEntry::new(
+        sessions.profile(&session_id),
+        AuditOperation::NamespaceCreate,
+        &input.account_id,
+        None,
+    );
+    audit_entry.namespace_title = Some(input.title.clone());
+    acknowledge_production(
+        &environments,
+        &cloudflare_client.kv,
+        &mut audit_entry,
+        acknowledged,
+    )
+    .await?;
+    let account_id = input.account_id.clone();
+    let result = cloudflare_client.kv.create_namespace(input).await;
+    invalidate(&cache, &namespaces_cache_prefix(&account_id));
+    audit_entry.namespace_id = result.as_ref().ok().map(|namespace| namespace.id.clone());
+    audit_entry.outcome = AuditOutcome::of(&result, |_| AuditOutcome::Succeeded);
+    audit_log.record(audit_entry);
+    let namespace = result?;
+    Ok(label_namespace(
+        &environments,
+        profile_environment(&sessions, &session_id),
+        &account_id,
+        namespace,
+    ))
 }
 
 #[tauri::command]
 pub async fn update_namespace(
-    credentials: Credentials,
-    input: KvNamespaceUpdateInput,
-) -> Result<KvNamespace, KvCommandError> {
-    let cloudflare_client = Cloudflare::new(credentials, None);
-    let kv = cloudflare_client.kv;
-    Ok(kv.update_namespace(input).await?)
+    sessions: State<'_, Arc<SessionRegistry>>,
+    cache: State<'_, Arc<KvCache>>,
+    audit_log: State<'_, Arc<AuditLog>>,
+    environments: State<'_, Arc<NamespaceEnvironments>>,
+    session_id: String,
+    input: AcknowledgedInput<KvNamespaceUpdateInput>,
+) -> Result<LabelledNamespace, KvCommandError> {
+    let cloudflare_client = sessions.get_writable(&session_id)?;
+    let AcknowledgedInput {
+        input,
+        acknowledged,
+    } = input;
+    let mut audit_entry = AuditEntry::new(
+        sessions.profile(&session_id),
+        AuditOperation::NamespaceRename,
+        &input.account_id,
+        Some(&input.namespace_id),
+    );
+    audit_entry.namespace_title = Some(input.title.clone());
+    acknowledge_production(
+        &environments,
+        &cloudflare_client.kv,
+        &mut audit_entry,
+        acknowledged,
+    )
+    .await?;
+    let account_id = input.account_id.clone();
+    let result = cloudflare_client.kv.update_namespace(input).await;
+    invalidate(&cache, &namespaces_cache_prefix(&account_id));
+    audit_entry.outcome = AuditOutcome::of(&result, |_| AuditOutcome::Succeeded);
+    audit_log.record(audit_entry);
+    let namespace = result?;
+    Ok(label_namespace(
+        &environments,
+        profile_environment(&sessions, &session_id),
+        &account_id,
+        namespace,
+    ))
 }
 
 #[tauri::command]
 pub async fn delete_namespace(
-    credentials: Credentials,
-    input: KvNamespaceDeleteInput,
+    sessions: State<'_, Arc<SessionRegistry>>,
+    cache: State<'_, Arc<KvCache>>,
+    audit_log: State<'_, Arc<AuditLog>>,
+    protected_namespaces: State<'_, Arc<ProtectedNamespaces>>,
+    environments: State<'_, Arc<NamespaceEnvironments>>,
+    session_id: String,
+    input: AcknowledgedInput<ConfirmedInput<KvNamespaceDeleteInput>>,
 ) -> Result<(), KvCommandError> {
-    let cloudflare_client = Cloudflare::new(credentials, None);
-    let kv = cloudflare_client.kv;
-    Ok(kv.delete_namespace(input).await?)
+    let cloudflare_client = sessions.get_writable(&session_id)?;
+    let AcknowledgedInput {
+        input: ConfirmedInput {
+            input,
+            confirmation,
+        },
+        acknowledged,
+    } = input;
+    let mut audit_entry = AuditEntry::new(
+        sessions.profile(&session_id),
+        AuditOperation::NamespaceDelete,
+        &input.account_id,
+        Some(&input.namespace_id),
+    );
+    acknowledge_production(
+        &environments,
+        &cloudflare_client.kv,
+        &mut audit_entry,
+        acknowledged,
+    )
+    .await?;
+    confirm_deletion(
+        &protected_namespaces,
+        &cloudflare_client.kv,
+        &input.account_id,
+        &input.namespace_id,
+        confirmation.as_deref(),
+    )
+    .await?;
+    let (account_id, namespace_id) = (input.account_id.clone(), input.namespace_id.clone());
+    let result = cloudflare_client.kv.delete_namespace(input).await;
+    invalidate(&cache, &namespaces_cache_prefix(&account_id));
+    invalidate(&cache, &namespace_cache_prefix(&account_id, &namespace_id));
+    audit_entry.outcome = AuditOutcome::of(&result, |_| AuditOutcome::Succeeded);
+    audit_log.record(audit_entry);
+    Ok(result?)
 }
 
 #[tauri::command]
 pub async fn get_kv_pair(
-    credentials: Credentials,
+    sessions: State<'_, Arc<SessionRegistry>>,
+    cache: State<'_, Arc<KvCache>>,
+    session_id: String,
     input: KvPairGetInput,
 ) -> Result<KvPair, KvCommandError> {
-    let cloudflare_client = Cloudflare::new(credentials, None);
-    let kv = cloudflare_client.kv;
-    Ok(kv.get_kv_pair(input).await?)
+    let cloudflare_client = sessions.get(&session_id)?;
+    let cache_key = value_cache_key(&input.account_id, &input.namespace_id, &input.key);
+    Ok(read_through(&cache, cache_key, move || async move {
+        cloudflare_client.kv.get_kv_pair(input).await
+    })
+    .await?)
 }
 
+// The values are cached by key, so the cache is only used if it holds all requested keys.
 #[tauri::command]
 pub async fn get_kv_pairs(
-    credentials: Credentials,
+    sessions: State<'_, Arc<SessionRegistry>>,
+    cache: State<'_, Arc<KvCache>>,
+    session_id: String,
     input: KvPairsGetInput,
 ) -> Result<Vec<KvPair>, KvCommandError> {
-    let cloudflare_client = Cloudflare::new(credentials, None);
-    let kv = cloudflare_client.kv;
-    Ok(kv.get_kv_pairs(input).await?)
+    let cloudflare_client = sessions.get(&session_id)?;
+    let generation = cache.generation();
+    let cached_pairs: Option<Vec<_>> = input
+        .keys
+        .iter()
+        .map(|key| {
+            cache.get::<KvPair>(&value_cache_key(
+                &input.account_id,
+                &input.namespace_id,
+                key,
+            ))
+        })
+        .collect();
+
+    if let Some(cached_pairs) = cached_pairs {
+        let stale_keys: Vec<String> = cached_pairs
+            .iter()
+            .filter(|cached_pair| cached_pair.is_stale)
+            .map(|cached_pair| cached_pair.value.key.clone())
+            .collect();
+        if !stale_keys.is_empty() {
+            let cache = cache.inner().clone();
+            let input = KvPairsGetInput {
+                keys: stale_keys,
+                ..input
+            };
+            tauri::async_runtime::spawn(async move {
+                match cloudflare_client.kv.get_kv_pairs(input.clone()).await {
+                    Ok(pairs) => store_pairs(&cache, &input, &pairs, generation),
+                    Err(error) => warn!("The cached values could not be refreshed: {error}"),
+                }
+            });
+        }
+
+        return Ok(cached_pairs
+            .into_iter()
+            .map(|cached_pair| cached_pair.value)
+            .collect());
+    }
+
+    let pairs = cloudflare_client.kv.get_kv_pairs(input.clone()).await?;
+    store_pairs(&cache, &input, &pairs, generation);
+    Ok(pairs)
 }
 
 #[tauri::command]
 pub async fn write_kv_pair(
-    credentials: Credentials,
-    input: KvPairWriteInput,
+    sessions: State<'_, Arc<SessionRegistry>>,
+    cache: State<'_, Arc<KvCache>>,
+    history: State<'_, Arc<ValueHistory>>,
+    audit_log: State<'_, Arc<AuditLog>>,
+    environments: State<'_, Arc<NamespaceEnvironments>>,
+    session_id: String,
+    input: AcknowledgedInput<KvPairWriteInput>,
 ) -> Result<KvPair, KvCommandError> {
-    let cloudflare_client = Cloudflare::new(credentials, None);
-    let kv = cloudflare_client.kv;
-    Ok(kv.write_kv_pair(input).await?)
+    let cloudflare_client = sessions.get_writable(&session_id)?;
+    let AcknowledgedInput {
+        input,
+        acknowledged,
+    } = input;
+    let mut audit_entry = AuditEntry::new(
+        sessions.profile(&session_id),
+        AuditOperation::KeyWrite,
+        &input.account_id,
+        Some(&input.namespace_id),
+    );
+    acknowledge_production(
+        &environments,
+        &cloudflare_client.kv,
+        &mut audit_entry,
+        acknowledged,
+    )
+    .await?;
+    let previous_pairs_input = KvPairsGetInput {
+        account_id: input.account_id.clone(),
+        namespace_id: input.namespace_id.clone(),
+        keys: vec![input.key.clone()],
+    };
+    record_previous_versions(
+        &history,
+        &cloudflare_client.kv,
+        previous_pairs_input,
+        KvVersionCause::Overwritten,
+    )
+    .await?;
+    audit_entry.keys = vec![AuditedKey::new(&input.key, input.value.as_deref())];
+    let cache_prefix = namespace_cache_prefix(&input.account_id, &input.namespace_id);
+    let result = cloudflare_client.kv.write_kv_pair(input).await;
+    invalidate(&cache, &cache_prefix);
+    audit_entry.outcome = AuditOutcome::of(&result, |_| AuditOutcome::Succeeded);
+    audit_log.record(audit_entry);
+    Ok(result?)
 }
 
 #[tauri::command]
 pub async fn write_kv_pairs(
-    credentials: Credentials,
-    input: KvPairsWriteInput,
+    sessions: State<'_, Arc<SessionRegistry>>,
+    cache: State<'_, Arc<KvCache>>,
+    history: State<'_, Arc<ValueHistory>>,
+    audit_log: State<'_, Arc<AuditLog>>,
+    environments: State<'_, Arc<NamespaceEnvironments>>,
+    session_id: String,
+    input: AcknowledgedInput<KvPairsWriteInput>,
 ) -> Result<KvPairsWriteResult, KvCommandError> {
-    let cloudflare_client = Cloudflare::new(credentials, None);
-    let kv = cloudflare_client.kv;
-    Ok(kv.write_kv_pairs(input).await?)
+    let cloudflare_client = sessions.get_writable(&session_id)?;
+    let AcknowledgedInput {
+        input,
+        acknowledged,
+    } = input;
+    let mut audit_entry = AuditEntry::new(
+        sessions.profile(&session_id),
+        AuditOperation::KeyWrite,
+        &input.account_id,
+        Some(&input.namespace_id),
+    );
+    acknowledge_production(
+        &environments,
+        &cloudflare_client.kv,
+        &mut audit_entry,
+        acknowledged,
+    )
+    .await?;
+    let previous_pairs_input = KvPairsGetInput {
+        account_id: input.account_id.clone(),
+        namespace_id: input.namespace_id.clone(),
+        keys: input.pairs.iter().map(|pair| pair.key.clone()).collect(),
+    };
+    record_previous_versions(
+        &history,
+        &cloudflare_client.kv,
+        previous_pairs_input,
+        KvVersionCause::Overwritten,
+    )
+    .await?;
+    audit_entry.keys = input
+        .pairs
+        .iter()
+        .map(|pair| AuditedKey::new(&pair.key, Some(&pair.value_bytes())))
+        .collect();
+    let cache_prefix = namespace_cache_prefix(&input.account_id, &input.namespace_id);
+    let result = cloudflare_client.kv.write_kv_pairs(input).await;
+    invalidate(&cache, &cache_prefix);
+    audit_entry.outcome = AuditOutcome::of(&result, |result| result.into());
+    audit_log.record(audit_entry);
+    Ok(result?)
 }
 
 #[tauri::command]
 pub async fn create_kv_pair(
-    credentials: Credentials,
-    input: KvPairCreateInput,
+    sessions: State<'_, Arc<SessionRegistry>>,
+    cache: State<'_, Arc<KvCache>>,
+    audit_log: State<'_, Arc<AuditLog>>,
+    environments: State<'_, Arc<NamespaceEnvironments>>,
+    session_id: String,
+    input: AcknowledgedInput<KvPairCreateInput>,
 ) -> Result<KvPair, KvCommandError> {
-    let cloudflare_client = Cloudflare::new(credentials, None);
-    let kv = cloudflare_client.kv;
-    Ok(kv.create_kv_pair(input).await?)
+    let cloudflare_client = sessions.get_writable(&session_id)?;
+    let AcknowledgedInput {
+        input,
+        acknowledged,
+    } = input;
+    let mut audit_entry = AuditEntry::new(
+        sessions.profile(&session_id),
+        AuditOperation::KeyCreate,
+        &input.account_id,
+        Some(&input.namespace_id),
+    );
+    acknowledge_production(
+        &environments,
+        &cloudflare_client.kv,
+        &mut audit_entry,
+        acknowledged,
+    )
+    .await?;
+    audit_entry.keys = vec![AuditedKey::new(&input.key, input.value.as_deref())];
+    let cache_prefix = namespace_cache_prefix(&input.account_id, &input.namespace_id);
+    let result = cloudflare_client.kv.create_kv_pair(input).await;
+    invalidate(&cache, &cache_prefix);
+    audit_entry.outcome = AuditOutcome::of(&result, |_| AuditOutcome::Succeeded);
+    audit_log.record(audit_entry);
+    Ok(result?)
 }
 
 #[tauri::command]
 pub async fn delete_kv_pairs(
-    credentials: Credentials,
-    input: KvPairsDeleteInput,
+    sessions: State<'_, Arc<SessionRegistry>>,
+    cache: State<'_, Arc<KvCache>>,
+    history: State<'_, Arc<ValueHistory>>,
+    protected_namespaces: State<'_, Arc<ProtectedNamespaces>>,
+    audit_log: State<'_, Arc<AuditLog>>,
+    environments: State<'_, Arc<NamespaceEnvironments>>,
+    session_id: String,
+    input: AcknowledgedInput<ConfirmedInput<KvPairsDeleteInput>>,
 ) -> Result<KvPairsDeleteResult, KvCommandError> {
-    let cloudflare_client = Cloudflare::new(credentials, None);
-    let kv = cloudflare_client.kv;
-    Ok(kv.delete_kv_pairs(input).await?)
+    let cloudflare_client = sessions.get_writable(&session_id)?;
+    let AcknowledgedInput {
+        input: ConfirmedInput {
+            input,
+            confirmation,
+        },
+        acknowledged,
+    } = input;
+    let mut audit_entry = AuditEntry::new(
+        sessions.profile(&session_id),
+        AuditOperation::KeyDelete,
+        &input.account_id,
+        Some(&input.namespace_id),
+    );
+    acknowledge_production(
+        &environments,
+        &cloudflare_client.kv,
+        &mut audit_entry,
+        acknowledged,
+    )
+    .await?;
+    confirm_deletion(
+        &protected_namespaces,
+        &cloudflare_client.kv,
+        &input.account_id,
+        &input.namespace_id,
+        confirmation.as_deref(),
+    )
+    .await?;
+    let previous_pairs_input = KvPairsGetInput {
+        account_id: input.account_id.clone(),
+        namespace_id: input.namespace_id.clone(),
+        keys: input.keys.clone(),
+    };
+    record_previous_versions(
+        &history,
+        &cloudflare_client.kv,
+        previous_pairs_input,
+        KvVersionCause::Deleted,
+    )
+    .await?;
+    audit_entry.keys = input
+        .keys
+        .iter()
+        .map(|key| AuditedKey::new(key, None))
+        .collect();
+    let cache_prefix = namespace_cache_prefix(&input.account_id, &input.namespace_id);
+    let result = cloudflare_client.kv.delete_kv_pairs(input).await;
+    invalidate(&cache, &cache_prefix);
+    audit_entry.outcome = AuditOutcome::of(&result, |result| result.into());
+    audit_log.record(audit_entry);
+    Ok(result?)
 }
 
 #[tauri::command]
 pub async fn list_kv_keys(
-    credentials: Credentials,
+    sessions: State<'_, Arc<SessionRegistry>>,
+    cache: State<'_, Arc<KvCache>>,
+    session_id: String,
     input: KvKeysListInput,
 ) -> Result<KvKeys, KvCommandError> {
-    let cloudflare_client = Cloudflare::new(credentials, None);
-    let kv = cloudflare_client.kv;
-    Ok(kv.list_keys(input).await?)
+    let cloudflare_client = sessions.get(&session_id)?;
+    let cache_key = keys_cache_key(&input.account_id, &input.namespace_id, &input);
+    Ok(read_through(&cache, cache_key, move || async move {
+        cloudflare_client.kv.list_keys(input).await
+    })
+    .await?)
+}
+
+// Lists the folders and keys directly below the prefix, the folders are cached like the keys.
+#[tauri::command]
+pub async fn list_key_tree(
+    sessions: State<'_, Arc<SessionRegistry>>,
+    cache: State<'_, Arc<KvCache>>,
+    session_id: String,
+    input: KvKeyTreeInput,
+) -> Result<KvKeyTree, KvCommandError> {
+    let cloudflare_client = sessions.get(&session_id)?;
+    let prefix = input.prefix.unwrap_or_default();
+    let delimiter = input
+        .delimiter
+        .filter(|delimiter| !delimiter.is_empty())
+        .unwrap_or_else(|| DEFAULT_KEY_DELIMITER.to_string());
+    let cache_key = keys_cache_key(
+        &input.account_id,
+        &input.namespace_id,
+        &("tree", &prefix, &delimiter),
+    );
+    Ok(read_through(&cache, cache_key, move || async move {
+        build_key_tree(
+            &cloudflare_client.kv,
+            &input.account_id,
+            &input.namespace_id,
+            &prefix,
+            &delimiter,
+        )
+        .await
+    })
+    .await?)
+}
+
+// Cached results are served right away, even offline. Stale ones are refreshed in the background.
+async fn read_through<T, F, Fut>(
+    cache: &Arc<KvCache>,
+    cache_key: String,
+    fetch: F,
+) -> Result<T, KvError>
+where
+    T: Serialize + DeserializeOwned + Send + 'static,
+    F: FnOnce() -> Fut,
+    Fut: Future<Output = Result<T, KvError>> + Send + 'static,
+{
+    let generation = cache.generation();
+    if let Some(cached) = cache.get::<T>(&cache_key) {
+        debug!(
+            "Serving {cache_key} from the cache, fetched at {}",
+            cached.fetched_at
+        );
+        if cached.is_stale {
+            let cache = cache.clone();
+            let refresh = fetch();
+            tauri::async_runtime::spawn(async move {
+                match refresh.await {
+                    Ok(value) => store(&cache, &cache_key, &value, generation),
+                    Err(error) => {
+                        warn!("The cache entry {cache_key} could not be refreshed: {error}")
+                    }
+                }
+            });
+        }
+
+        return Ok(cached.value);
+    }
+
+    let value = fetch().await?;
+    store(cache, &cache_key, &value, generation);
+    Ok(value)
+}
+
+fn store<T: Serialize>(cache: &KvCache, cache_key: &str, value: &T, generation: u64) {
+    if let Err(error) = cache.put(cache_key, value, generation) {
+        warn!("The cache entry {cache_key} could not be written: {error}");
+    }
+}
+
+fn store_pairs(cache: &KvCache, input: &KvPairsGetInput, pairs: &[KvPair], generation: u64) {
+    let entries = pairs.iter().map(|pair| {
+        let cache_key = value_cache_key(&input.account_id, &input.namespace_id, &pair.key);
+        (cache_key, pair)
+    });
+    if let Err(error) = cache.put_many(entries, generation) {
+        warn!(
+            "The cached values of {} could not be written: {error}",
+            input.namespace_id
+        );
+    }
+}
+
+fn profile_environment(sessions: &SessionRegistry, session_id: &str) -> Option<Environment> {
+    sessions
+        .profile(session_id)
+        .and_then(|profile| profile.environment)
+}
+
+fn invalidate(cache: &KvCache, cache_prefix: &str) {
+    if let Err(error) = cache.invalidate(cache_prefix) {
+        warn!("The cache entries {cache_prefix} could not be invalidated: {error}");
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct KvCommandError {
     kind: KvCommandErrorKind,
     message: String,
+    api_error: Option<CloudflareApiError>,
+    // When a rate limited request may be retried, if Cloudflare told.
+    retry_after_ms: Option<u64>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -149,41 +650,65 @@ pub enum KvCommandErrorKind {
     InvalidExpiration,
 
     NonTextValue,
+    ConflictingChange,
+    VersionNotFound,
+    HistoryUnavailable,
+    ConfirmationRequired,
+    ProductionAcknowledgementRequired,
+    InvalidPattern,
+    BackupFailed,
 
     Authentication,
+    SessionExpired,
+    ReadOnly,
+    RateLimited,
     Unknown,
 }
 
 impl From<KvError> for KvCommandError {
     fn from(error: KvError) -> Self {
         match error {
-            KvError::NamespaceAlreadyExists(message) => KvCommandError {
+            KvError::NamespaceAlreadyExists(api_error) => KvCommandError {
                 kind: KvCommandErrorKind::NamespaceAlreadyExists,
-                message,
+                message: api_error.message(),
+                api_error: Some(api_error),
+                retry_after_ms: None,
             },
-            KvError::NamespaceTitleMissing(message) => KvCommandError {
+            KvError::NamespaceTitleMissing(api_error) => KvCommandError {
                 kind: KvCommandErrorKind::NamespaceTitleMissing,
-                message,
+                message: api_error.message(),
+                api_error: Some(api_error),
+                retry_after_ms: None,
             },
-            KvError::NamespaceNotFound => KvCommandError {
+            KvError::NamespaceNotFound(api_error) => KvCommandError {
                 kind: KvCommandErrorKind::NamespaceNotFound,
                 message: "Namespace not found".to_string(),
+                api_error: Some(api_error),
+                retry_after_ms: None,
             },
-            KvError::KeyNotFound => KvCommandError {
+            KvError::KeyNotFound(api_error) => KvCommandError {
                 kind: KvCommandErrorKind::KeyNotFound,
                 message: "Key not found".to_string(),
+                api_error: Some(api_error),
+                retry_after_ms: None,
             },
             KvError::KeyAlreadyExists(key) => KvCommandError {
                 kind: KvCommandErrorKind::KeyAlreadyExists,
                 message: format!("An item with the key {key} already exists"),
+                api_error: None,
+                retry_after_ms: None,
             },
-            KvError::InvalidMetadata => KvCommandError {
+            KvError::InvalidMetadata(api_error) => KvCommandError {
                 kind: KvCommandErrorKind::InvalidMetadata,
                 message: "Metadata must be valid json".to_string(),
+                api_error: Some(api_error),
+                retry_after_ms: None,
             },
-            KvError::InvalidExpiration => KvCommandError {
+            KvError::InvalidExpiration(api_error) => KvCommandError {
                 kind: KvCommandErrorKind::InvalidExpiration,
                 message: "Invalid expiration date. Please specify integer greater than the current number of seconds since the UNIX epoch.".to_string(),
+                api_error: Some(api_error),
+                retry_after_ms: None,
             },
             KvError::Token(token_err) => {
                 error!(
@@ -192,6 +717,35 @@ impl From<KvError> for KvCommandError {
                 KvCommandError {
                     kind: KvCommandErrorKind::Authentication,
                     message: "Authentication error".to_string(),
+                    api_error: None,
+                    retry_after_ms: None,
+                }
+            }
+            KvError::Unauthorized(api_error) => {
+                error!("A request to kv could not be authenticated: {api_error}");
+                KvCommandError {
+                    kind: KvCommandErrorKind::Authentication,
+                    message: "Authentication error".to_string(),
+                    api_error: Some(api_error),
+                    retry_after_ms: None,
+                }
+            }
+            KvError::RateLimited(retry_after, api_error) => {
+                error!("Rate limit exceeded on interacting with kv, retry after: {retry_after:?}");
+                KvCommandError {
+                    kind: KvCommandErrorKind::RateLimited,
+                    message: "Too many requests, please try again later".to_string(),
+                    api_error: Some(api_error),
+                    retry_after_ms: retry_after_ms(retry_after),
+                }
+            }
+            KvError::Api(api_error) => {
+                error!("A kv API error occurred: {api_error}");
+                KvCommandError {
+                    kind: KvCommandErrorKind::Unknown,
+                    message: api_error.message(),
+                    api_error: Some(api_error),
+                    retry_after_ms: None,
                 }
             }
             KvError::Reqwest(reqwest_err) => {
@@ -201,6 +755,8 @@ impl From<KvError> for KvCommandError {
                 KvCommandError {
                     kind: KvCommandErrorKind::Unknown,
                     message: "A network error occurred".to_string(),
+                    api_error: None,
+                    retry_after_ms: None,
                 }
             }
             KvError::Unknown(unknown_err) => {
@@ -208,15 +764,140 @@ impl From<KvError> for KvCommandError {
                 KvCommandError {
                     kind: KvCommandErrorKind::Unknown,
                     message: "An unknown error occurred".to_string(),
+                    api_error: None,
+                    retry_after_ms: None,
                 }
             }
-            KvError::NonTextValue => {
+            KvError::NonTextValue(api_error) => {
                 error!("At least one of the requested keys corresponds to a non-text value.");
                 KvCommandError {
                     kind: KvCommandErrorKind::NonTextValue,
                     message: "At least one of the requested keys corresponds to a non-text value.".to_string(),
+                    api_error: Some(api_error),
+                    retry_after_ms: None,
                 }
             }
         }
     }
 }
+
+impl From<SessionError> for KvCommandError {
+    fn from(error: SessionError) -> Self {
+        if error == SessionError::ReadOnly {
+            return KvCommandError {
+                kind: KvCommandErrorKind::ReadOnly,
+                message: "The profile is read-only".to_string(),
+                api_error: None,
+                retry_after_ms: None,
+            };
+        }
+
+        error!("A session error occurred on interacting with kv: {error}");
+        KvCommandError {
+            kind: KvCommandErrorKind::SessionExpired,
+            message: "Session has expired, please log in again".to_string(),
+            api_error: None,
+            retry_after_ms: None,
+        }
+    }
+}
+
+impl From<StagingError> for KvCommandError {
+    fn from(error: StagingError) -> Self {
+        KvCommandError {
+            kind: KvCommandErrorKind::ConflictingChange,
+            message: error.to_string(),
+            api_error: None,
+            retry_after_ms: None,
+        }
+    }
+}
+
+impl From<ValueHistoryError> for KvCommandError {
+    fn from(error: ValueHistoryError) -> Self {
+        match error {
+            ValueHistoryError::VersionNotFound(_) => KvCommandError {
+                kind: KvCommandErrorKind::VersionNotFound,
+                message: error.to_string(),
+                api_error: None,
+                retry_after_ms: None,
+            },
+            ValueHistoryError::Io(_) | ValueHistoryError::Serialization(_) => {
+                error!("The value history could not be read or written: {error}");
+                KvCommandError {
+                    kind: KvCommandErrorKind::HistoryUnavailable,
+                    message: "The value history could not be read or written".to_string(),
+                    api_error: None,
+                    retry_after_ms: None,
+                }
+            }
+        }
+    }
+}
+
+impl From<ProtectionError> for KvCommandError {
+    fn from(error: ProtectionError) -> Self {
+        match error {
+            ProtectionError::ConfirmationRequired(_) => KvCommandError {
+                kind: KvCommandErrorKind::ConfirmationRequired,
+                message: error.to_string(),
+                api_error: None,
+                retry_after_ms: None,
+            },
+            ProtectionError::Io(_) | ProtectionError::Serialization(_) => {
+                error!("The protected namespaces could not be read or written: {error}");
+                KvCommandError {
+                    kind: KvCommandErrorKind::Unknown,
+                    message: "The protected namespaces could not be read or written".to_string(),
+                    api_error: None,
+                    retry_after_ms: None,
+                }
+            }
+        }
+    }
+}
+
+impl From<EnvironmentError> for KvCommandError {
+    fn from(error: EnvironmentError) -> Self {
+        match error {
+            EnvironmentError::AcknowledgementRequired(_) => KvCommandError {
+                kind: KvCommandErrorKind::ProductionAcknowledgementRequired,
+                message: error.to_string(),
+                api_error: None,
+                retry_after_ms: None,
+            },
+            EnvironmentError::Io(_) | EnvironmentError::Serialization(_) => {
+                error!("The namespace environments could not be read or written: {error}");
+                KvCommandError {
+                    kind: KvCommandErrorKind::Unknown,
+                    message: "The namespace environments could not be read or written".to_string(),
+                    api_error: None,
+                    retry_after_ms: None,
+                }
+            }
+        }
+    }
+}
+
+impl From<NamespaceBackupError> for KvCommandError {
+    fn from(error: NamespaceBackupError) -> Self {
+        error!("The namespace backup could not be written: {error}");
+        KvCommandError {
+            kind: KvCommandErrorKind::BackupFailed,
+            message: "The backup could not be written, the namespace was not deleted".to_string(),
+            api_error: None,
+            retry_after_ms: None,
+        }
+    }
+}
+
+impl From<regex::Error> for KvCommandError {
+    fn from(error: regex::Error) -> Self {
+        KvCommandError {
+            kind: KvCommandErrorKind::InvalidPattern,
+            message: format!("The pattern is not a valid regular expression: {error}"),
+            api_error: None,
+            retry_after_ms: None,
+        }
+    }
+}
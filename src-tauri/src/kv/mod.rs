@@ -1 +1,4 @@
+pub mod key_tree;
 pub mod kv_commands;
+pub mod staging_area;
+pub mod staging_commands;
@@ -0,0 +1,768 @@
+use crate::cloudflare::kv::{
+    BULK_WRITE_KEY_LIMIT, KvClient, KvError, KvPair, KvPairBulkWriteInput, KvPairMetadata,
+    KvPairValue, KvPairsDeleteInput, KvPairsGetInput, KvPairsWriteInput, bulk_write_chunks,
+};
+use chrono::serde::ts_seconds_option;
+use chrono::{DateTime, TimeDelta, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::error::Error;
+use std::fmt;
+use std::fmt::{Display, Formatter};
+use std::sync::{Mutex, MutexGuard};
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(tag = "type")]
+pub enum StagedChange {
+    Create {
+        value: Vec<u8>,
+        #[serde(default)]
+        metadata: KvPairMetadata,
+        #[serde(default)]
+        #[serde(with = "ts_seconds_option")]
+        expiration: Option<DateTime<Utc>>,
+        expiration_ttl: Option<u32>,
+    },
+    Update {
+        value: Vec<u8>,
+        #[serde(default)]
+        metadata: KvPairMetadata,
+        #[serde(default)]
+        #[serde(with = "ts_seconds_option")]
+        expiration: Option<DateTime<Utc>>,
+        expiration_ttl: Option<u32>,
+    },
+    Delete,
+    Expiration {
+        #[serde(default)]
+        #[serde(with = "ts_seconds_option")]
+        expiration: Option<DateTime<Utc>>,
+        expiration_ttl: Option<u32>,
+    },
+}
+
+impl StagedChange {
+    // The pair as it will be after the commit, `None` if it will not exist.
+    fn apply(&self, key: &str, remote_pair: Option<&KvPair>) -> Option<KvPair> {
+        match self {
+            StagedChange::Create {
+                value,
+                metadata,
+                expiration,
+                expiration_ttl,
+            }
+            | StagedChange::Update {
+                value,
+                metadata,
+                expiration,
+                expiration_ttl,
+            } => Some(KvPair {
+                key: key.to_string(),
+                value: value.clone(),
+                metadata: metadata.clone(),
+                expiration: resolve_expiration(*expiration, *expiration_ttl),
+            }),
+            StagedChange::Delete => None,
+            StagedChange::Expiration {
+                expiration,
+                expiration_ttl,
+            } => remote_pair.map(|remote_pair| KvPair {
+                expiration: resolve_expiration(*expiration, *expiration_ttl),
+                ..remote_pair.clone()
+            }),
+        }
+    }
+
+    fn conflict(&self, remote_pair: Option<&KvPair>) -> Option<String> {
+        match (self, remote_pair) {
+            (StagedChange::Create { .. }, Some(_)) => Some("The key already exists".to_string()),
+            (StagedChange::Expiration { .. }, None) => Some("The key does not exist".to_string()),
+            _ => None,
+        }
+    }
+
+    // Only the changes which can conflict depend on the remote pair.
+    fn needs_remote_pair(&self) -> bool {
+        matches!(
+            self,
+            StagedChange::Create { .. } | StagedChange::Expiration { .. }
+        )
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct StagedKeyChange {
+    pub key: String,
+    pub change: StagedChange,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Changeset {
+    pub account_id: String,
+    pub namespace_id: String,
+    pub changes: Vec<StagedKeyChange>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ChangesetInput {
+    pub account_id: String,
+    pub namespace_id: String,
+
+    // Staged deletes of a protected namespace are only committed if this matches its title.
+    #[serde(default)]
+    pub confirmation: Option<String>,
+
+    #[serde(default)]
+    pub acknowledge_production: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct KvChangesStageInput {
+    pub account_id: String,
+    pub namespace_id: String,
+    pub changes: Vec<StagedKeyChange>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct KvChangesUnstageInput {
+    pub account_id: String,
+    pub namespace_id: String,
+    pub keys: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct StagedChangeDiff {
+    pub key: String,
+    pub change: StagedChange,
+    pub remote: Option<KvPair>,
+    pub staged: Option<KvPair>,
+    pub conflict: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub enum KvChangeStatus {
+    Committed,
+    Failed,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct KvChangeOutcome {
+    pub key: String,
+    pub status: KvChangeStatus,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ChangesetCommitResult {
+    pub outcomes: Vec<KvChangeOutcome>,
+    pub remaining: Changeset,
+}
+
+// Collects changes per namespace until they are committed, like the index of git.
+#[derive(Default)]
+pub struct StagingArea {
+    changesets: Mutex<HashMap<(String, String), BTreeMap<String, StagedChange>>>,
+}
+
+impl StagingArea {
+    pub fn stage(&self, input: KvChangesStageInput) -> Result<Changeset, StagingError> {
+        let mut changesets = self.lock_changesets();
+        let changeset_key = (input.account_id.clone(), input.namespace_id.clone());
+        let mut changes = changesets.get(&changeset_key).cloned().unwrap_or_default();
+
+        for StagedKeyChange { key, change } in input.changes {
+            let change = match (changes.remove(&key), change) {
+                (
+                    Some(StagedChange::Create {
+                        value, metadata, ..
+                    }),
+                    StagedChange::Expiration {
+                        expiration,
+                        expiration_ttl,
+                    },
+                ) => StagedChange::Create {
+                    value,
+                    metadata,
+                    expiration,
+                    expiration_ttl,
+                },
+                (
+                    Some(StagedChange::Update {
+                        value, metadata, ..
+                    }),
+                    StagedChange::Expiration {
+                        expiration,
+                        expiration_ttl,
+                    },
+                ) => StagedChange::Update {
+                    value,
+                    metadata,
+                    expiration,
+                    expiration_ttl,
+                },
+                (Some(StagedChange::Delete), StagedChange::Expiration { .. }) => {
+                    return Err(StagingError::KeyStagedForDeletion(key));
+                }
+                // Deleting and creating a key again overwrites it.
+                (
+                    Some(StagedChange::Delete),
+                    StagedChange::Create {
+                        value,
+                        metadata,
+                        expiration,
+                        expiration_ttl,
+                    },
+                ) => StagedChange::Update {
+                    value,
+                    metadata,
+                    expiration,
+                    expiration_ttl,
+                },
+                (_, change) => change,
+            };
+            changes.insert(key, change);
+        }
+
+        changesets.insert(changeset_key, changes);
+        Ok(to_changeset(
+            &changesets,
+            &input.account_id,
+            &input.namespace_id,
+        ))
+    }
+
+    pub fn unstage(&self, input: KvChangesUnstageInput) -> Changeset {
+        let mut changesets = self.lock_changesets();
+        if let Some(changes) =
+            changesets.get_mut(&(input.account_id.clone(), input.namespace_id.clone()))
+        {
+            for key in &input.keys {
+                changes.remove(key);
+            }
+        }
+
+        to_changeset(&changesets, &input.account_id, &input.namespace_id)
+    }
+
+    pub fn changeset(&self, account_id: &str, namespace_id: &str) -> Changeset {
+        to_changeset(&self.lock_changesets(), account_id, namespace_id)
+    }
+
+    pub fn discard(&self, account_id: &str, namespace_id: &str) {
+        self.lock_changesets()
+            .remove(&(account_id.to_string(), namespace_id.to_string()));
+    }
+
+    // Keys which were staged again while the commit was running stay staged.
+    pub fn remove_committed(
+        &self,
+        committed: &Changeset,
+        outcomes: &[KvChangeOutcome],
+    ) -> Changeset {
+        let mut changesets = self.lock_changesets();
+        let changeset_key = (committed.account_id.clone(), committed.namespace_id.clone());
+        if let Some(changes) = changesets.get_mut(&changeset_key) {
+            for outcome in outcomes {
+                let committed_change = committed
+                    .changes
+                    .iter()
+                    .find(|staged_change| staged_change.key == outcome.key);
+                if outcome.status == KvChangeStatus::Committed
+                    && committed_change.is_some_and(|committed| {
+                        changes.get(&outcome.key) == Some(&committed.change)
+                    })
+                {
+                    changes.remove(&outcome.key);
+                }
+            }
+        }
+
+        to_changeset(&changesets, &committed.account_id, &committed.namespace_id)
+    }
+
+    fn lock_changesets(
+        &self,
+    ) -> MutexGuard<'_, HashMap<(String, String), BTreeMap<String, StagedChange>>> {
+        self.changesets
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+pub async fn diff_changeset(
+    kv: &KvClient,
+    changeset: &Changeset,
+) -> Result<Vec<StagedChangeDiff>, KvError> {
+    let keys: Vec<&String> = changeset.changes.iter().map(|change| &change.key).collect();
+    let remote_pairs = get_remote_pairs(kv, changeset, keys).await?;
+
+    Ok(changeset
+        .changes
+        .iter()
+        .map(|StagedKeyChange { key, change }| {
+            let remote_pair = remote_pairs.get(key);
+            StagedChangeDiff {
+                key: key.clone(),
+                change: change.clone(),
+                remote: remote_pair.cloned(),
+                staged: change.apply(key, remote_pair),
+                conflict: change.conflict(remote_pair),
+            }
+        })
+        .collect())
+}
+
+// Writes and deletes are sent in as few bulk requests as possible. Changes which conflict with
+// the remote values are not sent and reported as failed, like every key of a failed request.
+pub async fn commit_changeset(
+    kv: &KvClient,
+    changeset: &Changeset,
+) -> Result<Vec<KvChangeOutcome>, KvError> {
+    let keys: Vec<&String> = changeset
+        .changes
+        .iter()
+        .filter(|change| change.change.needs_remote_pair())
+        .map(|change| &change.key)
+        .collect();
+    let remote_pairs = get_remote_pairs(kv, changeset, keys).await?;
+
+    let mut outcomes = vec![];
+    let mut writes = vec![];
+    let mut deletes = vec![];
+    for StagedKeyChange { key, change } in &changeset.changes {
+        let remote_pair = remote_pairs.get(key);
+        if let Some(conflict) = change.conflict(remote_pair) {
+            outcomes.push(failed(key, conflict));
+            continue;
+        }
+
+        match change {
+            StagedChange::Create {
+                value,
+                metadata,
+                expiration,
+                expiration_ttl,
+            }
+            | StagedChange::Update {
+                value,
+                metadata,
+                expiration,
+                expiration_ttl,
+            } => writes.push(KvPairBulkWriteInput {
+                key: key.clone(),
+                value: KvPairValue::Binary(value.clone()),
+                expiration: *expiration,
+                expiration_ttl: *expiration_ttl,
+                metadata: metadata.clone(),
+                base64: None,
+            }),
+            StagedChange::Expiration {
+                expiration,
+                expiration_ttl,
+            } => {
+                if let Some(remote_pair) = remote_pair {
+                    writes.push(KvPairBulkWriteInput {
+                        key: key.clone(),
+                        value: KvPairValue::Binary(remote_pair.value.clone()),
+                        expiration: *expiration,
+                        expiration_ttl: *expiration_ttl,
+                        metadata: remote_pair.metadata.clone(),
+                        base64: None,
+                    });
+                }
+            }
+            StagedChange::Delete => deletes.push(key.clone()),
+        }
+    }
+
+    let (chunks, too_large) = bulk_write_chunks(writes);
+    outcomes.extend(too_large.iter().map(|pair| {
+        failed(
+            &pair.key,
+            "The value is too large for a bulk write".to_string(),
+        )
+    }));
+    for pairs in chunks {
+        let keys: Vec<String> = pairs.iter().map(|pair| pair.key.clone()).collect();
+        let result = kv
+            .write_kv_pairs(KvPairsWriteInput {
+                account_id: changeset.account_id.clone(),
+                namespace_id: changeset.namespace_id.clone(),
+                pairs,
+            })
+            .await
+            .map(|result| result.unsuccessful_keys);
+        outcomes.extend(bulk_outcomes(
+            keys.iter().collect(),
+            result,
+            "The key could not be written",
+        ));
+    }
+
+    for keys in deletes.chunks(BULK_WRITE_KEY_LIMIT) {
+        let result = kv
+            .delete_kv_pairs(KvPairsDeleteInput {
+                account_id: changeset.account_id.clone(),
+                namespace_id: changeset.namespace_id.clone(),
+                keys: keys.to_vec(),
+            })
+            .await
+            .map(|result| result.unsuccessful_keys);
+        outcomes.extend(bulk_outcomes(
+            keys.iter().collect(),
+            result,
+            "The key could not be deleted",
+        ));
+    }
+
+    Ok(outcomes)
+}
+
+async fn get_remote_pairs(
+    kv: &KvClient,
+    changeset: &Changeset,
+    keys: Vec<&String>,
+) -> Result<HashMap<String, KvPair>, KvError> {
+    let input = KvPairsGetInput {
+        account_id: changeset.account_id.clone(),
+        namespace_id: changeset.namespace_id.clone(),
+        keys: keys.into_iter().cloned().collect(),
+    };
+    Ok(kv
+        .find_kv_pairs(input)
+        .await?
+        .into_iter()
+        .map(|pair| (pair.key.clone(), pair))
+        .collect())
+}
+
+fn bulk_outcomes(
+    keys: Vec<&String>,
+    result: Result<Vec<String>, KvError>,
+    unsuccessful_message: &str,
+) -> Vec<KvChangeOutcome> {
+    match result {
+        Ok(unsuccessful_keys) => keys
+            .into_iter()
+            .map(|key| {
+                if unsuccessful_keys.contains(key) {
+                    failed(key, unsuccessful_message.to_string())
+                } else {
+                    KvChangeOutcome {
+                        key: key.clone(),
+                        status: KvChangeStatus::Committed,
+                        error: None,
+                    }
+                }
+            })
+            .collect(),
+        Err(error) => keys
+            .into_iter()
+            .map(|key| failed(key, error.to_string()))
+            .collect(),
+    }
+}
+
+fn failed(key: &str, error: String) -> KvChangeOutcome {
+    KvChangeOutcome {
+        key: key.to_string(),
+        status: KvChangeStatus::Failed,
+        error: Some(error),
+    }
+}
+
+fn resolve_expiration(
+    expiration: Option<DateTime<Utc>>,
+    expiration_ttl: Option<u32>,
+) -> Option<DateTime<Utc>> {
+    expiration.or_else(|| {
+        expiration_ttl.map(|expiration_ttl| Utc::now() + TimeDelta::seconds(expiration_ttl.into()))
+    })
+}
+
+fn to_changeset(
+    changesets: &HashMap<(String, String), BTreeMap<String, StagedChange>>,
+    account_id: &str,
+    namespace_id: &str,
+) -> Changeset {
+    let changes = changesets
+        .get(&(account_id.to_string(), namespace_id.to_string()))
+        .map(|changes| {
+            changes
+                .iter()
+                .map(|(key, change)| StagedKeyChange {
+                    key: key.clone(),
+                    change: change.clone(),
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+
+    Changeset {
+        account_id: account_id.to_string(),
+        namespace_id: namespace_id.to_string(),
+        changes,
+    }
+}
+
+#[derive(Debug)]
+pub enum StagingError {
+    KeyStagedForDeletion(String),
+}
+
+impl Error for StagingError {}
+
+impl Display for StagingError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match &self {
+            StagingError::KeyStagedForDeletion(key) => {
+                write!(f, "The key {key} is staged for deletion")
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{
+        Changeset, KvChangeStatus, KvChangesStageInput, StagedChange, StagedKeyChange, StagingArea,
+        StagingError, commit_changeset, diff_changeset,
+    };
+    use crate::cloudflare::common::ApiResponse;
+    use crate::cloudflare::kv::{KvError, KvPairsDeleteResult, KvPairsWriteResult};
+    use crate::test_support::create_kv_client;
+    use serde_json::{Value, json};
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    const NAMESPACE_PATH: &str =
+        "/client/v4/accounts/account_id/storage/kv/namespaces/namespace_id";
+
+    #[test]
+    fn should_merge_changes_of_the_same_key() -> Result<(), StagingError> {
+        let staging_area = StagingArea::default();
+        stage(&staging_area, "key1", update("value1"))?;
+        stage(&staging_area, "key2", StagedChange::Delete)?;
+        stage(
+            &staging_area,
+            "key1",
+            StagedChange::Expiration {
+                expiration: None,
+                expiration_ttl: Some(60),
+            },
+        )?;
+        let changeset = stage(&staging_area, "key2", create("value2"))?;
+
+        assert_eq!(
+            changeset.changes,
+            vec![
+                StagedKeyChange {
+                    key: "key1".to_string(),
+                    change: StagedChange::Update {
+                        value: b"value1".to_vec(),
+                        metadata: None,
+                        expiration: None,
+                        expiration_ttl: Some(60),
+                    },
+                },
+                StagedKeyChange {
+                    key: "key2".to_string(),
+                    change: update("value2"),
+                },
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn should_reject_an_expiration_of_a_key_staged_for_deletion() -> Result<(), StagingError> {
+        let staging_area = StagingArea::default();
+        stage(&staging_area, "key1", StagedChange::Delete)?;
+
+        let result = stage(
+            &staging_area,
+            "key1",
+            StagedChange::Expiration {
+                expiration: None,
+                expiration_ttl: Some(60),
+            },
+        );
+
+        assert!(matches!(result, Err(StagingError::KeyStagedForDeletion(_))));
+        assert_eq!(
+            staging_area.changeset("account_id", "namespace_id").changes[0].change,
+            StagedChange::Delete
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn should_diff_against_the_remote_values() -> Result<(), KvError> {
+        let mock_server = MockServer::start().await;
+        mount_remote_values(
+            &mock_server,
+            json!({"existing": {"value": "remote value"}, "missing": null}),
+        )
+        .await;
+
+        let staging_area = StagingArea::default();
+        stage(&staging_area, "existing", create("new value")).unwrap();
+        stage(&staging_area, "missing", update("new value")).unwrap();
+        let changeset = staging_area.changeset("account_id", "namespace_id");
+
+        let diff = diff_changeset(&create_kv_client(&mock_server), &changeset).await?;
+
+        assert_eq!(diff[0].remote.as_ref().unwrap().value, b"remote value");
+        assert_eq!(diff[0].staged.as_ref().unwrap().value, b"new value");
+        assert_eq!(diff[0].conflict, Some("The key already exists".to_string()));
+        assert_eq!(diff[1].remote, None);
+        assert_eq!(diff[1].conflict, None);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn should_commit_in_bulk_and_keep_the_failed_keys_staged() -> Result<(), KvError> {
+        let mock_server = MockServer::start().await;
+        mount_remote_values(
+            &mock_server,
+            json!({"taken": {"value": "remote value"}, "new": null}),
+        )
+        .await;
+        Mock::given(method("PUT"))
+            .and(path(format!("{NAMESPACE_PATH}/bulk")))
+            .respond_with(ResponseTemplate::new(200).set_body_json(ApiResponse {
+                result: KvPairsWriteResult {
+                    successful_key_count: 1,
+                    unsuccessful_keys: vec!["updated".to_string()],
+                },
+            }))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path(format!("{NAMESPACE_PATH}/bulk/delete")))
+            .respond_with(ResponseTemplate::new(200).set_body_json(ApiResponse {
+                result: KvPairsDeleteResult {
+                    successful_key_count: 1,
+                    unsuccessful_keys: vec![],
+                },
+            }))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let staging_area = StagingArea::default();
+        stage(&staging_area, "new", create("value")).unwrap();
+        stage(&staging_area, "taken", create("value")).unwrap();
+        stage(&staging_area, "updated", update("value")).unwrap();
+        stage(&staging_area, "deleted", StagedChange::Delete).unwrap();
+        let changeset = staging_area.changeset("account_id", "namespace_id");
+
+        let outcomes = commit_changeset(&create_kv_client(&mock_server), &changeset).await?;
+        let status = |key: &str| {
+            outcomes
+                .iter()
+                .find(|outcome| outcome.key == key)
+                .map(|outcome| outcome.status.clone())
+        };
+        assert_eq!(status("new"), Some(KvChangeStatus::Committed));
+        assert_eq!(status("taken"), Some(KvChangeStatus::Failed));
+        assert_eq!(status("updated"), Some(KvChangeStatus::Failed));
+        assert_eq!(status("deleted"), Some(KvChangeStatus::Committed));
+
+        let remaining = staging_area.remove_committed(&changeset, &outcomes);
+        let remaining_keys: Vec<&str> = remaining
+            .changes
+            .iter()
+            .map(|change| change.key.as_str())
+            .collect();
+        assert_eq!(remaining_keys, vec!["taken", "updated"]);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn should_fail_the_keys_which_exceed_the_bulk_write_size() -> Result<(), KvError> {
+        let mock_server = MockServer::start().await;
+        mount_remote_values(&mock_server, json!({"small": null, "large": null})).await;
+        Mock::given(method("PUT"))
+            .and(path(format!("{NAMESPACE_PATH}/bulk")))
+            .respond_with(ResponseTemplate::new(200).set_body_json(ApiResponse {
+                result: KvPairsWriteResult {
+                    successful_key_count: 1,
+                    unsuccessful_keys: vec![],
+                },
+            }))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let staging_area = StagingArea::default();
+        stage(&staging_area, "small", update("value")).unwrap();
+        stage(&staging_area, "large", update(&"a".repeat(100_000_000))).unwrap();
+        let changeset = staging_area.changeset("account_id", "namespace_id");
+
+        let outcomes = commit_changeset(&create_kv_client(&mock_server), &changeset).await?;
+
+        let outcome = |key: &str| {
+            outcomes
+                .iter()
+                .find(|outcome| outcome.key == key)
+                .map(|outcome| (outcome.status.clone(), outcome.error.clone()))
+        };
+        assert_eq!(outcome("small"), Some((KvChangeStatus::Committed, None)));
+        assert_eq!(
+            outcome("large"),
+            Some((
+                KvChangeStatus::Failed,
+                Some("The value is too large for a bulk write".to_string())
+            ))
+        );
+        Ok(())
+    }
+
+    fn stage(
+        staging_area: &StagingArea,
+        key: &str,
+        change: StagedChange,
+    ) -> Result<Changeset, StagingError> {
+        staging_area.stage(KvChangesStageInput {
+            account_id: "account_id".to_string(),
+            namespace_id: "namespace_id".to_string(),
+            changes: vec![StagedKeyChange {
+                key: key.to_string(),
+                change,
+            }],
+        })
+    }
+
+    fn create(value: &str) -> StagedChange {
+        StagedChange::Create {
+            value: value.as_bytes().to_vec(),
+            metadata: None,
+            expiration: None,
+            expiration_ttl: None,
+        }
+    }
+
+    fn update(value: &str) -> StagedChange {
+        StagedChange::Update {
+            value: value.as_bytes().to_vec(),
+            metadata: None,
+            expiration: None,
+            expiration_ttl: None,
+        }
+    }
+
+    async fn mount_remote_values(mock_server: &MockServer, values: Value) {
+        Mock::given(method("POST"))
+            .and(path(format!("{NAMESPACE_PATH}/bulk/get")))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "success": true,
+                "errors": [],
+                "messages": [],
+                "result": {"values": values},
+            })))
+            .mount(mock_server)
+            .await;
+    }
+}
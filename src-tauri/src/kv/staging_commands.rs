@@ -0,0 +1,290 @@
+use crate::audit::audit_log::{AuditEntry, AuditLog, AuditOperation, AuditOutcome, AuditedKey};
+use crate::cache::kv_cache::{KvCache, namespace_cache_prefix};
+use crate::cloudflare::kv::{KvClient, KvError, KvPairsGetInput};
+use crate::environment::environment_commands::acknowledge_production;
+use crate::environment::environment_labels::NamespaceEnvironments;
+use crate::history::history_commands::record_previous_versions;
+use crate::history::value_history::{KvVersionCause, ValueHistory};
+use crate::kv::kv_commands::KvCommandError;
+use crate::kv::staging_area::{
+    Changeset, ChangesetCommitResult, ChangesetInput, KvChangeOutcome, KvChangeStatus,
+    KvChangesStageInput, KvChangesUnstageInput, StagedChange, StagedChangeDiff, StagedKeyChange,
+    StagingArea, commit_changeset, diff_changeset,
+};
+use crate::protection::protected_namespaces::ProtectedNamespaces;
+use crate::protection::protection_commands::confirm_deletion;
+use crate::session::session_registry::SessionRegistry;
+use log::warn;
+use std::sync::Arc;
+use tauri::State;
+
+#[tauri::command]
+pub fn stage_kv_changes(
+    staging_area: State<'_, Arc<StagingArea>>,
+    input: KvChangesStageInput,
+) -> Result<Changeset, KvCommandError> {
+    Ok(staging_area.stage(input)?)
+}
+
+#[tauri::command]
+pub fn unstage_kv_changes(
+    staging_area: State<'_, Arc<StagingArea>>,
+    input: KvChangesUnstageInput,
+) -> Changeset {
+    staging_area.unstage(input)
+}
+
+#[tauri::command]
+pub fn get_staged_changes(
+    staging_area: State<'_, Arc<StagingArea>>,
+    input: ChangesetInput,
+) -> Changeset {
+    staging_area.changeset(&input.account_id, &input.namespace_id)
+}
+
+#[tauri::command]
+pub fn discard_staged_changes(
+    staging_area: State<'_, Arc<StagingArea>>,
+    input: ChangesetInput,
+) -> Changeset {
+    staging_area.discard(&input.account_id, &input.namespace_id);
+    staging_area.changeset(&input.account_id, &input.namespace_id)
+}
+
+#[tauri::command]
+pub async fn diff_staged_changes(
+    sessions: State<'_, Arc<SessionRegistry>>,
+    staging_area: State<'_, Arc<StagingArea>>,
+    session_id: String,
+    input: ChangesetInput,
+) -> Result<Vec<StagedChangeDiff>, KvCommandError> {
+    let cloudflare_client = sessions.get(&session_id)?;
+    let changeset = staging_area.changeset(&input.account_id, &input.namespace_id);
+    Ok(diff_changeset(&cloudflare_client.kv, &changeset).await?)
+}
+
+// Failed keys stay staged, so they can be fixed and committed again.
+#[tauri::command]
+pub async fn commit_staged_changes(
+    sessions: State<'_, Arc<SessionRegistry>>,
+    staging_area: State<'_, Arc<StagingArea>>,
+    cache: State<'_, Arc<KvCache>>,
+    history: State<'_, Arc<ValueHistory>>,
+    audit_log: State<'_, Arc<AuditLog>>,
+    protected_namespaces: State<'_, Arc<ProtectedNamespaces>>,
+    environments: State<'_, Arc<NamespaceEnvironments>>,
+    session_id: String,
+    input: ChangesetInput,
+) -> Result<ChangesetCommitResult, KvCommandError> {
+    let cloudflare_client = sessions.get_writable(&session_id)?;
+    let changeset = staging_area.changeset(&input.account_id, &input.namespace_id);
+    let mut target = AuditEntry::new(
+        sessions.profile(&session_id),
+        AuditOperation::KeyWrite,
+        &input.account_id,
+        Some(&input.namespace_id),
+    );
+    acknowledge_production(
+        &environments,
+        &cloudflare_client.kv,
+        &mut target,
+        input.acknowledge_production,
+    )
+    .await?;
+    if changeset
+        .changes
+        .iter()
+        .any(|staged| staged.change == StagedChange::Delete)
+    {
+        confirm_deletion(
+            &protected_namespaces,
+            &cloudflare_client.kv,
+            &input.account_id,
+            &input.namespace_id,
+            input.confirmation.as_deref(),
+        )
+        .await?;
+    }
+    record_staged_versions(&history, &cloudflare_client.kv, &changeset).await?;
+    let result = commit_changeset(&cloudflare_client.kv, &changeset).await;
+
+    let cache_prefix = namespace_cache_prefix(&input.account_id, &input.namespace_id);
+    if let Err(error) = cache.invalidate(&cache_prefix) {
+        warn!("The cache entries {cache_prefix} could not be invalidated: {error}");
+    }
+
+    for audit_entry in audit_entries(&target, &changeset, &result) {
+        audit_log.record(audit_entry);
+    }
+
+    let outcomes = result?;
+    Ok(ChangesetCommitResult {
+        remaining: staging_area.remove_committed(&changeset, &outcomes),
+        outcomes,
+    })
+}
+
+// Staged overwrites and deletes keep the previous values in the history, like direct ones.
+async fn record_staged_versions(
+    history: &ValueHistory,
+    kv: &KvClient,
+    changeset: &Changeset,
+) -> Result<(), KvCommandError> {
+    let (deleted, written): (Vec<&StagedKeyChange>, Vec<&StagedKeyChange>) = changeset
+        .changes
+        .iter()
+        .partition(|staged| staged.change == StagedChange::Delete);
+    for (changes, cause) in [
+        (written, KvVersionCause::Overwritten),
+        (deleted, KvVersionCause::Deleted),
+    ] {
+        let input = KvPairsGetInput {
+            account_id: changeset.account_id.clone(),
+            namespace_id: changeset.namespace_id.clone(),
+            keys: changes.iter().map(|staged| staged.key.clone()).collect(),
+        };
+        record_previous_versions(history, kv, input, cause).await?;
+    }
+
+    Ok(())
+}
+
+// One entry per kind of change, each with the outcomes of its keys. They share the profile,
+// environment and namespace of the target.
+fn audit_entries(
+    target: &AuditEntry,
+    changeset: &Changeset,
+    result: &Result<Vec<KvChangeOutcome>, KvError>,
+) -> Vec<AuditEntry> {
+    [
+        AuditOperation::KeyCreate,
+        AuditOperation::KeyWrite,
+        AuditOperation::KeyDelete,
+    ]
+    .into_iter()
+    .filter_map(|operation| {
+        let keys: Vec<AuditedKey> = changeset
+            .changes
+            .iter()
+            .filter_map(|staged| match (&operation, &staged.change) {
+                (AuditOperation::KeyCreate, StagedChange::Create { value, .. })
+                | (AuditOperation::KeyWrite, StagedChange::Update { value, .. }) => {
+                    Some(AuditedKey::new(&staged.key, Some(value)))
+                }
+                (AuditOperation::KeyWrite, StagedChange::Expiration { .. })
+                | (AuditOperation::KeyDelete, StagedChange::Delete) => {
+                    Some(AuditedKey::new(&staged.key, None))
+                }
+                _ => None,
+            })
+            .collect();
+        if keys.is_empty() {
+            return None;
+        }
+
+        let mut audit_entry = AuditEntry::new(
+            target.profile.clone(),
+            operation,
+            &changeset.account_id,
+            Some(&changeset.namespace_id),
+        );
+        audit_entry.namespace_title = target.namespace_title.clone();
+        audit_entry.environment = target.environment;
+        audit_entry.outcome = AuditOutcome::of(result, |outcomes| {
+            let key_outcomes: Vec<&KvChangeOutcome> = outcomes
+                .iter()
+                .filter(|outcome| keys.iter().any(|audited| audited.key == outcome.key))
+                .collect();
+            AuditOutcome::Completed {
+                successful_key_count: key_outcomes
+                    .iter()
+                    .filter(|outcome| outcome.status == KvChangeStatus::Committed)
+                    .count() as u32,
+                unsuccessful_keys: key_outcomes
+                    .iter()
+                    .filter(|outcome| outcome.status == KvChangeStatus::Failed)
+                    .map(|outcome| outcome.key.clone())
+                    .collect(),
+            }
+        });
+        audit_entry.keys = keys;
+        Some(audit_entry)
+    })
+    .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::record_staged_versions;
+    use crate::cloudflare::common::ApiResponse;
+    use crate::cloudflare::kv::KvPairsWriteResult;
+    use crate::history::value_history::{KvVersionCause, ValueHistory, ValueHistoryConfig};
+    use crate::kv::kv_commands::KvCommandError;
+    use crate::kv::staging_area::{
+        KvChangesStageInput, StagedChange, StagedKeyChange, StagingArea, commit_changeset,
+    };
+    use crate::test_support::{create_kv_client, create_temp_dir};
+    use serde_json::json;
+    use std::fs;
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    const NAMESPACE_PATH: &str =
+        "/client/v4/accounts/account_id/storage/kv/namespaces/namespace_id";
+
+    #[tokio::test]
+    async fn should_record_the_previous_value_of_a_staged_overwrite() -> Result<(), KvCommandError>
+    {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path(format!("{NAMESPACE_PATH}/bulk/get")))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "success": true,
+                "errors": [],
+                "messages": [],
+                "result": {"values": {"key1": {"value": "remote value"}}},
+            })))
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("PUT"))
+            .and(path(format!("{NAMESPACE_PATH}/bulk")))
+            .respond_with(ResponseTemplate::new(200).set_body_json(ApiResponse {
+                result: KvPairsWriteResult {
+                    successful_key_count: 1,
+                    unsuccessful_keys: vec![],
+                },
+            }))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+        let kv = create_kv_client(&mock_server);
+        let directory = create_temp_dir();
+        let history =
+            ValueHistory::new(directory.join("kv-history"), ValueHistoryConfig::default());
+
+        let staging_area = StagingArea::default();
+        let changeset = staging_area.stage(KvChangesStageInput {
+            account_id: "account_id".to_string(),
+            namespace_id: "namespace_id".to_string(),
+            changes: vec![StagedKeyChange {
+                key: "key1".to_string(),
+                change: StagedChange::Update {
+                    value: b"staged value".to_vec(),
+                    metadata: None,
+                    expiration: None,
+                    expiration_ttl: None,
+                },
+            }],
+        })?;
+        record_staged_versions(&history, &kv, &changeset).await?;
+        commit_changeset(&kv, &changeset).await?;
+
+        let versions = history.versions("account_id", "namespace_id", "key1")?;
+        assert_eq!(versions.len(), 1);
+        assert_eq!(versions[0].cause, KvVersionCause::Overwritten);
+        assert_eq!(versions[0].pair.value, b"remote value");
+
+        fs::remove_dir_all(directory).unwrap();
+        Ok(())
+    }
+}
@@ -1,34 +1,241 @@
-use crate::authentication::authentication_commands::verify_account_and_credentials;
+use crate::audit::audit_commands::{export_audit_log, list_audit_entries};
+use crate::audit::audit_log::{AUDIT_LOG_FILE_NAME, AuditLog};
+use crate::authentication::authentication_commands::{list_accessible_accounts, login, logout};
+use crate::bulk::bulk_commands::{clone_namespace, delete_namespaces, rename_namespaces};
+use crate::bulk::namespace_backups::{NAMESPACE_BACKUPS_DIRECTORY_NAME, NamespaceBackups};
+use crate::cache::cache_commands::{clear_kv_cache, get_kv_cache_status, update_kv_cache_config};
+use crate::cache::kv_cache::{KV_CACHE_DIRECTORY_NAME, KvCache, KvCacheConfig};
+use crate::cloudflare::common::{RateLimiter, TrafficRecorder};
+use crate::connection::connection_commands::{
+    CONNECTION_SETTINGS_FILE_NAME, get_connection_settings, load_connection_settings,
+    update_connection_settings,
+};
+use crate::credentials::credentials_commands::discover_credentials;
+use crate::credentials::credentials_registry::CredentialsRegistry;
+use crate::cross_account::cross_account_commands::{
+    find_key_across_profiles, list_namespaces_across_profiles, search_namespaces_across_profiles,
+};
+use crate::environment::environment_commands::{
+    list_namespace_environments, set_namespace_environment,
+};
+use crate::environment::environment_labels::{
+    NAMESPACE_ENVIRONMENTS_FILE_NAME, NamespaceEnvironments,
+};
+use crate::history::history_commands::{
+    diff_kv_versions, get_kv_history_config, list_kv_history, list_kv_versions, restore_kv_version,
+    update_kv_history_config,
+};
+use crate::history::value_history::{
+    VALUE_HISTORY_DIRECTORY_NAME, ValueHistory, ValueHistoryConfig,
+};
 use crate::kv::kv_commands::{
     create_kv_pair, create_namespace, delete_kv_pairs, delete_namespace, get_kv_pair, get_kv_pairs,
-    get_namespace, list_kv_keys, list_namespaces, update_namespace, write_kv_pair, write_kv_pairs,
+    get_namespace, list_all_namespaces, list_key_tree, list_kv_keys, list_namespaces,
+    update_namespace, write_kv_pair, write_kv_pairs,
+};
+use crate::kv::staging_area::StagingArea;
+use crate::kv::staging_commands::{
+    commit_staged_changes, diff_staged_changes, discard_staged_changes, get_staged_changes,
+    stage_kv_changes, unstage_kv_changes,
+};
+use crate::profile::profile_commands::{
+    create_profile, delete_profile, get_profile_vault_status, list_profiles, lock_profile_vault,
+    select_profile, unlock_profile_vault, update_profile,
+};
+use crate::profile::profile_vault::{
+    PROFILE_VAULT_FILE_NAME, PROFILE_VAULT_IDLE_TIMEOUT, ProfileVault,
+};
+use crate::protection::protected_namespaces::{
+    PROTECTED_NAMESPACES_FILE_NAME, ProtectedNamespaces,
+};
+use crate::protection::protection_commands::{
+    list_protected_namespaces, protect_namespace, unprotect_namespace,
+};
+use crate::rate_limit::rate_limit_commands::{get_rate_limit_status, update_rate_limit_config};
+use crate::session::session_registry::{SESSION_IDLE_TIMEOUT, SessionRegistry};
+use crate::statistics::statistics_commands::get_namespace_statistics;
+use crate::token::token_commands::{
+    create_token_from_template, delete_token, list_tokens, roll_token,
 };
+use crate::traffic::traffic_commands::{
+    clear_traffic_entries, export_traffic_har, get_traffic_recorder_config, list_traffic_entries,
+    update_traffic_recorder_config,
+};
+use log::error;
+use std::sync::Arc;
+use tauri::Manager;
 
+mod audit;
 mod authentication;
+mod bulk;
+mod cache;
 mod cloudflare;
+mod connection;
+mod credentials;
+mod cross_account;
+mod environment;
+mod history;
 mod kv;
+mod profile;
+mod protection;
+mod rate_limit;
+mod session;
+mod statistics;
+mod token;
+mod traffic;
+
+#[cfg(test)]
+mod test_support;
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
+    let rate_limiter = Arc::new(RateLimiter::default());
+    let traffic_recorder = Arc::new(TrafficRecorder::default());
+    let sessions = Arc::new(SessionRegistry::new(
+        rate_limiter.clone(),
+        traffic_recorder.clone(),
+        SESSION_IDLE_TIMEOUT,
+    ));
+
     tauri::Builder::default()
         .plugin(tauri_plugin_opener::init())
         .plugin(tauri_plugin_log::Builder::new().build())
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_fs::init())
+        .manage(rate_limiter.clone())
+        .manage(traffic_recorder.clone())
+        .manage(Arc::new(StagingArea::default()))
+        .manage(Arc::new(CredentialsRegistry::default()))
+        .manage(sessions.clone())
+        .setup(|app| {
+            // Invalid saved settings must not prevent the app from starting, so they are only logged.
+            let settings_path = app
+                .path()
+                .app_config_dir()?
+                .join(CONNECTION_SETTINGS_FILE_NAME);
+            if let Err(error) = load_connection_settings(&settings_path)
+                .and_then(|settings| sessions.apply_connection_settings(settings))
+            {
+                error!("The connection settings could not be applied: {error}");
+            }
+
+            let vault_path = app.path().app_data_dir()?.join(PROFILE_VAULT_FILE_NAME);
+            let profile_vault = Arc::new(ProfileVault::new(vault_path, PROFILE_VAULT_IDLE_TIMEOUT));
+            app.manage(profile_vault.clone());
+
+            let cache_directory = app.path().app_data_dir()?.join(KV_CACHE_DIRECTORY_NAME);
+            app.manage(Arc::new(KvCache::new(
+                cache_directory,
+                KvCacheConfig::default(),
+            )));
+
+            let history_directory = app
+                .path()
+                .app_data_dir()?
+                .join(VALUE_HISTORY_DIRECTORY_NAME);
+            app.manage(Arc::new(ValueHistory::new(
+                history_directory,
+                ValueHistoryConfig::default(),
+            )));
+
+            let protected_namespaces_path = app
+                .path()
+                .app_config_dir()?
+                .join(PROTECTED_NAMESPACES_FILE_NAME);
+            app.manage(Arc::new(ProtectedNamespaces::new(
+                protected_namespaces_path,
+            )));
+
+            let namespace_environments_path = app
+                .path()
+                .app_config_dir()?
+                .join(NAMESPACE_ENVIRONMENTS_FILE_NAME);
+            app.manage(Arc::new(NamespaceEnvironments::new(
+                namespace_environments_path,
+            )));
+
+            let backups_directory = app
+                .path()
+                .app_data_dir()?
+                .join(NAMESPACE_BACKUPS_DIRECTORY_NAME);
+            app.manage(Arc::new(NamespaceBackups::new(backups_directory)));
+
+            let audit_log_path = app.path().app_data_dir()?.join(AUDIT_LOG_FILE_NAME);
+            app.manage(Arc::new(AuditLog::new(audit_log_path)));
+
+            tauri::async_runtime::spawn(sessions.remove_expired_sessions_periodically());
+            tauri::async_runtime::spawn(profile_vault.lock_when_idle_periodically());
+            Ok(())
+        })
         .invoke_handler(tauri::generate_handler![
-            verify_account_and_credentials,
+            discover_credentials,
+            list_accessible_accounts,
+            login,
+            logout,
+            get_profile_vault_status,
+            unlock_profile_vault,
+            lock_profile_vault,
+            list_profiles,
+            create_profile,
+            update_profile,
+            delete_profile,
+            select_profile,
             list_namespaces,
+            list_all_namespaces,
             get_namespace,
             create_namespace,
             update_namespace,
             delete_namespace,
+            delete_namespaces,
+            rename_namespaces,
+            clone_namespace,
+            get_namespace_statistics,
+            list_namespaces_across_profiles,
+            search_namespaces_across_profiles,
+            find_key_across_profiles,
+            list_protected_namespaces,
+            protect_namespace,
+            unprotect_namespace,
+            list_namespace_environments,
+            set_namespace_environment,
             get_kv_pair,
             get_kv_pairs,
             list_kv_keys,
+            list_key_tree,
             create_kv_pair,
             write_kv_pair,
             write_kv_pairs,
             delete_kv_pairs,
+            stage_kv_changes,
+            unstage_kv_changes,
+            get_staged_changes,
+            discard_staged_changes,
+            diff_staged_changes,
+            commit_staged_changes,
+            get_kv_history_config,
+            update_kv_history_config,
+            list_kv_history,
+            list_kv_versions,
+            diff_kv_versions,
+            restore_kv_version,
+            list_audit_entries,
+            export_audit_log,
+            list_tokens,
+            create_token_from_template,
+            roll_token,
+            delete_token,
+            get_connection_settings,
+            update_connection_settings,
+            get_rate_limit_status,
+            update_rate_limit_config,
+            get_traffic_recorder_config,
+            update_traffic_recorder_config,
+            list_traffic_entries,
+            clear_traffic_entries,
+            export_traffic_har,
+            get_kv_cache_status,
+            update_kv_cache_config,
+            clear_kv_cache,
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
@@ -0,0 +1,3 @@
+pub mod profile_commands;
+pub mod profile_models;
+pub mod profile_vault;
@@ -0,0 +1,155 @@
+use crate::authentication::authentication_commands::{
+    AccountSession, AuthenticationCommandError, create_account_session, resolve_credentials,
+};
+use crate::cloudflare::common::Secret;
+use crate::credentials::credentials_registry::{CredentialsInput, CredentialsRegistry};
+use crate::profile::profile_models::{
+    ProfileCreateInput, ProfileSummary, ProfileUpdateInput, ProfileVaultError, ProfileVaultStatus,
+};
+use crate::profile::profile_vault::ProfileVault;
+use crate::session::session_registry::{SessionProfile, SessionRegistry};
+use log::error;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tauri::State;
+
+#[tauri::command]
+pub fn get_profile_vault_status(vault: State<'_, Arc<ProfileVault>>) -> ProfileVaultStatus {
+    vault.status()
+}
+
+#[tauri::command]
+pub async fn unlock_profile_vault(
+    vault: State<'_, Arc<ProfileVault>>,
+    passphrase: Secret,
+) -> Result<Vec<ProfileSummary>, ProfileCommandError> {
+    Ok(vault.unlock(&passphrase)?)
+}
+
+#[tauri::command]
+pub fn lock_profile_vault(vault: State<'_, Arc<ProfileVault>>) {
+    vault.lock();
+}
+
+#[tauri::command]
+pub fn list_profiles(
+    vault: State<'_, Arc<ProfileVault>>,
+) -> Result<Vec<ProfileSummary>, ProfileCommandError> {
+    Ok(vault.list()?)
+}
+
+#[tauri::command]
+pub fn create_profile(
+    vault: State<'_, Arc<ProfileVault>>,
+    credentials_registry: State<'_, Arc<CredentialsRegistry>>,
+    input: ProfileCreateInput,
+    credentials: CredentialsInput,
+) -> Result<ProfileSummary, ProfileCommandError> {
+    let credentials = resolve_credentials(&credentials_registry, credentials)?;
+    Ok(vault.create(input, credentials)?)
+}
+
+#[tauri::command]
+pub fn update_profile(
+    vault: State<'_, Arc<ProfileVault>>,
+    sessions: State<'_, Arc<SessionRegistry>>,
+    input: ProfileUpdateInput,
+) -> Result<ProfileSummary, ProfileCommandError> {
+    let summary = vault.update(input)?;
+    sessions.refresh_profile(
+        SessionProfile {
+            id: summary.id.clone(),
+            name: summary.name.clone(),
+            environment: summary.environment,
+        },
+        summary.read_only,
+    );
+    Ok(summary)
+}
+
+#[tauri::command]
+pub fn delete_profile(
+    vault: State<'_, Arc<ProfileVault>>,
+    profile_id: String,
+) -> Result<(), ProfileCommandError> {
+    Ok(vault.delete(&profile_id)?)
+}
+
+#[tauri::command]
+pub async fn select_profile(
+    vault: State<'_, Arc<ProfileVault>>,
+    sessions: State<'_, Arc<SessionRegistry>>,
+    profile_id: String,
+) -> Result<AccountSession, ProfileCommandError> {
+    let profile = vault.get(&profile_id)?;
+    Ok(create_account_session(
+        &sessions,
+        &profile.account_id,
+        profile.credentials,
+        profile.api_url,
+        Some(SessionProfile {
+            id: profile.id,
+            name: profile.name,
+            environment: profile.environment,
+        }),
+        profile.read_only,
+    )
+    .await?)
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ProfileCommandError {
+    kind: ProfileCommandErrorKind,
+    message: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub enum ProfileCommandErrorKind {
+    VaultLocked,
+    WrongPassphrase,
+    ProfileNotFound,
+    InvalidProfile,
+    Authentication,
+    Unknown,
+}
+
+impl From<ProfileVaultError> for ProfileCommandError {
+    fn from(error: ProfileVaultError) -> Self {
+        match error {
+            ProfileVaultError::Locked => ProfileCommandError {
+                kind: ProfileCommandErrorKind::VaultLocked,
+                message: "Profile vault is locked".to_string(),
+            },
+            ProfileVaultError::WrongPassphrase => ProfileCommandError {
+                kind: ProfileCommandErrorKind::WrongPassphrase,
+                message: "Passphrase is wrong".to_string(),
+            },
+            ProfileVaultError::ProfileNotFound => ProfileCommandError {
+                kind: ProfileCommandErrorKind::ProfileNotFound,
+                message: "Profile not found".to_string(),
+            },
+            ProfileVaultError::InvalidProfile(message) => ProfileCommandError {
+                kind: ProfileCommandErrorKind::InvalidProfile,
+                message,
+            },
+            ProfileVaultError::Io(_)
+            | ProfileVaultError::Serialization(_)
+            | ProfileVaultError::Crypto => {
+                error!("A profile vault error occurred: {error}");
+                ProfileCommandError {
+                    kind: ProfileCommandErrorKind::Unknown,
+                    message: "Profile vault could not be read or written".to_string(),
+                }
+            }
+        }
+    }
+}
+
+impl From<AuthenticationCommandError> for ProfileCommandError {
+    fn from(error: AuthenticationCommandError) -> Self {
+        ProfileCommandError {
+            kind: ProfileCommandErrorKind::Authentication,
+            message: error.message,
+        }
+    }
+}
@@ -0,0 +1,115 @@
+use crate::cloudflare::common::Credentials;
+use crate::environment::environment_labels::{Environment, deserialize_environment_label};
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::fmt;
+use std::fmt::{Display, Formatter};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Profile {
+    pub id: String,
+    pub name: String,
+    pub account_id: String,
+    pub credentials: Credentials,
+    pub api_url: Option<String>,
+
+    #[serde(default, deserialize_with = "deserialize_environment_label")]
+    pub environment: Option<Environment>,
+
+    // Blocks every command which changes something remotely.
+    #[serde(default)]
+    pub read_only: bool,
+}
+
+// The credentials of a profile never leave the backend, so the frontend only gets a summary.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProfileSummary {
+    pub id: String,
+    pub name: String,
+    pub account_id: String,
+    pub credentials_type: String,
+    pub api_url: Option<String>,
+    pub environment: Option<Environment>,
+    pub read_only: bool,
+}
+
+impl From<&Profile> for ProfileSummary {
+    fn from(profile: &Profile) -> Self {
+        Self {
+            id: profile.id.clone(),
+            name: profile.name.clone(),
+            account_id: profile.account_id.clone(),
+            credentials_type: profile.credentials.type_name().to_string(),
+            api_url: profile.api_url.clone(),
+            environment: profile.environment,
+            read_only: profile.read_only,
+        }
+    }
+}
+
+// The credentials are passed separately, so discovered ones can be referred to by their id.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct ProfileCreateInput {
+    pub name: String,
+    pub account_id: String,
+    pub api_url: Option<String>,
+    pub environment: Option<Environment>,
+
+    #[serde(default)]
+    pub read_only: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct ProfileUpdateInput {
+    pub id: String,
+    pub name: String,
+    pub account_id: String,
+    // The stored credentials are kept if no new ones are given.
+    pub credentials: Option<Credentials>,
+    pub api_url: Option<String>,
+    pub environment: Option<Environment>,
+
+    #[serde(default)]
+    pub read_only: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProfileVaultStatus {
+    pub initialized: bool,
+    pub locked: bool,
+}
+
+#[derive(Debug)]
+pub enum ProfileVaultError {
+    Locked,
+    WrongPassphrase,
+    ProfileNotFound,
+    InvalidProfile(String),
+    Io(std::io::Error),
+    Serialization(serde_json::Error),
+    Crypto,
+}
+
+impl From<std::io::Error> for ProfileVaultError {
+    fn from(error: std::io::Error) -> Self {
+        ProfileVaultError::Io(error)
+    }
+}
+
+impl From<serde_json::Error> for ProfileVaultError {
+    fn from(error: serde_json::Error) -> Self {
+        ProfileVaultError::Serialization(error)
+    }
+}
+
+impl Error for ProfileVaultError {}
+
+impl Display for ProfileVaultError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match &self {
+            ProfileVaultError::Io(err) => write!(f, "IO error: {}", err),
+            ProfileVaultError::Serialization(err) => write!(f, "Serialization error: {}", err),
+            _ => write!(f, "ProfileVaultError: {:?}", self),
+        }
+    }
+}
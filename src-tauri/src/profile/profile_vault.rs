@@ -0,0 +1,497 @@
+use crate::cloudflare::common::{Credentials, Secret};
+use crate::profile::profile_models::{
+    Profile, ProfileCreateInput, ProfileSummary, ProfileUpdateInput, ProfileVaultError,
+    ProfileVaultStatus,
+};
+use base64::Engine;
+use base64::prelude::BASE64_STANDARD;
+use reqwest::Url;
+use ring::aead::{AES_256_GCM, Aad, LessSafeKey, NONCE_LEN, Nonce, UnboundKey};
+use ring::pbkdf2;
+use ring::rand::{SecureRandom, SystemRandom};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::num::NonZeroU32;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+use uuid::Uuid;
+use zeroize::Zeroizing;
+
+pub const PROFILE_VAULT_FILE_NAME: &str = "profiles.vault";
+pub const PROFILE_VAULT_IDLE_TIMEOUT: Duration = Duration::from_secs(15 * 60);
+const PROFILE_VAULT_LOCK_INTERVAL: Duration = Duration::from_secs(30);
+
+const VAULT_VERSION: u32 = 1;
+const KDF_ITERATIONS: u32 = 600_000;
+const SALT_LEN: usize = 16;
+const KEY_LEN: usize = 32;
+
+pub struct ProfileVault {
+    path: PathBuf,
+    idle_timeout: Duration,
+    kdf_iterations: u32,
+    random: SystemRandom,
+    state: Mutex<Option<UnlockedVault>>,
+}
+
+struct UnlockedVault {
+    key: Zeroizing<[u8; KEY_LEN]>,
+    salt: Vec<u8>,
+    kdf_iterations: u32,
+    profiles: Vec<Profile>,
+    last_used: Instant,
+}
+
+#[derive(Serialize, Deserialize)]
+struct VaultFile {
+    version: u32,
+    kdf_iterations: u32,
+    salt: String,
+    nonce: String,
+    ciphertext: String,
+}
+
+impl ProfileVault {
+    pub fn new(path: PathBuf, idle_timeout: Duration) -> Self {
+        Self {
+            path,
+            idle_timeout,
+            kdf_iterations: KDF_ITERATIONS,
+            random: SystemRandom::new(),
+            state: Mutex::new(None),
+        }
+    }
+
+    pub fn status(&self) -> ProfileVaultStatus {
+        let mut state = self.lock_state();
+        self.lock_if_idle(&mut state);
+
+        ProfileVaultStatus {
+            initialized: self.path.exists(),
+            locked: state.is_none(),
+        }
+    }
+
+    // Creates a new, empty vault protected by the passphrase if there is none yet.
+    pub fn unlock(&self, passphrase: &Secret) -> Result<Vec<ProfileSummary>, ProfileVaultError> {
+        let unlocked_vault = if self.path.exists() {
+            self.open(passphrase)?
+        } else {
+            let mut salt = vec![0u8; SALT_LEN];
+            self.random
+                .fill(&mut salt)
+                .map_err(|_| ProfileVaultError::Crypto)?;
+            let unlocked_vault = UnlockedVault {
+                key: derive_key(passphrase, &salt, self.kdf_iterations),
+                salt,
+                kdf_iterations: self.kdf_iterations,
+                profiles: vec![],
+                last_used: Instant::now(),
+            };
+            self.save(&unlocked_vault)?;
+            unlocked_vault
+        };
+
+        let summaries = summarize(&unlocked_vault.profiles);
+        *self.lock_state() = Some(unlocked_vault);
+        Ok(summaries)
+    }
+
+    pub fn lock(&self) {
+        *self.lock_state() = None;
+    }
+
+    pub fn list(&self) -> Result<Vec<ProfileSummary>, ProfileVaultError> {
+        self.with_unlocked(|vault| Ok(summarize(&vault.profiles)))
+    }
+
+    pub fn get(&self, profile_id: &str) -> Result<Profile, ProfileVaultError> {
+        self.with_unlocked(|vault| {
+            vault
+                .profiles
+                .iter()
+                .find(|profile| profile.id == profile_id)
+                .cloned()
+                .ok_or(ProfileVaultError::ProfileNotFound)
+        })
+    }
+
+    pub fn create(
+        &self,
+        input: ProfileCreateInput,
+        credentials: Credentials,
+    ) -> Result<ProfileSummary, ProfileVaultError> {
+        let profile = validate_profile(Profile {
+            id: Uuid::new_v4().to_string(),
+            name: input.name,
+            account_id: input.account_id,
+            credentials,
+            api_url: input.api_url,
+            environment: input.environment,
+            read_only: input.read_only,
+        })?;
+
+        self.with_unlocked(|vault| {
+            let summary = ProfileSummary::from(&profile);
+            vault.profiles.push(profile);
+            self.save(vault)?;
+            Ok(summary)
+        })
+    }
+
+    pub fn update(&self, input: ProfileUpdateInput) -> Result<ProfileSummary, ProfileVaultError> {
+        self.with_unlocked(|vault| {
+            let index = vault
+                .profiles
+                .iter()
+                .position(|profile| profile.id == input.id)
+                .ok_or(ProfileVaultError::ProfileNotFound)?;
+
+            let profile = validate_profile(Profile {
+                id: input.id,
+                name: input.name,
+                account_id: input.account_id,
+                credentials: input
+                    .credentials
+                    .unwrap_or_else(|| vault.profiles[index].credentials.clone()),
+                api_url: input.api_url,
+                environment: input.environment,
+                read_only: input.read_only,
+            })?;
+
+            let summary = ProfileSummary::from(&profile);
+            vault.profiles[index] = profile;
+            self.save(vault)?;
+            Ok(summary)
+        })
+    }
+
+    pub fn delete(&self, profile_id: &str) -> Result<(), ProfileVaultError> {
+        self.with_unlocked(|vault| {
+            let profile_count = vault.profiles.len();
+            vault.profiles.retain(|profile| profile.id != profile_id);
+            if vault.profiles.len() == profile_count {
+                return Err(ProfileVaultError::ProfileNotFound);
+            }
+
+            self.save(vault)
+        })
+    }
+
+    pub async fn lock_when_idle_periodically(self: Arc<Self>) {
+        let mut interval = tokio::time::interval(PROFILE_VAULT_LOCK_INTERVAL);
+        loop {
+            interval.tick().await;
+            let mut state = self.lock_state();
+            self.lock_if_idle(&mut state);
+        }
+    }
+
+    fn with_unlocked<T, F>(&self, action: F) -> Result<T, ProfileVaultError>
+    where
+        F: FnOnce(&mut UnlockedVault) -> Result<T, ProfileVaultError>,
+    {
+        let mut state = self.lock_state();
+        self.lock_if_idle(&mut state);
+
+        let vault = state.as_mut().ok_or(ProfileVaultError::Locked)?;
+        vault.last_used = Instant::now();
+        action(vault)
+    }
+
+    // Dropping the unlocked vault zeroizes the key and the credentials of all profiles.
+    fn lock_if_idle(&self, state: &mut Option<UnlockedVault>) {
+        if let Some(vault) = state
+            && vault.last_used.elapsed() > self.idle_timeout
+        {
+            *state = None;
+        }
+    }
+
+    fn open(&self, passphrase: &Secret) -> Result<UnlockedVault, ProfileVaultError> {
+        let vault_file: VaultFile = serde_json::from_slice(&fs::read(&self.path)?)?;
+        if vault_file.version != VAULT_VERSION {
+            return Err(ProfileVaultError::Crypto);
+        }
+
+        let salt = decode(&vault_file.salt)?;
+        let nonce = decode(&vault_file.nonce)?;
+        let mut ciphertext = Zeroizing::new(decode(&vault_file.ciphertext)?);
+
+        let key = derive_key(passphrase, &salt, vault_file.kdf_iterations);
+        let plaintext = open_in_place(&key, &nonce, &mut ciphertext)?;
+        let profiles: Vec<Profile> = serde_json::from_slice(plaintext)?;
+
+        Ok(UnlockedVault {
+            key,
+            salt,
+            kdf_iterations: vault_file.kdf_iterations,
+            profiles,
+            last_used: Instant::now(),
+        })
+    }
+
+    fn save(&self, vault: &UnlockedVault) -> Result<(), ProfileVaultError> {
+        let mut nonce = [0u8; NONCE_LEN];
+        self.random
+            .fill(&mut nonce)
+            .map_err(|_| ProfileVaultError::Crypto)?;
+
+        let mut in_out = Zeroizing::new(serde_json::to_vec(&vault.profiles)?);
+        sealing_key(&vault.key)?
+            .seal_in_place_append_tag(
+                Nonce::assume_unique_for_key(nonce),
+                Aad::empty(),
+                &mut *in_out,
+            )
+            .map_err(|_| ProfileVaultError::Crypto)?;
+
+        let vault_file = VaultFile {
+            version: VAULT_VERSION,
+            kdf_iterations: vault.kdf_iterations,
+            salt: BASE64_STANDARD.encode(&vault.salt),
+            nonce: BASE64_STANDARD.encode(nonce),
+            ciphertext: BASE64_STANDARD.encode(&*in_out),
+        };
+
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        // Write to a temporary file first, so a crash never leaves a half written vault behind.
+        let temporary_path = self.path.with_extension("tmp");
+        fs::write(&temporary_path, serde_json::to_vec(&vault_file)?)?;
+        fs::rename(&temporary_path, &self.path)?;
+
+        Ok(())
+    }
+
+    fn lock_state(&self) -> MutexGuard<'_, Option<UnlockedVault>> {
+        self.state
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+fn derive_key(passphrase: &Secret, salt: &[u8], iterations: u32) -> Zeroizing<[u8; KEY_LEN]> {
+    let mut key = Zeroizing::new([0u8; KEY_LEN]);
+    pbkdf2::derive(
+        pbkdf2::PBKDF2_HMAC_SHA256,
+        NonZeroU32::new(iterations).unwrap_or(NonZeroU32::MIN),
+        salt,
+        passphrase.expose().as_bytes(),
+        &mut *key,
+    );
+    key
+}
+
+fn sealing_key(key: &[u8; KEY_LEN]) -> Result<LessSafeKey, ProfileVaultError> {
+    let unbound_key = UnboundKey::new(&AES_256_GCM, key).map_err(|_| ProfileVaultError::Crypto)?;
+    Ok(LessSafeKey::new(unbound_key))
+}
+
+fn open_in_place<'a>(
+    key: &[u8; KEY_LEN],
+    nonce: &[u8],
+    ciphertext: &'a mut [u8],
+) -> Result<&'a [u8], ProfileVaultError> {
+    let nonce = Nonce::try_assume_unique_for_key(nonce).map_err(|_| ProfileVaultError::Crypto)?;
+
+    // The authentication tag only matches if the key was derived from the right passphrase.
+    let plaintext = sealing_key(key)?
+        .open_in_place(nonce, Aad::empty(), ciphertext)
+        .map_err(|_| ProfileVaultError::WrongPassphrase)?;
+    Ok(plaintext)
+}
+
+fn decode(value: &str) -> Result<Vec<u8>, ProfileVaultError> {
+    BASE64_STANDARD
+        .decode(value)
+        .map_err(|_| ProfileVaultError::Crypto)
+}
+
+fn summarize(profiles: &[Profile]) -> Vec<ProfileSummary> {
+    profiles.iter().map(ProfileSummary::from).collect()
+}
+
+fn validate_profile(mut profile: Profile) -> Result<Profile, ProfileVaultError> {
+    profile.name = profile.name.trim().to_string();
+    profile.account_id = profile.account_id.trim().to_string();
+    profile.api_url = non_empty(profile.api_url);
+
+    if profile.name.is_empty() {
+        return Err(ProfileVaultError::InvalidProfile(
+            "Name must not be empty".to_string(),
+        ));
+    }
+
+    if profile.account_id.is_empty() {
+        return Err(ProfileVaultError::InvalidProfile(
+            "Account ID must not be empty".to_string(),
+        ));
+    }
+
+    if profile.credentials.headers().is_err() {
+        return Err(ProfileVaultError::InvalidProfile(
+            "Credentials contain invalid characters".to_string(),
+        ));
+    }
+
+    if let Some(api_url) = &profile.api_url
+        && !Url::parse(api_url).is_ok_and(|url| url.scheme() == "https" || url.scheme() == "http")
+    {
+        return Err(ProfileVaultError::InvalidProfile(format!(
+            "{api_url} is not a valid API URL"
+        )));
+    }
+
+    Ok(profile)
+}
+
+fn non_empty(value: Option<String>) -> Option<String> {
+    value
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty())
+}
+
+#[cfg(test)]
+mod test {
+    use super::ProfileVault;
+    use crate::cloudflare::common::{Credentials, Secret};
+    use crate::environment::environment_labels::Environment;
+    use crate::profile::profile_models::{
+        ProfileCreateInput, ProfileUpdateInput, ProfileVaultError,
+    };
+    use crate::test_support::create_temp_dir;
+    use std::fs;
+    use std::path::PathBuf;
+    use std::time::Duration;
+
+    #[test]
+    fn should_persist_profiles_encrypted() -> Result<(), ProfileVaultError> {
+        let path = create_vault_path();
+        let vault = create_vault(path.clone(), Duration::from_secs(60));
+        vault.unlock(&Secret::from("passphrase"))?;
+        vault.create(create_profile_input("Production"), create_credentials())?;
+
+        let vault_content = fs::read_to_string(&path)?;
+        assert!(!vault_content.contains("secret-token"));
+        assert!(!vault_content.contains("Production"));
+
+        let reopened_vault = create_vault(path.clone(), Duration::from_secs(60));
+        let profiles = reopened_vault.unlock(&Secret::from("passphrase"))?;
+        assert_eq!(profiles.len(), 1);
+        assert_eq!(profiles[0].name, "Production");
+        assert_eq!(
+            reopened_vault.get(&profiles[0].id)?.credentials,
+            create_credentials()
+        );
+
+        fs::remove_dir_all(path.parent().unwrap())?;
+        Ok(())
+    }
+
+    #[test]
+    fn should_respond_with_a_wrong_passphrase_error() -> Result<(), ProfileVaultError> {
+        let path = create_vault_path();
+        create_vault(path.clone(), Duration::from_secs(60)).unlock(&Secret::from("passphrase"))?;
+
+        let vault = create_vault(path.clone(), Duration::from_secs(60));
+        assert!(matches!(
+            vault.unlock(&Secret::from("wrong")),
+            Err(ProfileVaultError::WrongPassphrase)
+        ));
+        assert!(vault.status().locked);
+
+        fs::remove_dir_all(path.parent().unwrap())?;
+        Ok(())
+    }
+
+    #[test]
+    fn should_keep_credentials_if_none_are_given_on_update() -> Result<(), ProfileVaultError> {
+        let path = create_vault_path();
+        let vault = create_vault(path.clone(), Duration::from_secs(60));
+        vault.unlock(&Secret::from("passphrase"))?;
+        let profile = vault.create(create_profile_input("Staging"), create_credentials())?;
+
+        let updated_profile = vault.update(ProfileUpdateInput {
+            id: profile.id.clone(),
+            name: "Production".to_string(),
+            account_id: profile.account_id,
+            credentials: None,
+            api_url: None,
+            environment: Some(Environment::Production),
+            read_only: true,
+        })?;
+
+        assert_eq!(updated_profile.name, "Production");
+        assert!(updated_profile.read_only);
+        assert_eq!(vault.get(&profile.id)?.credentials, create_credentials());
+
+        fs::remove_dir_all(path.parent().unwrap())?;
+        Ok(())
+    }
+
+    #[test]
+    fn should_lock_after_the_idle_timeout() -> Result<(), ProfileVaultError> {
+        let path = create_vault_path();
+        let vault = create_vault(path.clone(), Duration::ZERO);
+        vault.unlock(&Secret::from("passphrase"))?;
+
+        std::thread::sleep(Duration::from_millis(5));
+
+        assert!(matches!(vault.list(), Err(ProfileVaultError::Locked)));
+        assert!(vault.status().locked);
+
+        fs::remove_dir_all(path.parent().unwrap())?;
+        Ok(())
+    }
+
+    #[test]
+    fn should_reject_an_invalid_api_url() -> Result<(), ProfileVaultError> {
+        let path = create_vault_path();
+        let vault = create_vault(path.clone(), Duration::from_secs(60));
+        vault.unlock(&Secret::from("passphrase"))?;
+
+        let result = vault.create(
+            ProfileCreateInput {
+                api_url: Some("ftp://example.com".to_string()),
+                ..create_profile_input("Production")
+            },
+            create_credentials(),
+        );
+
+        assert!(matches!(result, Err(ProfileVaultError::InvalidProfile(_))));
+
+        fs::remove_dir_all(path.parent().unwrap())?;
+        Ok(())
+    }
+
+    fn create_vault(path: PathBuf, idle_timeout: Duration) -> ProfileVault {
+        ProfileVault {
+            kdf_iterations: 1_000,
+            ..ProfileVault::new(path, idle_timeout)
+        }
+    }
+
+    fn create_vault_path() -> PathBuf {
+        create_temp_dir().join("profiles.vault")
+    }
+
+    fn create_profile_input(name: &str) -> ProfileCreateInput {
+        ProfileCreateInput {
+            name: name.to_string(),
+            account_id: "account-id".to_string(),
+            api_url: None,
+            environment: None,
+            read_only: false,
+        }
+    }
+
+    fn create_credentials() -> Credentials {
+        Credentials::UserAuthToken {
+            token: "secret-token".into(),
+        }
+    }
+}
@@ -0,0 +1,2 @@
+pub mod protected_namespaces;
+pub mod protection_commands;
@@ -0,0 +1,249 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::error::Error;
+use std::fmt;
+use std::fmt::{Display, Formatter};
+use std::fs;
+use std::io::ErrorKind;
+use std::path::PathBuf;
+use std::sync::{Mutex, MutexGuard};
+
+pub const PROTECTED_NAMESPACES_FILE_NAME: &str = "protected-namespaces.json";
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct ProtectedNamespace {
+    pub account_id: String,
+    pub namespace_id: String,
+}
+
+// Commands which delete something take the confirmation next to the API input, which stays as
+// Cloudflare expects it.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct ConfirmedInput<T> {
+    #[serde(flatten)]
+    pub input: T,
+
+    // Protected namespaces are only changed if this matches their title.
+    #[serde(default)]
+    pub confirmation: Option<String>,
+}
+
+// The namespaces are loaded on first use. An unreadable file blocks the deletes instead of
+// dropping the protection.
+pub struct ProtectedNamespaces {
+    path: PathBuf,
+    namespaces: Mutex<Option<BTreeSet<ProtectedNamespace>>>,
+}
+
+impl ProtectedNamespaces {
+    pub fn new(path: PathBuf) -> Self {
+        Self {
+            path,
+            namespaces: Mutex::new(None),
+        }
+    }
+
+    pub fn list(&self, account_id: &str) -> Result<Vec<String>, ProtectionError> {
+        let mut namespaces = self.lock_namespaces();
+        Ok(self
+            .load(&mut namespaces)?
+            .iter()
+            .filter(|namespace| namespace.account_id == account_id)
+            .map(|namespace| namespace.namespace_id.clone())
+            .collect())
+    }
+
+    pub fn is_protected(
+        &self,
+        account_id: &str,
+        namespace_id: &str,
+    ) -> Result<bool, ProtectionError> {
+        let mut namespaces = self.lock_namespaces();
+        Ok(self.load(&mut namespaces)?.contains(&ProtectedNamespace {
+            account_id: account_id.to_string(),
+            namespace_id: namespace_id.to_string(),
+        }))
+    }
+
+    pub fn set_protected(
+        &self,
+        namespace: ProtectedNamespace,
+        protected: bool,
+    ) -> Result<(), ProtectionError> {
+        let mut namespaces = self.lock_namespaces();
+        let loaded_namespaces = self.load(&mut namespaces)?;
+        let mut updated_namespaces = loaded_namespaces.clone();
+        let changed = if protected {
+            updated_namespaces.insert(namespace)
+        } else {
+            updated_namespaces.remove(&namespace)
+        };
+        if !changed {
+            return Ok(());
+        }
+
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        // The protection only changes once the file is replaced, so a failed write keeps both as
+        // they were.
+        let temporary_path = self.path.with_extension("tmp");
+        fs::write(
+            &temporary_path,
+            serde_json::to_vec_pretty(&updated_namespaces)?,
+        )?;
+        fs::rename(&temporary_path, &self.path)?;
+        *loaded_namespaces = updated_namespaces;
+        Ok(())
+    }
+
+    fn load<'a>(
+        &self,
+        namespaces: &'a mut Option<BTreeSet<ProtectedNamespace>>,
+    ) -> Result<&'a mut BTreeSet<ProtectedNamespace>, ProtectionError> {
+        if namespaces.is_none() {
+            let loaded_namespaces = match fs::read(&self.path) {
+                Ok(content) => serde_json::from_slice(&content)?,
+                Err(error) if error.kind() == ErrorKind::NotFound => BTreeSet::new(),
+                Err(error) => return Err(error.into()),
+            };
+            *namespaces = Some(loaded_namespaces);
+        }
+
+        Ok(namespaces.get_or_insert_default())
+    }
+
+    fn lock_namespaces(&self) -> MutexGuard<'_, Option<BTreeSet<ProtectedNamespace>>> {
+        self.namespaces
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+#[derive(Debug)]
+pub enum ProtectionError {
+    ConfirmationRequired(String),
+    Io(std::io::Error),
+    Serialization(serde_json::Error),
+}
+
+impl From<std::io::Error> for ProtectionError {
+    fn from(error: std::io::Error) -> Self {
+        ProtectionError::Io(error)
+    }
+}
+
+impl From<serde_json::Error> for ProtectionError {
+    fn from(error: serde_json::Error) -> Self {
+        ProtectionError::Serialization(error)
+    }
+}
+
+impl Error for ProtectionError {}
+
+impl Display for ProtectionError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match &self {
+            ProtectionError::ConfirmationRequired(title) => write!(
+                f,
+                "The namespace {title} is protected, enter its title to confirm the change"
+            ),
+            ProtectionError::Io(err) => write!(f, "IO error: {}", err),
+            ProtectionError::Serialization(err) => write!(f, "Serialization error: {}", err),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{ConfirmedInput, ProtectedNamespace, ProtectedNamespaces, ProtectionError};
+    use crate::cloudflare::kv::KvPairsDeleteInput;
+    use crate::environment::environment_labels::AcknowledgedInput;
+    use crate::test_support::create_temp_dir;
+    use serde_json::json;
+    use std::fs;
+
+    #[test]
+    fn should_keep_the_protection_after_a_restart() -> Result<(), ProtectionError> {
+        let directory = create_temp_dir();
+        let path = directory.join("protected-namespaces.json");
+        let namespace = |namespace_id: &str| ProtectedNamespace {
+            account_id: "account_id".to_string(),
+            namespace_id: namespace_id.to_string(),
+        };
+
+        let protected_namespaces = ProtectedNamespaces::new(path.clone());
+        protected_namespaces.set_protected(namespace("namespace1"), true)?;
+        protected_namespaces.set_protected(namespace("namespace2"), true)?;
+        protected_namespaces.set_protected(namespace("namespace2"), false)?;
+
+        let restarted = ProtectedNamespaces::new(path);
+        assert!(restarted.is_protected("account_id", "namespace1")?);
+        assert!(!restarted.is_protected("account_id", "namespace2")?);
+        assert!(!restarted.is_protected("other_account_id", "namespace1")?);
+        assert_eq!(restarted.list("account_id")?, vec!["namespace1"]);
+
+        fs::remove_dir_all(directory)?;
+        Ok(())
+    }
+
+    #[test]
+    fn should_keep_the_protection_if_it_could_not_be_saved() -> Result<(), ProtectionError> {
+        let directory = create_temp_dir();
+        let path = directory.join("protected-namespaces.json");
+        let namespace = ProtectedNamespace {
+            account_id: "account_id".to_string(),
+            namespace_id: "namespace1".to_string(),
+        };
+        let protected_namespaces = ProtectedNamespaces::new(path.clone());
+        protected_namespaces.set_protected(namespace.clone(), true)?;
+        // The temporary file cannot be written over a directory.
+        fs::create_dir_all(path.with_extension("tmp"))?;
+
+        assert!(matches!(
+            protected_namespaces.set_protected(namespace, false),
+            Err(ProtectionError::Io(_))
+        ));
+        assert!(protected_namespaces.is_protected("account_id", "namespace1")?);
+        assert!(ProtectedNamespaces::new(path).is_protected("account_id", "namespace1")?);
+
+        fs::remove_dir_all(directory)?;
+        Ok(())
+    }
+
+    #[test]
+    fn should_fail_if_the_file_is_unreadable() -> Result<(), ProtectionError> {
+        let directory = create_temp_dir();
+        let path = directory.join("protected-namespaces.json");
+        fs::create_dir_all(&directory)?;
+        fs::write(&path, "not json")?;
+
+        let protected_namespaces = ProtectedNamespaces::new(path);
+
+        assert!(matches!(
+            protected_namespaces.is_protected("account_id", "namespace1"),
+            Err(ProtectionError::Serialization(_))
+        ));
+
+        fs::remove_dir_all(directory)?;
+        Ok(())
+    }
+
+    #[test]
+    fn should_read_the_confirmation_next_to_the_api_input() {
+        let input: AcknowledgedInput<ConfirmedInput<KvPairsDeleteInput>> =
+            serde_json::from_value(json!({
+                "account_id": "account_id",
+                "namespace_id": "namespace_id",
+                "keys": ["key"],
+                "confirmation": "sessions",
+                "acknowledge_production": true
+            }))
+            .unwrap();
+
+        assert!(input.acknowledged);
+        assert_eq!(input.input.confirmation, Some("sessions".to_string()));
+        assert_eq!(input.input.input.keys, vec!["key".to_string()]);
+    }
+}
@@ -0,0 +1,155 @@
+use crate::audit::audit_log::{AuditEntry, AuditLog, AuditOperation, AuditOutcome};
+use crate::cloudflare::kv::{KvClient, KvNamespaceGetInput};
+use crate::kv::kv_commands::KvCommandError;
+use crate::protection::protected_namespaces::{
+    ConfirmedInput, ProtectedNamespace, ProtectedNamespaces, ProtectionError,
+};
+use crate::session::session_registry::SessionRegistry;
+use std::sync::Arc;
+use tauri::State;
+
+#[tauri::command]
+pub fn list_protected_namespaces(
+    protected_namespaces: State<'_, Arc<ProtectedNamespaces>>,
+    account_id: String,
+) -> Result<Vec<String>, KvCommandError> {
+    Ok(protected_namespaces.list(&account_id)?)
+}
+
+#[tauri::command]
+pub fn protect_namespace(
+    protected_namespaces: State<'_, Arc<ProtectedNamespaces>>,
+    input: ProtectedNamespace,
+) -> Result<(), KvCommandError> {
+    Ok(protected_namespaces.set_protected(input, true)?)
+}
+
+// Dropping the protection needs the same confirmation as a delete, otherwise it would be a way
+// around it.
+#[tauri::command]
+pub async fn unprotect_namespace(
+    sessions: State<'_, Arc<SessionRegistry>>,
+    audit_log: State<'_, Arc<AuditLog>>,
+    protected_namespaces: State<'_, Arc<ProtectedNamespaces>>,
+    session_id: String,
+    input: ConfirmedInput<ProtectedNamespace>,
+) -> Result<(), KvCommandError> {
+    let cloudflare_client = sessions.get_writable(&session_id)?;
+    let ConfirmedInput {
+        input: namespace,
+        confirmation,
+    } = input;
+    confirm_deletion(
+        &protected_namespaces,
+        &cloudflare_client.kv,
+        &namespace.account_id,
+        &namespace.namespace_id,
+        confirmation.as_deref(),
+    )
+    .await?;
+    let mut audit_entry = AuditEntry::new(
+        sessions.profile(&session_id),
+        AuditOperation::NamespaceUnprotect,
+        &namespace.account_id,
+        Some(&namespace.namespace_id),
+    );
+    let result = protected_namespaces.set_protected(namespace, false);
+    audit_entry.outcome = AuditOutcome::of(&result, |_| AuditOutcome::Succeeded);
+    audit_log.record(audit_entry);
+    Ok(result?)
+}
+
+// The title is read when it is needed, so a renamed namespace asks for its new title.
+pub async fn confirm_deletion(
+    protected_namespaces: &ProtectedNamespaces,
+    kv: &KvClient,
+    account_id: &str,
+    namespace_id: &str,
+    confirmation: Option<&str>,
+) -> Result<(), KvCommandError> {
+    if !protected_namespaces.is_protected(account_id, namespace_id)? {
+        return Ok(());
+    }
+
+    let namespace = kv
+        .get_namespace(KvNamespaceGetInput {
+            account_id: account_id.to_string(),
+            namespace_id: namespace_id.to_string(),
+        })
+        .await?;
+    if confirmation != Some(namespace.title.as_str()) {
+        return Err(ProtectionError::ConfirmationRequired(namespace.title).into());
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::confirm_deletion;
+    use crate::cloudflare::common::ApiResponse;
+    use crate::cloudflare::kv::KvNamespace;
+    use crate::kv::kv_commands::KvCommandError;
+    use crate::protection::protected_namespaces::{ProtectedNamespace, ProtectedNamespaces};
+    use crate::test_support::{create_kv_client, create_temp_dir};
+    use std::fs;
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[tokio::test]
+    async fn should_require_the_title_of_a_protected_namespace() -> Result<(), KvCommandError> {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path(
+                "/client/v4/accounts/account_id/storage/kv/namespaces/namespace_id",
+            ))
+            .respond_with(ResponseTemplate::new(200).set_body_json(ApiResponse {
+                result: KvNamespace {
+                    id: "namespace_id".to_string(),
+                    title: "sessions".to_string(),
+                    beta: None,
+                    supports_url_encoding: None,
+                },
+            }))
+            .mount(&mock_server)
+            .await;
+        let kv = create_kv_client(&mock_server);
+        let directory = create_temp_dir();
+        let protected_namespaces =
+            ProtectedNamespaces::new(directory.join("protected-namespaces.json"));
+        protected_namespaces.set_protected(
+            ProtectedNamespace {
+                account_id: "account_id".to_string(),
+                namespace_id: "namespace_id".to_string(),
+            },
+            true,
+        )?;
+
+        for confirmation in [None, Some("sessions-staging")] {
+            let error = confirm_deletion(
+                &protected_namespaces,
+                &kv,
+                "account_id",
+                "namespace_id",
+                confirmation,
+            )
+            .await
+            .unwrap_err();
+            assert_eq!(
+                serde_json::to_value(error).unwrap()["kind"],
+                "ConfirmationRequired"
+            );
+        }
+        confirm_deletion(
+            &protected_namespaces,
+            &kv,
+            "account_id",
+            "namespace_id",
+            Some("sessions"),
+        )
+        .await?;
+
+        fs::remove_dir_all(directory).unwrap();
+        Ok(())
+    }
+}
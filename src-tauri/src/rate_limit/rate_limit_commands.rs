@@ -0,0 +1,16 @@
+use crate::cloudflare::common::{RateLimitConfig, RateLimitStatus, RateLimiter};
+use std::sync::Arc;
+use tauri::State;
+
+#[tauri::command]
+pub fn get_rate_limit_status(rate_limiter: State<'_, Arc<RateLimiter>>) -> RateLimitStatus {
+    rate_limiter.status()
+}
+
+#[tauri::command]
+pub fn update_rate_limit_config(
+    rate_limiter: State<'_, Arc<RateLimiter>>,
+    config: RateLimitConfig,
+) -> RateLimitStatus {
+    rate_limiter.reconfigure(config)
+}
@@ -0,0 +1,375 @@
+use crate::cloudflare::Cloudflare;
+use crate::cloudflare::common::{
+    ConnectionSettings, ConnectionSettingsError, Credentials, RateLimiter, TrafficRecorder,
+};
+use crate::environment::environment_labels::Environment;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::fmt::{Display, Formatter};
+use std::sync::{Arc, RwLock, RwLockReadGuard, RwLockWriteGuard};
+use std::time::{Duration, Instant};
+use uuid::Uuid;
+
+pub const SESSION_IDLE_TIMEOUT: Duration = Duration::from_secs(60 * 60);
+const SESSION_CLEANUP_INTERVAL: Duration = Duration::from_secs(60);
+
+pub struct SessionRegistry {
+    rate_limiter: Arc<RateLimiter>,
+    traffic_recorder: Arc<TrafficRecorder>,
+    idle_timeout: Duration,
+    connection: RwLock<Connection>,
+    sessions: RwLock<HashMap<String, Session>>,
+}
+
+// All sessions created with the same settings share one HTTP client and its connection pool.
+struct Connection {
+    settings: ConnectionSettings,
+    http_client: Arc<reqwest::Client>,
+}
+
+struct Session {
+    cloudflare: Arc<Cloudflare>,
+    profile: Option<SessionProfile>,
+    read_only: bool,
+    last_used: Instant,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SessionProfile {
+    pub id: String,
+    pub name: String,
+
+    #[serde(default)]
+    pub environment: Option<Environment>,
+}
+
+impl SessionRegistry {
+    pub fn new(
+        rate_limiter: Arc<RateLimiter>,
+        traffic_recorder: Arc<TrafficRecorder>,
+        idle_timeout: Duration,
+    ) -> Self {
+        Self {
+            rate_limiter,
+            traffic_recorder,
+            idle_timeout,
+            connection: RwLock::new(Connection {
+                settings: ConnectionSettings::default(),
+                http_client: Arc::new(reqwest::Client::new()),
+            }),
+            sessions: RwLock::new(HashMap::new()),
+        }
+    }
+
+    pub fn create_client(
+        &self,
+        credentials: Credentials,
+        api_url: Option<String>,
+    ) -> Arc<Cloudflare> {
+        let connection = self.read_connection();
+        Arc::new(Cloudflare::new(
+            credentials,
+            api_url.or_else(|| connection.settings.api_url.clone()),
+            Some(connection.http_client.clone()),
+            Some(connection.settings.retry_policy()),
+            Some(self.rate_limiter.clone()),
+            Some(self.traffic_recorder.clone()),
+        ))
+    }
+
+    pub fn connection_settings(&self) -> ConnectionSettings {
+        self.read_connection().settings.clone()
+    }
+
+    // Existing sessions keep their client, the settings apply to sessions created afterwards.
+    pub fn apply_connection_settings(
+        &self,
+        settings: ConnectionSettings,
+    ) -> Result<(), ConnectionSettingsError> {
+        let http_client = Arc::new(settings.build_http_client()?);
+        *self
+            .connection
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner()) = Connection {
+            settings,
+            http_client,
+        };
+
+        Ok(())
+    }
+
+    pub fn create_session(
+        &self,
+        cloudflare: Arc<Cloudflare>,
+        profile: Option<SessionProfile>,
+        read_only: bool,
+    ) -> String {
+        let session_id = Uuid::new_v4().to_string();
+        let mut sessions = self.write_sessions();
+        self.remove_expired(&mut sessions);
+        sessions.insert(
+            session_id.clone(),
+            Session {
+                cloudflare,
+                profile,
+                read_only,
+                last_used: Instant::now(),
+            },
+        );
+
+        session_id
+    }
+
+    pub fn get(&self, session_id: &str) -> Result<Arc<Cloudflare>, SessionError> {
+        let mut sessions = self.write_sessions();
+        let session = sessions.get_mut(session_id).ok_or(SessionError::NotFound)?;
+
+        if session.last_used.elapsed() > self.idle_timeout {
+            sessions.remove(session_id);
+            return Err(SessionError::Expired);
+        }
+
+        session.last_used = Instant::now();
+        Ok(session.cloudflare.clone())
+    }
+
+    // Every command which changes something remotely gets its client from here.
+    pub fn get_writable(&self, session_id: &str) -> Result<Arc<Cloudflare>, SessionError> {
+        let cloudflare = self.get(session_id)?;
+        match self.write_sessions().get(session_id) {
+            Some(session) if session.read_only => Err(SessionError::ReadOnly),
+            _ => Ok(cloudflare),
+        }
+    }
+
+    // Open sessions follow the read-only switch and the environment of their profile right away.
+    pub fn refresh_profile(&self, profile: SessionProfile, read_only: bool) {
+        for session in self.write_sessions().values_mut() {
+            if session
+                .profile
+                .as_ref()
+                .is_some_and(|session_profile| session_profile.id == profile.id)
+            {
+                session.profile = Some(profile.clone());
+                session.read_only = read_only;
+            }
+        }
+    }
+
+    // The profile a session was opened with, there is none after a login with plain credentials.
+    pub fn profile(&self, session_id: &str) -> Option<SessionProfile> {
+        self.write_sessions()
+            .get(session_id)
+            .and_then(|session| session.profile.clone())
+    }
+
+    pub fn remove(&self, session_id: &str) -> bool {
+        self.write_sessions().remove(session_id).is_some()
+    }
+
+    pub async fn remove_expired_sessions_periodically(self: Arc<Self>) {
+        let mut interval = tokio::time::interval(SESSION_CLEANUP_INTERVAL);
+        loop {
+            interval.tick().await;
+            let mut sessions = self.write_sessions();
+            self.remove_expired(&mut sessions);
+        }
+    }
+
+    // Dropping a session drops its client and with it the last reference to the credentials.
+    fn remove_expired(&self, sessions: &mut HashMap<String, Session>) {
+        sessions.retain(|_, session| session.last_used.elapsed() <= self.idle_timeout);
+    }
+
+    fn read_connection(&self) -> RwLockReadGuard<'_, Connection> {
+        self.connection
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    fn write_sessions(&self) -> RwLockWriteGuard<'_, HashMap<String, Session>> {
+        self.sessions
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum SessionError {
+    NotFound,
+    Expired,
+    ReadOnly,
+}
+
+impl Error for SessionError {}
+
+impl Display for SessionError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "SessionError: {:?}", self)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{SESSION_IDLE_TIMEOUT, SessionError, SessionProfile, SessionRegistry};
+    use crate::cloudflare::common::{
+        ConnectionSettings, ConnectionSettingsError, Credentials, RateLimiter,
+    };
+    use crate::environment::environment_labels::Environment;
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    #[test]
+    fn should_reuse_the_client_of_a_session() -> Result<(), SessionError> {
+        let sessions = SessionRegistry::new(
+            Arc::new(RateLimiter::default()),
+            Arc::default(),
+            SESSION_IDLE_TIMEOUT,
+        );
+        let client = sessions.create_client(create_credentials(), None);
+        let session_id = sessions.create_session(client.clone(), None, false);
+
+        assert!(Arc::ptr_eq(&sessions.get(&session_id)?, &client));
+        assert!(Arc::ptr_eq(&sessions.get(&session_id)?, &client));
+
+        Ok(())
+    }
+
+    #[test]
+    fn should_remember_the_profile_of_a_session() {
+        let sessions = SessionRegistry::new(
+            Arc::new(RateLimiter::default()),
+            Arc::default(),
+            SESSION_IDLE_TIMEOUT,
+        );
+        let profile = SessionProfile {
+            id: "profile_id".to_string(),
+            name: "Production".to_string(),
+            environment: Some(Environment::Production),
+        };
+        let profile_session_id = sessions.create_session(
+            sessions.create_client(create_credentials(), None),
+            Some(profile.clone()),
+            false,
+        );
+        let session_id = sessions.create_session(
+            sessions.create_client(create_credentials(), None),
+            None,
+            false,
+        );
+
+        assert_eq!(sessions.profile(&profile_session_id), Some(profile));
+        assert_eq!(sessions.profile(&session_id), None);
+    }
+
+    #[test]
+    fn should_block_writes_while_the_profile_is_read_only() -> Result<(), SessionError> {
+        let sessions = SessionRegistry::new(
+            Arc::new(RateLimiter::default()),
+            Arc::default(),
+            SESSION_IDLE_TIMEOUT,
+        );
+        let profile = SessionProfile {
+            id: "profile_id".to_string(),
+            name: "Production".to_string(),
+            environment: Some(Environment::Production),
+        };
+        let session_id = sessions.create_session(
+            sessions.create_client(create_credentials(), None),
+            Some(profile),
+            true,
+        );
+
+        assert!(sessions.get(&session_id).is_ok());
+        assert!(matches!(
+            sessions.get_writable(&session_id),
+            Err(SessionError::ReadOnly)
+        ));
+
+        sessions.refresh_profile(
+            SessionProfile {
+                id: "profile_id".to_string(),
+                name: "Production".to_string(),
+                environment: None,
+            },
+            false,
+        );
+        sessions.get_writable(&session_id)?;
+        assert_eq!(
+            sessions
+                .profile(&session_id)
+                .and_then(|profile| profile.environment),
+            None
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn should_forget_a_removed_session() {
+        let sessions = SessionRegistry::new(
+            Arc::new(RateLimiter::default()),
+            Arc::default(),
+            SESSION_IDLE_TIMEOUT,
+        );
+        let client = sessions.create_client(create_credentials(), None);
+        let session_id = sessions.create_session(client, None, false);
+
+        assert!(sessions.remove(&session_id));
+        assert!(matches!(
+            sessions.get(&session_id),
+            Err(SessionError::NotFound)
+        ));
+    }
+
+    #[test]
+    fn should_expire_an_idle_session() {
+        let sessions = SessionRegistry::new(
+            Arc::new(RateLimiter::default()),
+            Arc::default(),
+            Duration::ZERO,
+        );
+        let client = sessions.create_client(create_credentials(), None);
+        let session_id = sessions.create_session(client, None, false);
+
+        std::thread::sleep(Duration::from_millis(5));
+
+        assert!(matches!(
+            sessions.get(&session_id),
+            Err(SessionError::Expired)
+        ));
+        assert!(matches!(
+            sessions.get(&session_id),
+            Err(SessionError::NotFound)
+        ));
+    }
+
+    #[test]
+    fn should_keep_the_previous_settings_when_new_ones_are_invalid() {
+        let sessions = SessionRegistry::new(
+            Arc::new(RateLimiter::default()),
+            Arc::default(),
+            SESSION_IDLE_TIMEOUT,
+        );
+        let settings = ConnectionSettings {
+            proxy_url: Some("not a proxy".to_string()),
+            ..ConnectionSettings::default()
+        };
+
+        assert!(matches!(
+            sessions.apply_connection_settings(settings),
+            Err(ConnectionSettingsError::InvalidProxyUrl(_))
+        ));
+        assert_eq!(
+            sessions.connection_settings(),
+            ConnectionSettings::default()
+        );
+    }
+
+    fn create_credentials() -> Credentials {
+        Credentials::UserAuthToken {
+            token: "12345".into(),
+        }
+    }
+}
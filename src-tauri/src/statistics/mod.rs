@@ -0,0 +1,2 @@
+pub mod namespace_statistics;
+pub mod statistics_commands;
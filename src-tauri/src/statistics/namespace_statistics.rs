@@ -0,0 +1,279 @@
+use crate::cloudflare::kv::{KvClient, KvError, KvKey, KvKeysListInput, KvPair, KvPairsGetInput};
+use chrono::{DateTime, Utc};
+use futures::TryStreamExt;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::pin::pin;
+
+pub const DEFAULT_KEY_DELIMITER: &str = "/";
+const MAX_SAMPLED_KEYS: usize = 256;
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct KvNamespaceStatisticsInput {
+    pub account_id: String,
+    pub namespace_id: String,
+    pub delimiter: Option<String>,
+
+    // Cached statistics are returned as they are unless a refresh is requested.
+    #[serde(default)]
+    pub refresh: bool,
+}
+
+// Keys without the delimiter are counted without a prefix.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct KvPrefixStatistics {
+    pub prefix: Option<String>,
+    pub key_count: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct KvNamespaceStatistics {
+    pub account_id: String,
+    pub namespace_id: String,
+    pub delimiter: String,
+    pub computed_at: DateTime<Utc>,
+    pub key_count: u64,
+    pub prefixes: Vec<KvPrefixStatistics>,
+    pub keys_with_metadata: u64,
+    pub keys_with_expiration: u64,
+    pub metadata_share: f64,
+    pub expiration_share: f64,
+    pub value_size_bytes: u64,
+    pub sampled_key_count: u64,
+    pub value_size_estimated: bool,
+}
+
+// Counts the keys while they are listed. The values are too many to read, so every n-th key is
+// kept as a sample, and n doubles whenever the sample gets too large.
+pub struct NamespaceStatisticsCollector {
+    delimiter: String,
+    key_count: u64,
+    prefix_key_counts: HashMap<Option<String>, u64>,
+    keys_with_metadata: u64,
+    keys_with_expiration: u64,
+    sampled_keys: Vec<String>,
+    sample_stride: u64,
+}
+
+impl NamespaceStatisticsCollector {
+    pub fn new(delimiter: &str) -> Self {
+        Self {
+            delimiter: delimiter.to_string(),
+            key_count: 0,
+            prefix_key_counts: HashMap::new(),
+            keys_with_metadata: 0,
+            keys_with_expiration: 0,
+            sampled_keys: vec![],
+            sample_stride: 1,
+        }
+    }
+
+    pub fn add_key(&mut self, key: &KvKey) {
+        let prefix = key
+            .name
+            .split_once(&self.delimiter)
+            .map(|(prefix, _)| prefix.to_string());
+        *self.prefix_key_counts.entry(prefix).or_default() += 1;
+        if key
+            .metadata
+            .as_ref()
+            .is_some_and(|metadata| !metadata.is_null())
+        {
+            self.keys_with_metadata += 1;
+        }
+        if key.expiration.is_some() {
+            self.keys_with_expiration += 1;
+        }
+
+        if self.key_count % self.sample_stride == 0 {
+            self.sampled_keys.push(key.name.clone());
+            if self.sampled_keys.len() > MAX_SAMPLED_KEYS {
+                let mut index = 0;
+                self.sampled_keys.retain(|_| {
+                    index += 1;
+                    index % 2 == 1
+                });
+                self.sample_stride *= 2;
+            }
+        }
+        self.key_count += 1;
+    }
+
+    pub fn sampled_keys(&self) -> &[String] {
+        &self.sampled_keys
+    }
+
+    // The value size is exact as long as every key was sampled, the sampled pairs may miss keys
+    // which were deleted in the meantime.
+    pub fn finish(
+        self,
+        account_id: &str,
+        namespace_id: &str,
+        sampled_pairs: &[KvPair],
+    ) -> KvNamespaceStatistics {
+        let sampled_size_bytes: u64 = sampled_pairs
+            .iter()
+            .map(|pair| pair.value.len() as u64)
+            .sum();
+        let value_size_estimated = self.sample_stride > 1;
+        let value_size_bytes = if !value_size_estimated || sampled_pairs.is_empty() {
+            sampled_size_bytes
+        } else {
+            sampled_size_bytes * self.key_count / sampled_pairs.len() as u64
+        };
+
+        let mut prefixes: Vec<KvPrefixStatistics> = self
+            .prefix_key_counts
+            .into_iter()
+            .map(|(prefix, key_count)| KvPrefixStatistics { prefix, key_count })
+            .collect();
+        prefixes.sort_by(|a, b| {
+            b.key_count
+                .cmp(&a.key_count)
+                .then_with(|| a.prefix.cmp(&b.prefix))
+        });
+
+        KvNamespaceStatistics {
+            account_id: account_id.to_string(),
+            namespace_id: namespace_id.to_string(),
+            delimiter: self.delimiter,
+            computed_at: Utc::now(),
+            key_count: self.key_count,
+            prefixes,
+            keys_with_metadata: self.keys_with_metadata,
+            keys_with_expiration: self.keys_with_expiration,
+            metadata_share: share(self.keys_with_metadata, self.key_count),
+            expiration_share: share(self.keys_with_expiration, self.key_count),
+            value_size_bytes,
+            sampled_key_count: sampled_pairs.len() as u64,
+            value_size_estimated,
+        }
+    }
+}
+
+pub async fn collect_statistics(
+    kv: &KvClient,
+    account_id: &str,
+    namespace_id: &str,
+    delimiter: &str,
+) -> Result<KvNamespaceStatistics, KvError> {
+    let mut collector = NamespaceStatisticsCollector::new(delimiter);
+    let mut keys = pin!(kv.list_all_keys(KvKeysListInput {
+        account_id: account_id.to_string(),
+        namespace_id: namespace_id.to_string(),
+        cursor: None,
+        limit: None,
+        prefix: None,
+    }));
+    while let Some(key) = keys.try_next().await? {
+        collector.add_key(&key);
+    }
+
+    let sampled_pairs = kv
+        .find_kv_pairs(KvPairsGetInput {
+            account_id: account_id.to_string(),
+            namespace_id: namespace_id.to_string(),
+            keys: collector.sampled_keys().to_vec(),
+        })
+        .await?;
+    Ok(collector.finish(account_id, namespace_id, &sampled_pairs))
+}
+
+fn share(count: u64, total: u64) -> f64 {
+    if total == 0 {
+        0.0
+    } else {
+        count as f64 / total as f64
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{KvPrefixStatistics, MAX_SAMPLED_KEYS, NamespaceStatisticsCollector};
+    use crate::cloudflare::kv::{KvKey, KvPair};
+    use chrono::Utc;
+    use serde_json::json;
+
+    #[test]
+    fn should_count_the_keys_by_prefix() {
+        let mut collector = NamespaceStatisticsCollector::new(":");
+        for (name, with_metadata, with_expiration) in [
+            ("user:1", true, false),
+            ("user:2", false, true),
+            ("session:1", true, true),
+            ("config", false, false),
+        ] {
+            collector.add_key(&KvKey {
+                name: name.to_string(),
+                metadata: with_metadata.then(|| json!({"owner": "team"})),
+                expiration: with_expiration.then(Utc::now),
+            });
+        }
+
+        let sampled_pairs: Vec<KvPair> = collector
+            .sampled_keys()
+            .iter()
+            .map(|key| create_pair(key, 10))
+            .collect();
+        let statistics = collector.finish("account_id", "namespace_id", &sampled_pairs);
+
+        assert_eq!(statistics.key_count, 4);
+        assert_eq!(
+            statistics.prefixes,
+            vec![
+                KvPrefixStatistics {
+                    prefix: Some("user".to_string()),
+                    key_count: 2,
+                },
+                KvPrefixStatistics {
+                    prefix: None,
+                    key_count: 1,
+                },
+                KvPrefixStatistics {
+                    prefix: Some("session".to_string()),
+                    key_count: 1,
+                },
+            ]
+        );
+        assert_eq!(statistics.keys_with_metadata, 2);
+        assert_eq!(statistics.expiration_share, 0.5);
+        assert_eq!(statistics.value_size_bytes, 40);
+        assert!(!statistics.value_size_estimated);
+    }
+
+    #[test]
+    fn should_estimate_the_value_size_from_a_sample() {
+        let mut collector = NamespaceStatisticsCollector::new("/");
+        let key_count = MAX_SAMPLED_KEYS * 10;
+        for index in 0..key_count {
+            collector.add_key(&KvKey {
+                name: format!("key{index}"),
+                metadata: None,
+                expiration: None,
+            });
+        }
+
+        assert!(collector.sampled_keys().len() <= MAX_SAMPLED_KEYS);
+        assert_eq!(collector.sampled_keys()[0], "key0");
+        assert_eq!(collector.sampled_keys()[1], "key16");
+
+        let sampled_pairs: Vec<KvPair> = collector
+            .sampled_keys()
+            .iter()
+            .map(|key| create_pair(key, 100))
+            .collect();
+        let statistics = collector.finish("account_id", "namespace_id", &sampled_pairs);
+
+        assert_eq!(statistics.value_size_bytes, key_count as u64 * 100);
+        assert!(statistics.value_size_estimated);
+    }
+
+    fn create_pair(key: &str, size: usize) -> KvPair {
+        KvPair {
+            key: key.to_string(),
+            value: vec![b'x'; size],
+            expiration: None,
+            metadata: None,
+        }
+    }
+}
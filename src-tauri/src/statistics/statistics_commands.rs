@@ -0,0 +1,43 @@
+use crate::cache::kv_cache::{KvCache, statistics_cache_key};
+use crate::kv::kv_commands::KvCommandError;
+use crate::session::session_registry::SessionRegistry;
+use crate::statistics::namespace_statistics::{
+    DEFAULT_KEY_DELIMITER, KvNamespaceStatistics, KvNamespaceStatisticsInput, collect_statistics,
+};
+use log::warn;
+use std::sync::Arc;
+use tauri::State;
+
+// Walking a namespace takes a while, so the statistics are not refreshed in the background. Their
+// `computed_at` tells how old they are.
+#[tauri::command]
+pub async fn get_namespace_statistics(
+    sessions: State<'_, Arc<SessionRegistry>>,
+    cache: State<'_, Arc<KvCache>>,
+    session_id: String,
+    input: KvNamespaceStatisticsInput,
+) -> Result<KvNamespaceStatistics, KvCommandError> {
+    let cloudflare_client = sessions.get(&session_id)?;
+    let delimiter = input
+        .delimiter
+        .filter(|delimiter| !delimiter.is_empty())
+        .unwrap_or_else(|| DEFAULT_KEY_DELIMITER.to_string());
+    let cache_key = statistics_cache_key(&input.account_id, &input.namespace_id, &delimiter);
+    if !input.refresh
+        && let Some(cached) = cache.get::<KvNamespaceStatistics>(&cache_key)
+    {
+        return Ok(cached.value);
+    }
+
+    let statistics = collect_statistics(
+        &cloudflare_client.kv,
+        &input.account_id,
+        &input.namespace_id,
+        &delimiter,
+    )
+    .await?;
+    if let Err(error) = cache.put(&cache_key, &statistics, cache.generation()) {
+        warn!("The statistics {cache_key} could not be cached: {error}");
+    }
+    Ok(statistics)
+}
@@ -0,0 +1,29 @@
+use crate::cloudflare::Cloudflare;
+use crate::cloudflare::common::Credentials;
+use crate::cloudflare::kv::KvClient;
+use std::path::PathBuf;
+use uuid::Uuid;
+use wiremock::MockServer;
+
+// Every test gets a directory of its own, which it removes when it is done. The directory is not
+// created, the code under test has to create it.
+pub fn create_temp_dir() -> PathBuf {
+    std::env::temp_dir().join(format!("flare-commander-{}", Uuid::new_v4()))
+}
+
+pub fn create_cloudflare(mock_server: &MockServer) -> Cloudflare {
+    Cloudflare::new(
+        Credentials::UserAuthToken {
+            token: "12345".into(),
+        },
+        Some(format!("{}/client/v4", mock_server.uri())),
+        None,
+        None,
+        None,
+        None,
+    )
+}
+
+pub fn create_kv_client(mock_server: &MockServer) -> KvClient {
+    create_cloudflare(mock_server).kv
+}
@@ -0,0 +1,2 @@
+pub mod token_commands;
+pub mod token_templates;
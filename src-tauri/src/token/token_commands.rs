@@ -0,0 +1,151 @@
+use crate::cloudflare::common::{CreatedToken, Secret, Tokens, TokensListInput, retry_after_ms};
+use crate::cloudflare::user::UserError;
+use crate::session::session_registry::{SessionError, SessionRegistry};
+use crate::token::token_templates::{
+    TokenFromTemplateCreateInput, TokenTemplateError, create_token_input,
+};
+use log::error;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tauri::State;
+
+#[tauri::command]
+pub async fn list_tokens(
+    sessions: State<'_, Arc<SessionRegistry>>,
+    session_id: String,
+    input: TokensListInput,
+) -> Result<Tokens, TokenCommandError> {
+    let cloudflare_client = sessions.get(&session_id)?;
+    Ok(cloudflare_client.user.list_tokens(input).await?)
+}
+
+#[tauri::command]
+pub async fn create_token_from_template(
+    sessions: State<'_, Arc<SessionRegistry>>,
+    session_id: String,
+    input: TokenFromTemplateCreateInput,
+) -> Result<CreatedToken, TokenCommandError> {
+    let cloudflare_client = sessions.get_writable(&session_id)?;
+    let permission_groups = cloudflare_client
+        .user
+        .list_token_permission_groups()
+        .await?;
+    let token_input = create_token_input(input, &permission_groups)?;
+    Ok(cloudflare_client.user.create_token(token_input).await?)
+}
+
+#[tauri::command]
+pub async fn roll_token(
+    sessions: State<'_, Arc<SessionRegistry>>,
+    session_id: String,
+    token_id: String,
+) -> Result<Secret, TokenCommandError> {
+    let cloudflare_client = sessions.get_writable(&session_id)?;
+    Ok(cloudflare_client.user.roll_token(&token_id).await?)
+}
+
+#[tauri::command]
+pub async fn delete_token(
+    sessions: State<'_, Arc<SessionRegistry>>,
+    session_id: String,
+    token_id: String,
+) -> Result<(), TokenCommandError> {
+    let cloudflare_client = sessions.get_writable(&session_id)?;
+    Ok(cloudflare_client.user.delete_token(&token_id).await?)
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TokenCommandError {
+    kind: TokenCommandErrorKind,
+    message: String,
+    // When a rate limited request may be retried, if Cloudflare told.
+    retry_after_ms: Option<u64>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub enum TokenCommandErrorKind {
+    PermissionGroupNotFound,
+    Authentication,
+    SessionExpired,
+    ReadOnly,
+    RateLimited,
+    Unknown,
+}
+
+impl From<UserError> for TokenCommandError {
+    fn from(error: UserError) -> Self {
+        match error {
+            UserError::Token(token_err) => {
+                error!("A token error occurred on managing tokens: {token_err}");
+                TokenCommandError {
+                    kind: TokenCommandErrorKind::Authentication,
+                    message: "Authentication error".to_string(),
+                    retry_after_ms: None,
+                }
+            }
+            UserError::Unauthorized(api_error) => {
+                error!("A request on managing tokens could not be authenticated: {api_error}");
+                TokenCommandError {
+                    kind: TokenCommandErrorKind::Authentication,
+                    message: "Authentication error".to_string(),
+                    retry_after_ms: None,
+                }
+            }
+            UserError::RateLimited(retry_after, _) => {
+                error!("Rate limit exceeded on managing tokens, retry after: {retry_after:?}");
+                TokenCommandError {
+                    kind: TokenCommandErrorKind::RateLimited,
+                    message: "Too many requests, please try again later".to_string(),
+                    retry_after_ms: retry_after_ms(retry_after),
+                }
+            }
+            UserError::Reqwest(reqwest_err) => {
+                error!("A reqwest error occurred on managing tokens: {reqwest_err}");
+                TokenCommandError {
+                    kind: TokenCommandErrorKind::Unknown,
+                    message: "A network error occurred".to_string(),
+                    retry_after_ms: None,
+                }
+            }
+            UserError::Api(api_error) => {
+                error!("An API error occurred on managing tokens: {api_error}");
+                TokenCommandError {
+                    kind: TokenCommandErrorKind::Unknown,
+                    message: api_error.message(),
+                    retry_after_ms: None,
+                }
+            }
+        }
+    }
+}
+
+impl From<TokenTemplateError> for TokenCommandError {
+    fn from(error: TokenTemplateError) -> Self {
+        match error {
+            TokenTemplateError::PermissionGroupNotFound(name) => TokenCommandError {
+                kind: TokenCommandErrorKind::PermissionGroupNotFound,
+                message: format!("The permission group {name} is not available"),
+                retry_after_ms: None,
+            },
+        }
+    }
+}
+
+impl From<SessionError> for TokenCommandError {
+    fn from(error: SessionError) -> Self {
+        if error == SessionError::ReadOnly {
+            return TokenCommandError {
+                kind: TokenCommandErrorKind::ReadOnly,
+                message: "The profile is read-only".to_string(),
+                retry_after_ms: None,
+            };
+        }
+
+        error!("A session error occurred on managing tokens: {error}");
+        TokenCommandError {
+            kind: TokenCommandErrorKind::SessionExpired,
+            message: "Session has expired, please log in again".to_string(),
+            retry_after_ms: None,
+        }
+    }
+}
@@ -0,0 +1,171 @@
+use crate::cloudflare::common::{
+    ACCOUNT_RESOURCE_PREFIX, KV_READ_PERMISSION_GROUP, KV_WRITE_PERMISSION_GROUP, PermissionGroup,
+    RequestIpCondition, TokenCondition, TokenCreateInput, TokenPolicy, TokenPolicyEffect,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::fmt::{Display, Formatter};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum TokenTemplate {
+    KvReadOnly,
+    KvReadWrite,
+}
+
+impl TokenTemplate {
+    fn permission_group_names(&self) -> Vec<&'static str> {
+        match self {
+            TokenTemplate::KvReadOnly => vec![KV_READ_PERMISSION_GROUP],
+            TokenTemplate::KvReadWrite => vec![KV_READ_PERMISSION_GROUP, KV_WRITE_PERMISSION_GROUP],
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct TokenFromTemplateCreateInput {
+    pub name: String,
+    pub account_id: String,
+    pub template: TokenTemplate,
+    pub expires_on: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub allowed_ips: Vec<String>,
+}
+
+// The ids of the permission groups differ between accounts, so they are looked up by name.
+pub fn create_token_input(
+    input: TokenFromTemplateCreateInput,
+    available_permission_groups: &[PermissionGroup],
+) -> Result<TokenCreateInput, TokenTemplateError> {
+    let permission_groups = input
+        .template
+        .permission_group_names()
+        .into_iter()
+        .map(|name| {
+            available_permission_groups
+                .iter()
+                .find(|group| group.name.as_deref() == Some(name))
+                .map(|group| PermissionGroup {
+                    id: group.id.clone(),
+                    name: None,
+                })
+                .ok_or(TokenTemplateError::PermissionGroupNotFound(
+                    name.to_string(),
+                ))
+        })
+        .collect::<Result<Vec<PermissionGroup>, TokenTemplateError>>()?;
+
+    let condition = if input.allowed_ips.is_empty() {
+        None
+    } else {
+        Some(TokenCondition {
+            request_ip: Some(RequestIpCondition {
+                allowed: input.allowed_ips,
+                denied: vec![],
+            }),
+        })
+    };
+
+    Ok(TokenCreateInput {
+        name: input.name,
+        policies: vec![TokenPolicy {
+            id: None,
+            effect: TokenPolicyEffect::Allow,
+            permission_groups,
+            resources: HashMap::from([(
+                format!("{}{}", ACCOUNT_RESOURCE_PREFIX, input.account_id),
+                Value::from("*"),
+            )]),
+        }],
+        not_before: None,
+        expires_on: input.expires_on,
+        condition,
+    })
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum TokenTemplateError {
+    PermissionGroupNotFound(String),
+}
+
+impl Error for TokenTemplateError {}
+
+impl Display for TokenTemplateError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "TokenTemplateError: {:?}", self)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{
+        TokenFromTemplateCreateInput, TokenTemplate, TokenTemplateError, create_token_input,
+    };
+    use crate::cloudflare::common::PermissionGroup;
+    use serde_json::Value;
+
+    #[test]
+    fn should_create_a_kv_read_only_token_input_for_the_account() -> Result<(), TokenTemplateError>
+    {
+        let token_input = create_token_input(
+            create_template_input(TokenTemplate::KvReadOnly),
+            &create_permission_groups(),
+        )?;
+
+        let policy = &token_input.policies[0];
+        assert_eq!(policy.permission_groups.len(), 1);
+        assert_eq!(policy.permission_groups[0].id, "kv-read-id");
+        assert_eq!(
+            policy.resources.get("com.cloudflare.api.account.12345"),
+            Some(&Value::from("*"))
+        );
+        assert!(
+            token_input
+                .condition
+                .is_some_and(|condition| condition.request_ip.is_some())
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn should_respond_with_an_error_for_a_missing_permission_group() {
+        let result = create_token_input(
+            create_template_input(TokenTemplate::KvReadWrite),
+            &create_permission_groups()[..1],
+        );
+
+        assert_eq!(
+            result,
+            Err(TokenTemplateError::PermissionGroupNotFound(
+                "Workers KV Storage Write".to_string()
+            ))
+        );
+    }
+
+    fn create_template_input(template: TokenTemplate) -> TokenFromTemplateCreateInput {
+        TokenFromTemplateCreateInput {
+            name: "Contractor".to_string(),
+            account_id: "12345".to_string(),
+            template,
+            expires_on: None,
+            allowed_ips: vec!["192.0.2.0/24".to_string()],
+        }
+    }
+
+    fn create_permission_groups() -> Vec<PermissionGroup> {
+        vec![
+            PermissionGroup {
+                id: "kv-read-id".to_string(),
+                name: Some("Workers KV Storage Read".to_string()),
+            },
+            PermissionGroup {
+                id: "kv-write-id".to_string(),
+                name: Some("Workers KV Storage Write".to_string()),
+            },
+        ]
+    }
+}
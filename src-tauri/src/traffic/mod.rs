@@ -0,0 +1 @@
+pub mod traffic_commands;
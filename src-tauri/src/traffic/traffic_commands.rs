@@ -0,0 +1,40 @@
+use crate::cloudflare::common::{
+    TrafficEntry, TrafficQuery, TrafficRecorder, TrafficRecorderConfig,
+};
+use serde_json::Value;
+use std::sync::Arc;
+use tauri::State;
+
+#[tauri::command]
+pub fn get_traffic_recorder_config(
+    traffic_recorder: State<'_, Arc<TrafficRecorder>>,
+) -> TrafficRecorderConfig {
+    traffic_recorder.config()
+}
+
+#[tauri::command]
+pub fn update_traffic_recorder_config(
+    traffic_recorder: State<'_, Arc<TrafficRecorder>>,
+    config: TrafficRecorderConfig,
+) -> TrafficRecorderConfig {
+    traffic_recorder.reconfigure(config)
+}
+
+#[tauri::command]
+pub fn list_traffic_entries(
+    traffic_recorder: State<'_, Arc<TrafficRecorder>>,
+    query: TrafficQuery,
+) -> Vec<TrafficEntry> {
+    traffic_recorder.entries(&query)
+}
+
+#[tauri::command]
+pub fn clear_traffic_entries(traffic_recorder: State<'_, Arc<TrafficRecorder>>) {
+    traffic_recorder.clear();
+}
+
+// The HAR document is saved by the frontend, like every other export.
+#[tauri::command]
+pub fn export_traffic_har(traffic_recorder: State<'_, Arc<TrafficRecorder>>) -> Value {
+    traffic_recorder.export_har()
+}
@@ -1,26 +1,61 @@
 export type AuthenticationErrorKind =
   | 'InvalidToken'
+  | 'MalformedCredentials'
   | 'ExpiredToken'
   | 'DisabledToken'
   | 'InvalidAccountId'
+  | 'RateLimited'
   | 'Unknown';
 
+export interface CloudflareApiError {
+  status: number;
+  ray_id: string | null;
+  errors: { code: number; message: string }[];
+  messages: { code: number | null; message: string }[];
+}
+
+export interface AuthenticationCommandError {
+  message: string;
+  kind: AuthenticationErrorKind;
+  api_error: CloudflareApiError | null;
+  retry_after_ms: number | null;
+}
+
 export class AuthenticationError extends Error {
   constructor(
     message: string,
     public kind: AuthenticationErrorKind,
+    public apiError: CloudflareApiError | null = null,
+    public retryAfterMs: number | null = null,
   ) {
     super(message);
     this.name = 'AuthenticationError';
   }
 }
 
-export interface AccountWithCredentials {
+export interface AccountSession {
   id: string;
   name: string;
-  credentials: Credentials;
+  session_id: string;
+  token_capabilities: TokenCapabilities | null;
+  read_only: boolean;
+  environment: Environment | null;
 }
 
+export type Environment = 'Development' | 'Staging' | 'Production';
+
+export interface TokenCapabilities {
+  kv_access: KvAccess;
+  permission_groups: string[];
+  expires_on: string | null;
+  ip_restricted: boolean;
+  warnings: TokenWarning[];
+}
+
+export type KvAccess = 'None' | 'Read' | 'ReadWrite';
+
+export type TokenWarning = 'ExpiresSoon' | 'NotYetValid' | 'KvReadOnly' | 'NoKvAccess';
+
 export type Credentials =
   | UserAuthKeyCredentials
   | UserAuthTokenCredentials
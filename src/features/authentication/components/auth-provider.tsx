@@ -1,11 +1,16 @@
 import { createContext, FunctionComponent, ReactNode } from 'react';
 import { useNavigate } from 'react-router';
 import { invoke } from '@tauri-apps/api/core';
-import { AccountWithCredentials, AuthenticationError, Credentials } from '@/features/authentication/auth-models.ts';
+import {
+  AccountSession,
+  AuthenticationCommandError,
+  AuthenticationError,
+  Credentials,
+} from '@/features/authentication/auth-models.ts';
 import { useLocalStorage } from '@/hooks/use-local-storage.ts';
 
 interface AuthContextValue {
-  account: AccountWithCredentials | null;
+  account: AccountSession | null;
   verifyCredentials: (accountId: string, credentials: Credentials) => Promise<void>;
   resetCredentials: () => void;
 }
@@ -22,14 +27,19 @@ interface AuthProviderProps {
 
 const AuthProvider: FunctionComponent<AuthProviderProps> = ({ children }) => {
   const navigate = useNavigate();
-  const [account, setAccount] = useLocalStorage<AccountWithCredentials>('account');
+  const [account, setAccount] = useLocalStorage<AccountSession>('account');
 
   const verifyCredentials = async (accountId: string, credentials: Credentials) => {
-    const accountWithCredentials = await invokeVerifyAccountAndCredentials(accountId, credentials);
-    setAccount(accountWithCredentials);
+    const accountSession = await invokeLogin(accountId, credentials);
+    setAccount(accountSession);
     navigate('/');
   };
-  const logout = async () => setAccount(null);
+  const logout = async () => {
+    if (account) {
+      await invokeLogout(account.session_id);
+    }
+    setAccount(null);
+  };
 
   const value: AuthContextValue = {
     account,
@@ -40,19 +50,24 @@ const AuthProvider: FunctionComponent<AuthProviderProps> = ({ children }) => {
   return <AuthContext.Provider value={value}>{children}</AuthContext.Provider>;
 };
 
-async function invokeVerifyAccountAndCredentials(
-  accountId: string,
-  credentials: Credentials,
-): Promise<AccountWithCredentials> {
+async function invokeLogin(accountId: string, credentials: Credentials): Promise<AccountSession> {
   try {
-    return await invoke<AccountWithCredentials>('verify_account_and_credentials', {
+    return await invoke<AccountSession>('login', {
       accountId,
       credentials,
     });
   } catch (e) {
     console.error('An error occurred on verifying account and credentials', e);
-    const error = e as AuthenticationError;
-    throw new AuthenticationError(error.message, error.kind);
+    const error = e as AuthenticationCommandError;
+    throw new AuthenticationError(error.message, error.kind, error.api_error, error.retry_after_ms);
+  }
+}
+
+async function invokeLogout(sessionId: string): Promise<void> {
+  try {
+    await invoke('logout', { sessionId });
+  } catch (e) {
+    console.error('An error occurred on logging out', e);
   }
 }
 
@@ -1,5 +1,6 @@
 import { useEffect, useState } from 'react';
 import {
+  KvCommandError,
   KvError,
   KvKey,
   KvKeys,
@@ -8,13 +9,13 @@ import {
   KvPairsDeleteInput,
   KvPairsDeleteResult,
 } from '@/features/kv/kv-models.ts';
-import { CredentialsType, UserAuthTokenCredentials } from '@/features/authentication/auth-models.ts';
 import { useAuth } from '@/features/authentication/hooks/use-auth.ts';
 import { invoke } from '@tauri-apps/api/core';
 import { convertPlainToKvErrorClass } from '@/features/kv/lib/kv-utils.ts';
 
 export function useKvKeys(namespaceId: string) {
   const { account } = useAuth();
+  const sessionId = account?.session_id ?? '';
   const [isLoading, setIsLoading] = useState(true);
   const [isInitialLoading, setIsInitialLoading] = useState(true);
   const [isLoadingNextKeys, setIsLoadingNextKeys] = useState(false);
@@ -28,15 +29,10 @@ export function useKvKeys(namespaceId: string) {
   const loadKeys = async (cursor?: string, limit = 20, prefix?: string) => {
     setIsLoading(true);
 
-    const credentials: UserAuthTokenCredentials = {
-      type: CredentialsType.UserAuthToken,
-      token: (account?.credentials as UserAuthTokenCredentials).token,
-    };
-
     try {
       const nextKeys = await invokeListKvKeys(
         { account_id: account?.id ?? '', namespace_id: namespaceId, cursor, limit, prefix },
-        credentials,
+        sessionId,
       );
       if (cursor) {
         setKvKeys((previousKeys) => {
@@ -99,10 +95,6 @@ export function useKvKeys(namespaceId: string) {
   const deleteKeys = async (keys: string[]) => {
     setIsDeleting(true);
 
-    const credentials: UserAuthTokenCredentials = {
-      type: CredentialsType.UserAuthToken,
-      token: (account?.credentials as UserAuthTokenCredentials).token,
-    };
     const input: KvPairsDeleteInput = {
       account_id: account?.id ?? '',
       namespace_id: namespaceId,
@@ -110,7 +102,7 @@ export function useKvKeys(namespaceId: string) {
     };
 
     try {
-      const result = await invokeDeleteKvPairs(input, credentials);
+      const result = await invokeDeleteKvPairs(input, sessionId);
       setDeletionResult(result);
     } catch (e) {
       console.error(e);
@@ -142,11 +134,11 @@ export function useKvKeys(namespaceId: string) {
   };
 }
 
-export async function invokeListKvKeys(input: KvKeysListInput, credentials: UserAuthTokenCredentials): Promise<KvKeys> {
+export async function invokeListKvKeys(input: KvKeysListInput, sessionId: string): Promise<KvKeys> {
   try {
     const kvKeys = await invoke<KvKeysDTO>('list_kv_keys', {
       input,
-      credentials,
+      sessionId,
     });
 
     return {
@@ -157,20 +149,20 @@ export async function invokeListKvKeys(input: KvKeysListInput, credentials: User
       })),
     };
   } catch (e) {
-    throw convertPlainToKvErrorClass(e as KvError);
+    throw convertPlainToKvErrorClass(e as KvCommandError);
   }
 }
 
 export async function invokeDeleteKvPairs(
   input: KvPairsDeleteInput,
-  credentials: UserAuthTokenCredentials,
+  sessionId: string,
 ): Promise<KvPairsDeleteResult> {
   try {
     return invoke<KvPairsDeleteResult>('delete_kv_pairs', {
       input,
-      credentials,
+      sessionId,
     });
   } catch (e) {
-    throw convertPlainToKvErrorClass(e as KvError);
+    throw convertPlainToKvErrorClass(e as KvCommandError);
   }
 }
@@ -1,6 +1,7 @@
 import { useAuth } from '@/features/authentication/hooks/use-auth.ts';
 import { useState } from 'react';
 import {
+  KvCommandError,
   KvError,
   KvNamespace,
   KvNamespaceCreateInput,
@@ -11,13 +12,16 @@ import {
   KvNamespacesOrderBy,
   KvNamespaceUpdateInput,
 } from '@/features/kv/kv-models.ts';
-import { CredentialsType, UserAuthTokenCredentials } from '@/features/authentication/auth-models.ts';
 
 import { invoke } from '@tauri-apps/api/core';
 import { convertPlainToKvErrorClass } from '@/features/kv/lib/kv-utils.ts';
 
+// The page size of the Cloudflare API if none is requested.
+const NAMESPACES_PER_PAGE = 20;
+
 export function useKvNamespaces() {
   const { account } = useAuth();
+  const sessionId = account?.session_id ?? '';
   const [isListing, setIsListing] = useState(false);
   const [isRelisting, setIsRelisting] = useState(false);
   const [isLoadingOne, setIsLoadingOne] = useState(false);
@@ -35,18 +39,14 @@ export function useKvNamespaces() {
     setIsListing(true);
 
     try {
-      const credentials: UserAuthTokenCredentials = {
-        type: CredentialsType.UserAuthToken,
-        token: (account?.credentials as UserAuthTokenCredentials).token,
-      };
-      const namespaces = await invokeListNamespaces(credentials, {
+      const namespaces = await invokeListAllNamespaces(sessionId, {
         account_id: account?.id ?? '',
         order_by: KvNamespacesOrderBy.TITLE,
       });
 
-      setPage(namespaces.page_info.page);
-      setTotalCount(namespaces.page_info.total_count);
-      setNamespaces(namespaces.items);
+      setPage(Math.max(1, Math.ceil(namespaces.length / NAMESPACES_PER_PAGE)));
+      setTotalCount(namespaces.length);
+      setNamespaces(namespaces);
     } catch (e) {
       setError(e as KvError);
     } finally {
@@ -58,11 +58,7 @@ export function useKvNamespaces() {
     setIsLoadingNext(true);
 
     try {
-      const credentials: UserAuthTokenCredentials = {
-        type: CredentialsType.UserAuthToken,
-        token: (account?.credentials as UserAuthTokenCredentials).token,
-      };
-      const nextNamespaces = await invokeListNamespaces(credentials, {
+      const nextNamespaces = await invokeListNamespaces(sessionId, {
         account_id: account?.id ?? '',
         order_by: KvNamespacesOrderBy.TITLE,
         page: page + 1,
@@ -98,20 +94,14 @@ export function useKvNamespaces() {
     setIsRelisting(true);
 
     try {
-      const credentials: UserAuthTokenCredentials = {
-        type: CredentialsType.UserAuthToken,
-        token: (account?.credentials as UserAuthTokenCredentials).token,
-      };
-      const per_page = namespaces && namespaces.length > 20 ? namespaces.length : 20;
-      const reloadedNamespaces = await invokeListNamespaces(credentials, {
+      const reloadedNamespaces = await invokeListAllNamespaces(sessionId, {
         account_id: account?.id ?? '',
         order_by: KvNamespacesOrderBy.TITLE,
-        per_page,
       });
 
-      setPage(reloadedNamespaces.page_info.page);
-      setTotalCount(reloadedNamespaces.page_info.total_count);
-      setNamespaces(reloadedNamespaces.items);
+      setPage(Math.max(1, Math.ceil(reloadedNamespaces.length / NAMESPACES_PER_PAGE)));
+      setTotalCount(reloadedNamespaces.length);
+      setNamespaces(reloadedNamespaces);
     } catch (e) {
       setError(e as KvError);
     } finally {
@@ -123,15 +113,11 @@ export function useKvNamespaces() {
     setIsLoadingOne(true);
 
     try {
-      const credentials: UserAuthTokenCredentials = {
-        type: CredentialsType.UserAuthToken,
-        token: (account?.credentials as UserAuthTokenCredentials).token,
-      };
       const input: KvNamespaceGetInput = {
         account_id: account?.id ?? '',
         namespace_id: namespaceId,
       };
-      const namespace = await invokeGetNamespace(input, credentials);
+      const namespace = await invokeGetNamespace(input, sessionId);
       setNamespace(namespace);
     } catch (e) {
       setError(e as KvError);
@@ -144,11 +130,7 @@ export function useKvNamespaces() {
     setIsCreating(true);
 
     try {
-      const credentials: UserAuthTokenCredentials = {
-        type: CredentialsType.UserAuthToken,
-        token: (account?.credentials as UserAuthTokenCredentials).token,
-      };
-      const createdNamespace = await invokeCreateNamespace({ ...input, account_id: account?.id ?? '' }, credentials);
+      const createdNamespace = await invokeCreateNamespace({ ...input, account_id: account?.id ?? '' }, sessionId);
       setNamespace(createdNamespace);
     } catch (e) {
       setError(e as KvError);
@@ -161,11 +143,7 @@ export function useKvNamespaces() {
     setIsUpdating(true);
 
     try {
-      const credentials: UserAuthTokenCredentials = {
-        type: CredentialsType.UserAuthToken,
-        token: (account?.credentials as UserAuthTokenCredentials).token,
-      };
-      await invokeUpdateNamespace({ ...input, account_id: account?.id ?? '' }, credentials);
+      await invokeUpdateNamespace({ ...input, account_id: account?.id ?? '' }, sessionId);
     } catch (e) {
       setError(e as KvError);
       throw e;
@@ -178,11 +156,7 @@ export function useKvNamespaces() {
     setIsDeleting(true);
 
     try {
-      const credentials: UserAuthTokenCredentials = {
-        type: CredentialsType.UserAuthToken,
-        token: (account?.credentials as UserAuthTokenCredentials).token,
-      };
-      await invokeDeleteNamespace({ account_id: account?.id ?? '', namespace_id: namespaceId }, credentials);
+      await invokeDeleteNamespace({ account_id: account?.id ?? '', namespace_id: namespaceId }, sessionId);
     } catch (e) {
       setError(e as KvError);
     } finally {
@@ -214,68 +188,82 @@ export function useKvNamespaces() {
 }
 
 export async function invokeListNamespaces(
-  credentials: UserAuthTokenCredentials,
+  sessionId: string,
   input: KvNamespacesListInput,
 ): Promise<KvNamespaces> {
   try {
     return await invoke<KvNamespaces>('list_namespaces', {
-      credentials,
+      sessionId,
+      input,
+    });
+  } catch (e) {
+    throw convertPlainToKvErrorClass(e as KvCommandError);
+  }
+}
+
+export async function invokeListAllNamespaces(
+  sessionId: string,
+  input: KvNamespacesListInput,
+): Promise<KvNamespace[]> {
+  try {
+    return await invoke<KvNamespace[]>('list_all_namespaces', {
+      sessionId,
       input,
     });
   } catch (e) {
-    throw convertPlainToKvErrorClass(e as KvError);
+    throw convertPlainToKvErrorClass(e as KvCommandError);
   }
 }
 
 export async function invokeGetNamespace(
   input: KvNamespaceGetInput,
-  credentials: UserAuthTokenCredentials,
+  sessionId: string,
 ): Promise<KvNamespace> {
   try {
     return await invoke<KvNamespace>('get_namespace', {
-      credentials,
+      sessionId,
       input,
     });
   } catch (e) {
-    throw convertPlainToKvErrorClass(e as KvError);
+    throw convertPlainToKvErrorClass(e as KvCommandError);
   }
 }
 
 export async function invokeCreateNamespace(
   input: KvNamespaceCreateInput,
-  credentials: UserAuthTokenCredentials,
+  sessionId: string,
 ): Promise<KvNamespace> {
   try {
     return await invoke<KvNamespace>('create_namespace', {
       input,
-      credentials,
+      sessionId,
     });
   } catch (e) {
-    throw convertPlainToKvErrorClass(e as KvError);
+    throw convertPlainToKvErrorClass(e as KvCommandError);
   }
 }
 
 export async function invokeUpdateNamespace(
   input: KvNamespaceUpdateInput,
-  credentials: UserAuthTokenCredentials,
+  sessionId: string,
 ): Promise<KvNamespace> {
   try {
     return await invoke<KvNamespace>('update_namespace', {
       input,
-      credentials,
+      sessionId,
     });
   } catch (e) {
-    throw convertPlainToKvErrorClass(e as KvError);
+    throw convertPlainToKvErrorClass(e as KvCommandError);
   }
 }
 
-async function invokeDeleteNamespace(input: KvNamespaceDeleteInput, credentials: UserAuthTokenCredentials) {
+async function invokeDeleteNamespace(input: KvNamespaceDeleteInput, sessionId: string) {
   try {
     return await invoke<KvNamespace>('delete_namespace', {
       input,
-      credentials,
+      sessionId,
     });
   } catch (e) {
-    throw convertPlainToKvErrorClass(e as KvError);
+    throw convertPlainToKvErrorClass(e as KvCommandError);
   }
 }
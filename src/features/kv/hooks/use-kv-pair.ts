@@ -1,6 +1,6 @@
-import { Credentials, CredentialsType, UserAuthTokenCredentials } from '@/features/authentication/auth-models.ts';
 import { useAuth } from '@/features/authentication/hooks/use-auth.ts';
 import {
+  KvCommandError,
   KvError,
   KvPairWriteInput,
   KvPair,
@@ -14,6 +14,7 @@ import { convertPlainToKvErrorClass } from '@/features/kv/lib/kv-utils.ts';
 
 export function useKvPair() {
   const { account } = useAuth();
+  const sessionId = account?.session_id ?? '';
   const [kvPair, setKvPair] = useState<KvPair | null>(null);
   const [isLoading, setIsLoading] = useState(false);
   const [isCreating, setIsCreating] = useState(false);
@@ -28,7 +29,7 @@ export function useKvPair() {
     try {
       const pair = await invokeGetKvPair(
         { account_id: account?.id as string, namespace_id: namespaceId, key },
-        account?.credentials as Credentials,
+        sessionId,
       );
       setKvPair(pair);
     } catch (e) {
@@ -41,7 +42,7 @@ export function useKvPair() {
   const createKvPairJSONExport = async (namespaceId: string, key: string): Promise<Uint8Array> => {
     const kvPair = await invokeGetKvPair(
       { account_id: account?.id as string, namespace_id: namespaceId, key },
-      account?.credentials as Credentials,
+      sessionId,
     );
 
     const kvPairJSON = JSON.stringify(kvPair, (_, value) => {
@@ -59,7 +60,7 @@ export function useKvPair() {
   const createKvValueExport = async (namespaceId: string, key: string): Promise<Uint8Array> => {
     const kvPair = await invokeGetKvPair(
       { account_id: account?.id as string, namespace_id: namespaceId, key },
-      account?.credentials as Credentials,
+      sessionId,
     );
 
     return kvPair.value ?? new Uint8Array();
@@ -69,12 +70,8 @@ export function useKvPair() {
     setIsCreating(true);
     setError(null);
 
-    const credentials: UserAuthTokenCredentials = {
-      type: CredentialsType.UserAuthToken,
-      token: (account?.credentials as UserAuthTokenCredentials).token,
-    };
     try {
-      const createdKvPair = await invokeCreateKvPair({ ...input, account_id: account?.id ?? '' }, credentials);
+      const createdKvPair = await invokeCreateKvPair({ ...input, account_id: account?.id ?? '' }, sessionId);
       setKvPair(createdKvPair);
     } catch (e) {
       setError(e as KvError);
@@ -87,13 +84,8 @@ export function useKvPair() {
     setIsWriting(true);
     setError(null);
 
-    const credentials: UserAuthTokenCredentials = {
-      type: CredentialsType.UserAuthToken,
-      token: (account?.credentials as UserAuthTokenCredentials).token,
-    };
-
     try {
-      const updatedKvPair = await invokeWriteKvPair({ ...input, account_id: account?.id ?? '' }, credentials);
+      const updatedKvPair = await invokeWriteKvPair({ ...input, account_id: account?.id ?? '' }, sessionId);
       setKvPair(updatedKvPair);
     } catch (e) {
       setError(e as KvError);
@@ -116,11 +108,11 @@ export function useKvPair() {
   };
 }
 
-export async function invokeGetKvPair(input: KvPairGetInput, credentials: Credentials): Promise<KvPair> {
+export async function invokeGetKvPair(input: KvPairGetInput, sessionId: string): Promise<KvPair> {
   try {
     const kvPair = await invoke<KvPairDTO>('get_kv_pair', {
       input,
-      credentials,
+      sessionId,
     });
 
     return {
@@ -129,18 +121,18 @@ export async function invokeGetKvPair(input: KvPairGetInput, credentials: Creden
       expiration: kvPair.expiration ? new Date(kvPair.expiration * 1000) : undefined,
     };
   } catch (e) {
-    throw convertPlainToKvErrorClass(e as KvError);
+    throw convertPlainToKvErrorClass(e as KvCommandError);
   }
 }
 
 export async function invokeCreateKvPair(
   input: KvPairCreateInput,
-  credentials: UserAuthTokenCredentials,
+  sessionId: string,
 ): Promise<KvPair> {
   try {
     const kvPair = await invoke<KvPairDTO>('create_kv_pair', {
       input,
-      credentials,
+      sessionId,
     });
 
     return {
@@ -149,18 +141,18 @@ export async function invokeCreateKvPair(
       expiration: kvPair.expiration ? new Date(kvPair.expiration * 1000) : undefined,
     };
   } catch (e) {
-    throw convertPlainToKvErrorClass(e as KvError);
+    throw convertPlainToKvErrorClass(e as KvCommandError);
   }
 }
 
 export async function invokeWriteKvPair(
   input: KvPairWriteInput,
-  credentials: UserAuthTokenCredentials,
+  sessionId: string,
 ): Promise<KvPair> {
   try {
     const kvPair = await invoke<KvPairDTO>('write_kv_pair', {
       input,
-      credentials,
+      sessionId,
     });
 
     return {
@@ -169,6 +161,6 @@ export async function invokeWriteKvPair(
       expiration: kvPair.expiration ? new Date(kvPair.expiration * 1000) : undefined,
     };
   } catch (e) {
-    throw convertPlainToKvErrorClass(e as KvError);
+    throw convertPlainToKvErrorClass(e as KvCommandError);
   }
 }
@@ -1,5 +1,5 @@
 import {
-  KvError,
+  KvCommandError,
   KvPair,
   KvPairDTO,
   KvPairsGetInput,
@@ -7,13 +7,13 @@ import {
   KvPairsWriteInputPair,
   KvPairsWriteResult,
 } from '@/features/kv/kv-models.ts';
-import { Credentials } from '@/features/authentication/auth-models.ts';
 import { invoke } from '@tauri-apps/api/core';
 import { convertPlainToKvErrorClass } from '@/features/kv/lib/kv-utils.ts';
 import { useAuth } from '@/features/authentication/hooks/use-auth.ts';
 
 export function useKvPairs() {
   const { account } = useAuth();
+  const sessionId = account?.session_id ?? '';
 
   const createKvPairsJSONExport = async (namespaceId: string, keys: string[]): Promise<Uint8Array> => {
     const kvPairs = await invokeGetKvPairs(
@@ -22,7 +22,7 @@ export function useKvPairs() {
         namespace_id: namespaceId,
         keys,
       },
-      account?.credentials as Credentials,
+      sessionId,
     );
 
     const kvPairsForExport = kvPairs.map((kvPair) => ({
@@ -43,7 +43,7 @@ export function useKvPairs() {
         namespace_id: namespaceId,
         pairs,
       },
-      account?.credentials as Credentials,
+      sessionId,
     );
   };
 
@@ -53,11 +53,11 @@ export function useKvPairs() {
   };
 }
 
-export async function invokeGetKvPairs(input: KvPairsGetInput, credentials: Credentials): Promise<KvPair[]> {
+export async function invokeGetKvPairs(input: KvPairsGetInput, sessionId: string): Promise<KvPair[]> {
   try {
     const kvPairs = await invoke<KvPairDTO[]>('get_kv_pairs', {
       input,
-      credentials,
+      sessionId,
     });
 
     return kvPairs.map((kvPair) => ({
@@ -66,20 +66,20 @@ export async function invokeGetKvPairs(input: KvPairsGetInput, credentials: Cred
       expiration: kvPair.expiration ? new Date(kvPair.expiration * 1000) : undefined,
     }));
   } catch (e) {
-    throw convertPlainToKvErrorClass(e as KvError);
+    throw convertPlainToKvErrorClass(e as KvCommandError);
   }
 }
 
 export async function invokeWriteKvPairs(
   input: KvPairsWriteInput,
-  credentials: Credentials,
+  sessionId: string,
 ): Promise<KvPairsWriteResult> {
   try {
     return await invoke<KvPairsWriteResult>('write_kv_pairs', {
       input,
-      credentials,
+      sessionId,
     });
   } catch (e) {
-    throw convertPlainToKvErrorClass(e as KvError);
+    throw convertPlainToKvErrorClass(e as KvCommandError);
   }
 }
@@ -1,4 +1,5 @@
 import * as zod from 'zod';
+import { CloudflareApiError, Environment } from '@/features/authentication/auth-models.ts';
 
 export interface KvNamespaces {
   items: KvNamespace[];
@@ -28,6 +29,44 @@ export interface KvNamespace {
   title: string;
   beta?: boolean;
   supports_url_encoding?: boolean;
+  environment?: Environment | null;
+}
+
+export interface NamespaceEnvironment {
+  account_id: string;
+  namespace_id: string;
+  environment: Environment | null;
+}
+
+export interface SessionProfile {
+  id: string;
+  name: string;
+  environment: Environment | null;
+}
+
+export interface ProfileNamespace {
+  profile: SessionProfile;
+  account_id: string;
+  namespace: KvNamespace;
+}
+
+export interface ProfileKeyMatch {
+  profile: SessionProfile;
+  account_id: string;
+  namespace: KvNamespace;
+  key: KvKeyDTO;
+}
+
+export interface ProfileFailure {
+  profile: SessionProfile;
+  account_id: string;
+  namespace_id: string | null;
+  error: KvCommandError;
+}
+
+export interface CrossAccountResult<T> {
+  items: T[];
+  failures: ProfileFailure[];
 }
 
 export interface KvNamespaceGetInput {
@@ -43,6 +82,28 @@ export interface KvKeysListInput {
   prefix?: string;
 }
 
+export interface KvKeyTreeInput {
+  account_id: string;
+  namespace_id: string;
+  prefix?: string;
+  delimiter?: string;
+}
+
+export interface KvKeyFolder {
+  name: string;
+  prefix: string;
+  key_count: number;
+}
+
+export interface KvKeyTree {
+  prefix: string;
+  delimiter: string;
+  folders: KvKeyFolder[];
+  keys: KvKeyDTO[];
+  complete: boolean;
+  listed_at: string;
+}
+
 export interface PageInfo {
   count: number;
   page: number;
@@ -53,6 +114,7 @@ export interface PageInfo {
 export interface KvNamespaceCreateInput {
   account_id: string;
   title: string;
+  acknowledge_production?: boolean;
 }
 
 export interface KvNamespaceUpdateInput extends KvNamespaceCreateInput {
@@ -62,6 +124,88 @@ export interface KvNamespaceUpdateInput extends KvNamespaceCreateInput {
 export interface KvNamespaceDeleteInput {
   account_id: string;
   namespace_id: string;
+  confirmation?: string;
+  acknowledge_production?: boolean;
+}
+
+export interface KvNamespacesDeleteInput {
+  account_id: string;
+  namespace_ids: string[];
+  backup?: boolean;
+  confirmations?: Record<string, string>;
+  acknowledge_production?: boolean;
+}
+
+export interface KvNamespacesRenameInput {
+  account_id: string;
+  pattern: string;
+  replacement: string;
+  namespace_ids?: string[];
+  dry_run?: boolean;
+  acknowledge_production?: boolean;
+}
+
+export interface KvNamespaceCloneInput {
+  account_id: string;
+  namespace_id: string;
+  title: string;
+  acknowledge_production?: boolean;
+}
+
+export type KvNamespaceOperationStatus = 'Planned' | 'Succeeded' | 'Failed';
+
+export interface KvNamespaceDeleteOutcome {
+  namespace_id: string;
+  backup_path: string | null;
+  status: KvNamespaceOperationStatus;
+  error: KvCommandError | null;
+}
+
+export interface KvNamespaceRenameOutcome {
+  namespace_id: string;
+  title: string;
+  new_title: string;
+  status: KvNamespaceOperationStatus;
+  error: KvCommandError | null;
+}
+
+export interface KvNamespaceCopyResult {
+  copied_key_count: number;
+  skipped_keys: string[];
+  unsuccessful_keys: string[];
+}
+
+export interface KvNamespaceCloneResult {
+  namespace: KvNamespace;
+  copy: KvNamespaceCopyResult;
+}
+
+export interface KvNamespaceStatisticsInput {
+  account_id: string;
+  namespace_id: string;
+  delimiter?: string;
+  refresh?: boolean;
+}
+
+export interface KvPrefixStatistics {
+  prefix: string | null;
+  key_count: number;
+}
+
+export interface KvNamespaceStatistics {
+  account_id: string;
+  namespace_id: string;
+  delimiter: string;
+  computed_at: string;
+  key_count: number;
+  prefixes: KvPrefixStatistics[];
+  keys_with_metadata: number;
+  keys_with_expiration: number;
+  metadata_share: number;
+  expiration_share: number;
+  value_size_bytes: number;
+  sampled_key_count: number;
+  value_size_estimated: boolean;
 }
 
 export interface KvPair {
@@ -107,6 +251,7 @@ export interface KvPairCreateInput {
   expiration?: Date;
   expiration_ttl?: number;
   metadata?: KvMetadata;
+  acknowledge_production?: boolean;
 }
 
 export interface KvPairWriteInput {
@@ -117,15 +262,19 @@ export interface KvPairWriteInput {
   expiration?: number;
   expiration_ttl?: number;
   metadata?: KvMetadata;
+  acknowledge_production?: boolean;
 }
 
 export interface KvPairsWriteInput {
   account_id: string;
   namespace_id: string;
   pairs: KvPairsWriteInputPair[];
+  acknowledge_production?: boolean;
 }
 
-export type KvPairsWriteInputPair = Omit<KvPairWriteInput, 'account_id' | 'namespace_id'> & { value: number[] };
+export type KvPairsWriteInputPair = Omit<KvPairWriteInput, 'account_id' | 'namespace_id' | 'acknowledge_production'> & {
+  value: number[];
+};
 
 export interface KvPairsWriteResult {
   successful_key_count: number;
@@ -136,6 +285,8 @@ export interface KvPairsDeleteInput {
   account_id: string;
   namespace_id: string;
   keys: string[];
+  confirmation?: string;
+  acknowledge_production?: boolean;
 }
 
 export interface KvPairsDeleteResult {
@@ -173,16 +324,35 @@ export interface KvTableKey extends KvKey {
 
 export type KvErrorKind =
   | 'Authentication'
+  | 'SessionExpired'
+  | 'ReadOnly'
+  | 'RateLimited'
   | 'Unknown'
   | 'NamespaceAlreadyExists'
   | 'KeyAlreadyExists'
   | 'InvalidMetadata'
-  | 'InvalidExpiration';
+  | 'InvalidExpiration'
+  | 'ConflictingChange'
+  | 'VersionNotFound'
+  | 'HistoryUnavailable'
+  | 'ConfirmationRequired'
+  | 'ProductionAcknowledgementRequired'
+  | 'InvalidPattern'
+  | 'BackupFailed';
+
+export interface KvCommandError {
+  message: string;
+  kind: KvErrorKind;
+  api_error: CloudflareApiError | null;
+  retry_after_ms: number | null;
+}
 
 export class KvError extends Error {
   constructor(
     message: string,
     public kind: KvErrorKind,
+    public apiError: CloudflareApiError | null = null,
+    public retryAfterMs: number | null = null,
   ) {
     super(message);
     this.name = 'KvError';
@@ -1,4 +1,4 @@
-import { KvError, KvMetadata } from '@/features/kv/kv-models.ts';
+import { KvCommandError, KvError, KvMetadata } from '@/features/kv/kv-models.ts';
 
 export function stringifyMetadataJSON(value: KvMetadata): string {
   if (value === null) {
@@ -48,12 +48,12 @@ export function validateExpirationTTL(value: string): boolean {
   return expirationTTL === 0 || expirationTTL >= 60;
 }
 
-export function convertPlainToKvErrorClass(kvError: KvError | string): KvError {
+export function convertPlainToKvErrorClass(kvError: KvCommandError | string): KvError {
   console.error(kvError);
 
   if (typeof kvError === 'string') {
     return new KvError(kvError, 'Unknown');
   }
 
-  return new KvError(kvError.message, kvError.kind);
+  return new KvError(kvError.message, kvError.kind, kvError.api_error, kvError.retry_after_ms);
 }
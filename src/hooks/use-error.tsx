@@ -6,7 +6,7 @@ export function useError() {
 
   const handleError = (error: Error, title = 'An unknown error occurred') => {
     console.error(error);
-    if ('kind' in error && error.kind === 'Authentication') {
+    if ('kind' in error && (error.kind === 'Authentication' || error.kind === 'SessionExpired')) {
       resetCredentials();
       return;
     }